@@ -21,7 +21,7 @@ impl BlockHeader {
         serialized.extend_from_slice(&self.nonce.to_le_bytes());
     }
 
-    pub fn deserialize(serialized: &[u8]) -> BlockHeader {
+    pub fn deserialize(_serialized: &[u8]) -> BlockHeader {
         todo!()
     }
 
@@ -155,8 +155,8 @@ impl<T: Transaction> Block<T> {
 
         serialized
     }
-    
-    pub fn deserialized(serialized: &[u8]) -> Self {
+
+    pub fn deserialized(_serialized: &[u8]) -> Self {
         todo!()
     }
 
@@ -280,6 +280,12 @@ mod tests {
         fn hash(&self) -> [u8; 32] {
             d_sha256(&self.id)
         }
+
+        fn serialize(&self, serialized: &mut Vec<u8>) {
+            serialized.extend_from_slice(&self.id);
+        }
+
+        fn deserialize(_serialized: &[u8]) {}
     }
 
     fn create_test_block(txs: Vec<MockTx>) -> Block<MockTx> {
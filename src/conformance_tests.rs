@@ -0,0 +1,849 @@
+//! Conformance harness for Bitcoin Core's script and transaction test vectors.
+//!
+//! `test_data/` holds Bitcoin Core's `src/test/data/script_tests.json`,
+//! `tx_valid.json` and `tx_invalid.json`, unmodified (see
+//! `test_data/README.md` for where they come from). Every vector is
+//! classified as passed, failed or unsupported (it needs an opcode, flag, hash
+//! type or transaction feature this crate does not implement yet), and a
+//! per-vector report is printed. Run with
+//! `cargo test conformance -- --nocapture` to see it.
+//!
+//! Only failures make the tests fail: an unsupported vector is a gap in
+//! coverage, a failed vector is a disagreement with consensus.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::script::{OpCode, Script, ScriptError, ScriptFlags};
+use crate::test_utils::parse_hex;
+use crate::transaction::{Transaction, Tx, TxIn, TxOut, UtxoSet};
+
+const SCRIPT_TESTS: &str = include_str!("../test_data/script_tests.json");
+const TX_VALID: &str = include_str!("../test_data/tx_valid.json");
+const TX_INVALID: &str = include_str!("../test_data/tx_invalid.json");
+
+// --- Bitcoin Core's script assembly format ---
+
+// Every opcode name Bitcoin Core's `ParseScript` understands. Names may be
+// written with or without the `OP_` prefix.
+const OPCODE_NAMES: &[(&str, u8)] = &[
+    ("PUSHDATA1", 0x4c),
+    ("PUSHDATA2", 0x4d),
+    ("PUSHDATA4", 0x4e),
+    ("1NEGATE", 0x4f),
+    ("RESERVED", 0x50),
+    ("NOP", 0x61),
+    ("VER", 0x62),
+    ("IF", 0x63),
+    ("NOTIF", 0x64),
+    ("VERIF", 0x65),
+    ("VERNOTIF", 0x66),
+    ("ELSE", 0x67),
+    ("ENDIF", 0x68),
+    ("VERIFY", 0x69),
+    ("RETURN", 0x6a),
+    ("TOALTSTACK", 0x6b),
+    ("FROMALTSTACK", 0x6c),
+    ("2DROP", 0x6d),
+    ("2DUP", 0x6e),
+    ("3DUP", 0x6f),
+    ("2OVER", 0x70),
+    ("2ROT", 0x71),
+    ("2SWAP", 0x72),
+    ("IFDUP", 0x73),
+    ("DEPTH", 0x74),
+    ("DROP", 0x75),
+    ("DUP", 0x76),
+    ("NIP", 0x77),
+    ("OVER", 0x78),
+    ("PICK", 0x79),
+    ("ROLL", 0x7a),
+    ("ROT", 0x7b),
+    ("SWAP", 0x7c),
+    ("TUCK", 0x7d),
+    ("CAT", 0x7e),
+    ("SUBSTR", 0x7f),
+    ("LEFT", 0x80),
+    ("RIGHT", 0x81),
+    ("SIZE", 0x82),
+    ("INVERT", 0x83),
+    ("AND", 0x84),
+    ("OR", 0x85),
+    ("XOR", 0x86),
+    ("EQUAL", 0x87),
+    ("EQUALVERIFY", 0x88),
+    ("RESERVED1", 0x89),
+    ("RESERVED2", 0x8a),
+    ("1ADD", 0x8b),
+    ("1SUB", 0x8c),
+    ("2MUL", 0x8d),
+    ("2DIV", 0x8e),
+    ("NEGATE", 0x8f),
+    ("ABS", 0x90),
+    ("NOT", 0x91),
+    ("0NOTEQUAL", 0x92),
+    ("ADD", 0x93),
+    ("SUB", 0x94),
+    ("MUL", 0x95),
+    ("DIV", 0x96),
+    ("MOD", 0x97),
+    ("LSHIFT", 0x98),
+    ("RSHIFT", 0x99),
+    ("BOOLAND", 0x9a),
+    ("BOOLOR", 0x9b),
+    ("NUMEQUAL", 0x9c),
+    ("NUMEQUALVERIFY", 0x9d),
+    ("NUMNOTEQUAL", 0x9e),
+    ("LESSTHAN", 0x9f),
+    ("GREATERTHAN", 0xa0),
+    ("LESSTHANOREQUAL", 0xa1),
+    ("GREATERTHANOREQUAL", 0xa2),
+    ("MIN", 0xa3),
+    ("MAX", 0xa4),
+    ("WITHIN", 0xa5),
+    ("RIPEMD160", 0xa6),
+    ("SHA1", 0xa7),
+    ("SHA256", 0xa8),
+    ("HASH160", 0xa9),
+    ("HASH256", 0xaa),
+    ("CODESEPARATOR", 0xab),
+    ("CHECKSIG", 0xac),
+    ("CHECKSIGVERIFY", 0xad),
+    ("CHECKMULTISIG", 0xae),
+    ("CHECKMULTISIGVERIFY", 0xaf),
+    ("NOP1", 0xb0),
+    ("CHECKLOCKTIMEVERIFY", 0xb1),
+    ("NOP2", 0xb1),
+    ("CHECKSEQUENCEVERIFY", 0xb2),
+    ("NOP3", 0xb2),
+    ("NOP4", 0xb3),
+    ("NOP5", 0xb4),
+    ("NOP6", 0xb5),
+    ("NOP7", 0xb6),
+    ("NOP8", 0xb7),
+    ("NOP9", 0xb8),
+    ("NOP10", 0xb9),
+    ("CHECKSIGADD", 0xba),
+    ("INVALIDOPCODE", 0xff),
+];
+
+/// Appends `data` with the shortest push opcode, like `CScript << vector`.
+fn push_data(script: &mut Vec<u8>, data: &[u8]) {
+    match data.len() {
+        len if len < 0x4c => script.push(len as u8),
+        len if len <= 0xff => script.extend([0x4c, len as u8]),
+        len if len <= 0xffff => {
+            script.push(0x4d);
+            script.extend((len as u16).to_le_bytes());
+        }
+        len => {
+            script.push(0x4e);
+            script.extend((len as u32).to_le_bytes());
+        }
+    }
+    script.extend_from_slice(data);
+}
+
+/// Serializes a number the way `CScriptNum` does: little-endian magnitude with
+/// the sign carried in the top bit of the last byte.
+fn script_num(n: i64) -> Vec<u8> {
+    let negative = n < 0;
+    let mut magnitude = n.unsigned_abs();
+    let mut result = Vec::new();
+
+    while magnitude > 0 {
+        result.push((magnitude & 0xff) as u8);
+        magnitude >>= 8;
+    }
+
+    if let Some(last) = result.last_mut() {
+        if *last & 0x80 != 0 {
+            result.push(if negative { 0x80 } else { 0x00 });
+        } else if negative {
+            *last |= 0x80;
+        }
+    }
+
+    result
+}
+
+/// Converts Bitcoin Core's script assembly (`"DUP HASH160 0x14 0x89ab.."`)
+/// into raw script bytes. Returns `None` for tokens it does not understand.
+fn parse_asm(asm: &str) -> Option<Vec<u8>> {
+    let mut script = Vec::new();
+
+    for token in asm.split_whitespace() {
+        let digits = token.strip_prefix('-').unwrap_or(token);
+        let is_number = !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit());
+
+        if is_number {
+            let n: i64 = token.parse().ok()?;
+            match n {
+                0 => script.push(0x00),
+                -1 => script.push(0x4f),
+                1..=16 => script.push(0x50 + n as u8),
+                _ => push_data(&mut script, &script_num(n)),
+            }
+        } else if let Some(hex) = token.strip_prefix("0x") {
+            script.extend(parse_hex(hex)?);
+        } else if token.len() >= 2 && token.starts_with('\'') && token.ends_with('\'') {
+            push_data(&mut script, &token.as_bytes()[1..token.len() - 1]);
+        } else {
+            let name = token.strip_prefix("OP_").unwrap_or(token);
+            let (_, byte) = OPCODE_NAMES.iter().find(|(op, _)| *op == name)?;
+            script.push(*byte);
+        }
+    }
+
+    Some(script)
+}
+
+/// Returns the first opcode in `script` that the interpreter does not
+/// implement, if any. Pushes running past the end are left for `Script::parse`
+/// to reject.
+fn unsupported_opcode(script: &[u8]) -> Option<u8> {
+    let mut index = 0;
+
+    while index < script.len() {
+        let byte = script[index];
+        match byte {
+            0x01..=0x4b => index += byte as usize + 1,
+            // Opcodes above OP_NOP10 are invalid in legacy scripts, which the
+            // interpreter already reports as `BAD_OPCODE`.
+            0xba..=0xff => index += 1,
+            _ if OpCode::from_u8(byte).is_some() => index += 1,
+            _ => return Some(byte),
+        }
+    }
+
+    None
+}
+
+fn is_p2sh(script_pub_key: &[u8]) -> bool {
+    script_pub_key.len() == 23
+        && script_pub_key[0] == 0xa9
+        && script_pub_key[1] == 0x14
+        && script_pub_key[22] == 0x87
+}
+
+/// A version opcode (`OP_0` to `OP_16`) followed by one direct push of 2 to
+/// 40 bytes, as in BIP141.
+fn is_witness_program(script_pub_key: &[u8]) -> bool {
+    (4..=42).contains(&script_pub_key.len())
+        && matches!(script_pub_key[0], 0x00 | 0x51..=0x60)
+        && script_pub_key[1] as usize == script_pub_key.len() - 2
+}
+
+/// Reports a scriptPubKey whose evaluation the applied flags would change in
+/// a way the interpreter does not implement: under P2SH a `HASH160 <h>
+/// EQUAL` output also runs the redeem script, and under WITNESS a witness
+/// program is spent with the witness.
+fn unsupported_shape(script_pub_key: &[u8], applied: &[&str]) -> Option<String> {
+    if applied.contains(&"P2SH") && is_p2sh(script_pub_key) {
+        return Some("P2SH".to_string());
+    }
+    if applied.contains(&"WITNESS") && is_witness_program(script_pub_key) {
+        return Some("witness program".to_string());
+    }
+
+    None
+}
+
+/// Returns the hash type of the first signature in `script_sig` whose result
+/// the interpreter cannot reproduce: it only computes SIGHASH_ALL digests, so
+/// any other hash type is unsupported, except an undefined one under
+/// STRICTENC, which fails before any digest is computed.
+fn unsupported_hash_type(script_sig: &[u8], strictenc: bool) -> Option<u8> {
+    let mut index = 0;
+
+    while index < script_sig.len() {
+        let byte = script_sig[index];
+        let Some(push) = (0x01..=0x4b)
+            .contains(&byte)
+            .then(|| script_sig.get(index + 1..index + 1 + byte as usize))
+            .flatten()
+        else {
+            index += 1;
+            continue;
+        };
+        index += 1 + push.len();
+
+        // DER signatures start with a SEQUENCE tag.
+        if push.len() < 9 || push[0] != 0x30 {
+            continue;
+        }
+
+        let hash_type = push[push.len() - 1];
+        let defined = matches!(hash_type & !0x80, 0x01..=0x03);
+        if hash_type != 0x01 && (defined || !strictenc) {
+            return Some(hash_type);
+        }
+    }
+
+    None
+}
+
+// --- Flags ---
+
+// Every flag name Bitcoin Core's test framework understands, which is the
+// full set `tx_valid.json` excludes flags from.
+const ALL_FLAGS: &[&str] = &[
+    "P2SH",
+    "STRICTENC",
+    "DERSIG",
+    "LOW_S",
+    "SIGPUSHONLY",
+    "MINIMALDATA",
+    "NULLDUMMY",
+    "DISCOURAGE_UPGRADABLE_NOPS",
+    "CLEANSTACK",
+    "MINIMALIF",
+    "NULLFAIL",
+    "CHECKLOCKTIMEVERIFY",
+    "CHECKSEQUENCEVERIFY",
+    "WITNESS",
+    "DISCOURAGE_UPGRADABLE_WITNESS_PROGRAM",
+    "WITNESS_PUBKEYTYPE",
+    "CONST_SCRIPTCODE",
+    "TAPROOT",
+    "DISCOURAGE_UPGRADABLE_PUBKEYTYPE",
+    "DISCOURAGE_OP_SUCCESS",
+    "DISCOURAGE_UPGRADABLE_TAPROOT_VERSION",
+];
+
+// Flags that only change the behaviour of opcodes or transaction features this
+// crate does not implement. A vector that uses none of those features behaves
+// the same with or without them, so they can be ignored. P2SH and WITNESS
+// are in this list because `unsupported_shape` reports every scriptPubKey
+// they would change before the flags are parsed.
+const VACUOUS_FLAGS: &[&str] = &[
+    "NONE",
+    "P2SH",
+    "NULLDUMMY",
+    "CHECKLOCKTIMEVERIFY",
+    "CHECKSEQUENCEVERIFY",
+    "DISCOURAGE_UPGRADABLE_NOPS",
+    "MINIMALIF",
+    "WITNESS",
+    "DISCOURAGE_UPGRADABLE_WITNESS_PROGRAM",
+    "WITNESS_PUBKEYTYPE",
+    "TAPROOT",
+    "DISCOURAGE_UPGRADABLE_TAPROOT_VERSION",
+    "DISCOURAGE_OP_SUCCESS",
+    "DISCOURAGE_UPGRADABLE_PUBKEYTYPE",
+];
+
+fn known_flag(name: &str) -> Option<ScriptFlags> {
+    match name {
+        "STRICTENC" => Some(ScriptFlags::STRICTENC),
+        "DERSIG" => Some(ScriptFlags::DERSIG),
+        "LOW_S" => Some(ScriptFlags::LOW_S),
+        _ => None,
+    }
+}
+
+fn flag_names(flags: &str) -> impl Iterator<Item = &str> {
+    flags
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// Converts a comma separated flag list into `ScriptFlags`, or reports the
+/// first flag the interpreter cannot honour.
+fn parse_flags(flags: &str) -> Result<ScriptFlags, String> {
+    let mut result = ScriptFlags::NONE;
+
+    for name in flag_names(flags) {
+        if let Some(flag) = known_flag(name) {
+            result = result | flag;
+        } else if !VACUOUS_FLAGS.contains(&name) {
+            return Err(format!("flag {}", name));
+        }
+    }
+
+    Ok(result)
+}
+
+/// Bitcoin Core's name for each error, as used in `script_tests.json`.
+fn core_error_name(error: ScriptError) -> &'static str {
+    match error {
+        ScriptError::EvalFalse => "EVAL_FALSE",
+        ScriptError::OpReturn => "OP_RETURN",
+        ScriptError::ScriptSize => "SCRIPT_SIZE",
+        ScriptError::PushSize => "PUSH_SIZE",
+        ScriptError::OpCount => "OP_COUNT",
+        ScriptError::StackSize => "STACK_SIZE",
+        ScriptError::EqualVerify => "EQUALVERIFY",
+        ScriptError::BadOpcode => "BAD_OPCODE",
+        ScriptError::InvalidStackOperation => "INVALID_STACK_OPERATION",
+        ScriptError::SigHashType => "SIG_HASHTYPE",
+        ScriptError::SigDer => "SIG_DER",
+        ScriptError::SigHighS => "SIG_HIGH_S",
+        ScriptError::PubKeyType => "PUBKEYTYPE",
+    }
+}
+
+// --- Reporting ---
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Passed,
+    Failed(String),
+    Unsupported(String),
+}
+
+#[derive(Default)]
+struct Report {
+    passed: usize,
+    failed: Vec<String>,
+    unsupported: HashMap<String, usize>,
+}
+
+impl Report {
+    fn record(&mut self, file: &str, index: usize, description: &str, outcome: Outcome) {
+        match outcome {
+            Outcome::Passed => {
+                self.passed += 1;
+                println!("{} #{}: passed   {}", file, index, description);
+            }
+            Outcome::Failed(reason) => {
+                println!("{} #{}: FAILED   {} ({})", file, index, description, reason);
+                self.failed
+                    .push(format!("{} #{}: {} ({})", file, index, description, reason));
+            }
+            Outcome::Unsupported(reason) => {
+                println!("{} #{}: skipped  {} ({})", file, index, description, reason);
+                *self.unsupported.entry(reason).or_default() += 1;
+            }
+        }
+    }
+
+    fn summarize(&self, file: &str) {
+        let unsupported: usize = self.unsupported.values().sum();
+        let total = self.passed + self.failed.len() + unsupported;
+        println!(
+            "{}: {} vectors, {} passed, {} failed, {} unsupported",
+            file,
+            total,
+            self.passed,
+            self.failed.len(),
+            unsupported
+        );
+
+        let mut reasons: Vec<_> = self.unsupported.iter().collect();
+        reasons.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (reason, count) in reasons {
+            println!("    unsupported {:>5}  {}", count, reason);
+        }
+
+        assert!(
+            self.failed.is_empty(),
+            "{} vectors disagree with Bitcoin Core:\n{}",
+            self.failed.len(),
+            self.failed.join("\n")
+        );
+    }
+}
+
+// --- script_tests.json ---
+
+/// Builds the transaction pair `script_tests.json` is defined against: a
+/// coinbase-like crediting transaction paying to `script_pub_key`, and a
+/// transaction spending its only output with `script_sig`.
+fn build_spending_tx(script_sig: &[u8], script_pub_key: &[u8]) -> Tx {
+    let crediting = Tx {
+        version: 1,
+        tx_ins: vec![TxIn {
+            prev_tx_hash: [0; 32],
+            prev_index: 0xffffffff,
+            script_sig: vec![0x00, 0x00],
+            sequence: 0xffffffff,
+            witness: vec![],
+        }],
+        tx_outs: vec![TxOut {
+            amount: 0,
+            script_pub_key: script_pub_key.to_vec(),
+        }],
+        locktime: 0,
+    };
+
+    Tx {
+        version: 1,
+        tx_ins: vec![TxIn {
+            prev_tx_hash: crediting.hash(),
+            prev_index: 0,
+            script_sig: script_sig.to_vec(),
+            sequence: 0xffffffff,
+            witness: vec![],
+        }],
+        tx_outs: vec![TxOut {
+            amount: 0,
+            script_pub_key: vec![],
+        }],
+        locktime: 0,
+    }
+}
+
+fn run_script_vector(vector: &[Value]) -> Outcome {
+    if vector[0].as_array().is_some() {
+        return Outcome::Unsupported("witness".to_string());
+    }
+
+    let fields: Option<Vec<&str>> = vector.iter().take(4).map(Value::as_str).collect();
+    let Some([sig_asm, pub_key_asm, flags, expected]) = fields.as_deref() else {
+        return Outcome::Failed("malformed vector".to_string());
+    };
+
+    let (Some(script_sig), Some(script_pub_key)) = (parse_asm(sig_asm), parse_asm(pub_key_asm))
+    else {
+        return Outcome::Unsupported("asm syntax".to_string());
+    };
+
+    if let Some(op) = unsupported_opcode(&script_sig).or(unsupported_opcode(&script_pub_key)) {
+        return Outcome::Unsupported(format!("opcode 0x{:02x}", op));
+    }
+
+    let applied: Vec<&str> = flag_names(flags).collect();
+    if let Some(reason) = unsupported_shape(&script_pub_key, &applied) {
+        return Outcome::Unsupported(reason);
+    }
+    if let Some(hash_type) = unsupported_hash_type(&script_sig, applied.contains(&"STRICTENC")) {
+        return Outcome::Unsupported(format!("hash type 0x{:02x}", hash_type));
+    }
+
+    let flags = match parse_flags(flags) {
+        Ok(flags) => flags,
+        Err(reason) => return Outcome::Unsupported(reason),
+    };
+
+    let result = Script::parse(&script_sig)
+        .and_then(|sig| Ok(sig.combine(&Script::parse(&script_pub_key)?)))
+        .and_then(|script| {
+            let z = build_spending_tx(&script_sig, &script_pub_key).sig_hash(0, &script_pub_key);
+            script.evaluate_with_flags(&z, flags)
+        });
+
+    let actual = match result {
+        Ok(()) => "OK",
+        Err(error) => core_error_name(error),
+    };
+
+    if actual == *expected {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(format!("expected {}, got {}", expected, actual))
+    }
+}
+
+// --- tx_valid.json / tx_invalid.json ---
+
+fn read_compact_size(bytes: &[u8], cursor: &mut usize) -> Option<u64> {
+    let first = *bytes.get(*cursor)?;
+    let len = match first {
+        0xfd => 2,
+        0xfe => 4,
+        0xff => 8,
+        _ => 0,
+    };
+    let value = if len == 0 {
+        first as u64
+    } else {
+        let mut buf = [0u8; 8];
+        buf[..len].copy_from_slice(bytes.get(*cursor + 1..*cursor + 1 + len)?);
+        u64::from_le_bytes(buf)
+    };
+    *cursor += 1 + len;
+
+    Some(value)
+}
+
+fn read_bytes<'a>(bytes: &'a [u8], cursor: &mut usize, len: usize) -> Option<&'a [u8]> {
+    let slice = bytes.get(*cursor..cursor.checked_add(len)?)?;
+    *cursor += len;
+
+    Some(slice)
+}
+
+fn read_u32(bytes: &[u8], cursor: &mut usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        read_bytes(bytes, cursor, 4)?.try_into().ok()?,
+    ))
+}
+
+/// Parses a legacy (non-witness) serialized transaction.
+fn parse_tx(bytes: &[u8]) -> Result<Tx, String> {
+    let mut cursor = 0;
+    let malformed = || "malformed transaction".to_string();
+
+    let version = read_u32(bytes, &mut cursor).ok_or_else(malformed)?;
+    if bytes.get(cursor) == Some(&0x00) && bytes.get(cursor + 1) == Some(&0x01) {
+        return Err("segwit serialization".to_string());
+    }
+
+    let mut tx_ins = Vec::new();
+    for _ in 0..read_compact_size(bytes, &mut cursor).ok_or_else(malformed)? {
+        let prev_tx_hash = read_bytes(bytes, &mut cursor, 32)
+            .and_then(|hash| hash.try_into().ok())
+            .ok_or_else(malformed)?;
+        let prev_index = read_u32(bytes, &mut cursor).ok_or_else(malformed)?;
+        let script_len = read_compact_size(bytes, &mut cursor).ok_or_else(malformed)?;
+        let script_sig = read_bytes(bytes, &mut cursor, script_len as usize)
+            .ok_or_else(malformed)?
+            .to_vec();
+        let sequence = read_u32(bytes, &mut cursor).ok_or_else(malformed)?;
+        tx_ins.push(TxIn {
+            prev_tx_hash,
+            prev_index,
+            script_sig,
+            sequence,
+            witness: vec![],
+        });
+    }
+
+    let mut tx_outs = Vec::new();
+    for _ in 0..read_compact_size(bytes, &mut cursor).ok_or_else(malformed)? {
+        let amount = read_bytes(bytes, &mut cursor, 8)
+            .and_then(|amount| Some(u64::from_le_bytes(amount.try_into().ok()?)))
+            .ok_or_else(malformed)?;
+        let script_len = read_compact_size(bytes, &mut cursor).ok_or_else(malformed)?;
+        let script_pub_key = read_bytes(bytes, &mut cursor, script_len as usize)
+            .ok_or_else(malformed)?
+            .to_vec();
+        tx_outs.push(TxOut {
+            amount,
+            script_pub_key,
+        });
+    }
+
+    let locktime = read_u32(bytes, &mut cursor).ok_or_else(malformed)?;
+    if cursor != bytes.len() {
+        return Err(malformed());
+    }
+
+    Ok(Tx {
+        version,
+        tx_ins,
+        tx_outs,
+        locktime,
+    })
+}
+
+/// Runs one `tx_valid.json`/`tx_invalid.json` vector through `Tx::verify`.
+///
+/// `tx_valid.json` lists the flags to *exclude* from the full set, while
+/// `tx_invalid.json` lists the flags to apply.
+fn run_tx_vector(vector: &[Value], expect_valid: bool) -> Outcome {
+    let (Some(prevouts), Some(tx_hex), Some(flags)) = (
+        vector[0].as_array(),
+        vector.get(1).and_then(Value::as_str),
+        vector.get(2).and_then(Value::as_str),
+    ) else {
+        return Outcome::Failed("malformed vector".to_string());
+    };
+
+    let applied_flags: Vec<&str> = if expect_valid {
+        let excluded: Vec<&str> = flag_names(flags).collect();
+        ALL_FLAGS
+            .iter()
+            .copied()
+            .filter(|name| !excluded.contains(name))
+            .collect()
+    } else {
+        flag_names(flags).collect()
+    };
+
+    let flags = if expect_valid {
+        // A valid transaction stays valid under fewer flags, so the ones the
+        // interpreter cannot honour are simply left out.
+        applied_flags
+            .iter()
+            .filter_map(|name| known_flag(name))
+            .fold(ScriptFlags::NONE, |flags, flag| flags | flag)
+    } else {
+        if applied_flags.contains(&"BADTX") {
+            return Outcome::Unsupported("BADTX".to_string());
+        }
+        match parse_flags(flags) {
+            Ok(flags) => flags,
+            Err(reason) => return Outcome::Unsupported(reason),
+        }
+    };
+
+    let Some(tx_bytes) = parse_hex(tx_hex) else {
+        return Outcome::Failed("invalid transaction hex".to_string());
+    };
+    let tx = match parse_tx(&tx_bytes) {
+        Ok(tx) => tx,
+        Err(reason) => return Outcome::Unsupported(reason),
+    };
+
+    // Legacy vectors carry no amounts. The first prevout is funded with the
+    // transaction's total output so that `Tx::verify`'s fee check passes.
+    let total_output: u64 = tx.tx_outs.iter().map(|tx_out| tx_out.amount).sum();
+    let mut utxos = UtxoSet::new();
+    for (position, prevout) in prevouts.iter().enumerate() {
+        let (Some(hash_hex), Some(index), Some(script_asm)) = (
+            prevout.get(0).and_then(Value::as_str),
+            prevout.get(1).and_then(Value::as_i64),
+            prevout.get(2).and_then(Value::as_str),
+        ) else {
+            return Outcome::Failed("malformed prevout".to_string());
+        };
+
+        // Hashes are written in the usual reversed display order.
+        let Some(mut prev_tx_hash) =
+            parse_hex(hash_hex).and_then(|hash| <[u8; 32]>::try_from(hash).ok())
+        else {
+            return Outcome::Failed("malformed prevout hash".to_string());
+        };
+        prev_tx_hash.reverse();
+
+        let Some(script_pub_key) = parse_asm(script_asm) else {
+            return Outcome::Unsupported("asm syntax".to_string());
+        };
+        if let Some(reason) = unsupported_shape(&script_pub_key, &applied_flags) {
+            return Outcome::Unsupported(reason);
+        }
+        if let Some(op) = unsupported_opcode(&script_pub_key) {
+            return Outcome::Unsupported(format!("opcode 0x{:02x}", op));
+        }
+
+        let amount = match prevout.get(3).and_then(Value::as_u64) {
+            Some(amount) => amount,
+            None if position == 0 => total_output,
+            None => 0,
+        };
+
+        utxos.insert(
+            (prev_tx_hash, index as u32),
+            TxOut {
+                amount,
+                script_pub_key,
+            },
+        );
+    }
+
+    for tx_in in &tx.tx_ins {
+        if let Some(op) = unsupported_opcode(&tx_in.script_sig) {
+            return Outcome::Unsupported(format!("opcode 0x{:02x}", op));
+        }
+        let strictenc = applied_flags.contains(&"STRICTENC");
+        if let Some(hash_type) = unsupported_hash_type(&tx_in.script_sig, strictenc) {
+            return Outcome::Unsupported(format!("hash type 0x{:02x}", hash_type));
+        }
+    }
+
+    match (tx.verify_with_flags(&utxos, flags), expect_valid) {
+        (true, true) | (false, false) => Outcome::Passed,
+        (true, false) => Outcome::Failed("accepted an invalid transaction".to_string()),
+        (false, true) => Outcome::Failed("rejected a valid transaction".to_string()),
+    }
+}
+
+/// Describes a vector by its trailing comment, falling back to its scripts.
+fn describe(vector: &[Value]) -> String {
+    vector
+        .iter()
+        .skip(4)
+        .filter_map(Value::as_str)
+        .next()
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            vector
+                .iter()
+                .filter_map(Value::as_str)
+                .take(2)
+                .collect::<Vec<_>>()
+                .join(" | ")
+        })
+}
+
+/// Vectors are arrays; single-string arrays are comments.
+fn vectors(file: &str) -> Vec<Vec<Value>> {
+    let entries: Vec<Value> = serde_json::from_str(file).expect("Vector file must be a JSON array");
+
+    entries
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Value::Array(items) if items.len() <= 1 && items.iter().all(Value::is_string) => None,
+            Value::Array(items) => Some(items),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_script_tests_json() {
+    let mut report = Report::default();
+
+    for (index, vector) in vectors(SCRIPT_TESTS).iter().enumerate() {
+        report.record(
+            "script_tests.json",
+            index,
+            &describe(vector),
+            run_script_vector(vector),
+        );
+    }
+
+    report.summarize("script_tests.json");
+    assert!(report.passed > 0);
+}
+
+#[test]
+fn test_tx_valid_json() {
+    let mut report = Report::default();
+
+    for (index, vector) in vectors(TX_VALID).iter().enumerate() {
+        report.record(
+            "tx_valid.json",
+            index,
+            tx_hex_prefix(vector),
+            run_tx_vector(vector, true),
+        );
+    }
+
+    report.summarize("tx_valid.json");
+    assert!(report.passed > 0);
+}
+
+#[test]
+fn test_tx_invalid_json() {
+    let mut report = Report::default();
+
+    for (index, vector) in vectors(TX_INVALID).iter().enumerate() {
+        report.record(
+            "tx_invalid.json",
+            index,
+            tx_hex_prefix(vector),
+            run_tx_vector(vector, false),
+        );
+    }
+
+    report.summarize("tx_invalid.json");
+    assert!(report.passed > 0);
+}
+
+/// Transaction vectors have no comment field; identify them by the start of
+/// their serialized transaction.
+fn tx_hex_prefix(vector: &[Value]) -> &str {
+    let hex = vector.get(1).and_then(Value::as_str).unwrap_or("");
+    &hex[..hex.len().min(24)]
+}
+
+#[test]
+fn test_parse_asm() {
+    assert_eq!(parse_asm("").unwrap(), Vec::<u8>::new());
+    assert_eq!(
+        parse_asm("0 -1 1 16").unwrap(),
+        vec![0x00, 0x4f, 0x51, 0x60]
+    );
+    assert_eq!(parse_asm("17").unwrap(), vec![0x01, 0x11]);
+    assert_eq!(parse_asm("-17").unwrap(), vec![0x01, 0x91]);
+    assert_eq!(parse_asm("128").unwrap(), vec![0x02, 0x80, 0x00]);
+    assert_eq!(parse_asm("DUP OP_HASH160").unwrap(), vec![0x76, 0xa9]);
+    assert_eq!(parse_asm("0x02 0x417a").unwrap(), vec![0x02, 0x41, 0x7a]);
+    assert_eq!(parse_asm("'Az'").unwrap(), vec![0x02, 0x41, 0x7a]);
+    assert!(parse_asm("NOT_AN_OPCODE").is_none());
+}
@@ -0,0 +1,500 @@
+use std::fmt::Debug;
+use std::ops::{Add, Mul, Neg};
+
+use num_bigint::BigUint;
+
+use crate::elliptic_curve::{Point, PointError};
+use crate::finite_field::{Field, TypedFieldElement, prime_field};
+
+mod ecdsa;
+
+pub use ecdsa::EcdsaPrivateKey;
+
+/// The parameters of a short Weierstrass curve `y^2 = x^3 + a x + b` over a
+/// prime field.
+///
+/// A curve is a zero-sized marker type, like a `Field`, so `CurvePoint<C>`
+/// carries no parameters at runtime and points on different curves are
+/// different types. Declare new curves with `curve_params!`.
+pub trait CurveParams: Debug + Clone + Copy + PartialEq + Eq + 'static {
+    type Field: Field;
+
+    fn a() -> &'static TypedFieldElement<Self::Field>;
+    fn b() -> &'static TypedFieldElement<Self::Field>;
+    /// The base point of the subgroup used for keys and signatures.
+    fn generator() -> &'static CurvePoint<Self>;
+    /// The order of the generator, a prime.
+    fn order() -> &'static BigUint;
+    /// The number of points on the curve divided by the order of the
+    /// generator.
+    fn cofactor() -> u32;
+}
+
+/// Declares a marker type implementing `CurveParams`.
+///
+/// The field must already be declared with `prime_field!`. `a`, `b`, the
+/// generator coordinates and the order are `BigUint` expressions, evaluated
+/// once on first use; invalid parameters panic at that point.
+macro_rules! curve_params {
+    (
+        $(#[$meta:meta])*
+        $vis:vis $name:ident {
+            field: $field:ty,
+            a: $a:expr,
+            b: $b:expr,
+            generator: ($gx:expr, $gy:expr $(,)?),
+            order: $order:expr,
+            cofactor: $cofactor:expr $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        $vis struct $name;
+
+        impl $crate::curve::CurveParams for $name {
+            type Field = $field;
+
+            fn a() -> &'static $crate::finite_field::TypedFieldElement<$field> {
+                static A: ::std::sync::LazyLock<$crate::finite_field::TypedFieldElement<$field>> =
+                    ::std::sync::LazyLock::new(|| {
+                        $crate::finite_field::TypedFieldElement::new($a).unwrap()
+                    });
+
+                &A
+            }
+
+            fn b() -> &'static $crate::finite_field::TypedFieldElement<$field> {
+                static B: ::std::sync::LazyLock<$crate::finite_field::TypedFieldElement<$field>> =
+                    ::std::sync::LazyLock::new(|| {
+                        $crate::finite_field::TypedFieldElement::new($b).unwrap()
+                    });
+
+                &B
+            }
+
+            fn generator() -> &'static $crate::curve::CurvePoint<Self> {
+                static G: ::std::sync::LazyLock<$crate::curve::CurvePoint<$name>> =
+                    ::std::sync::LazyLock::new(|| {
+                        $crate::curve::CurvePoint::new(
+                            $crate::finite_field::TypedFieldElement::new($gx).unwrap(),
+                            $crate::finite_field::TypedFieldElement::new($gy).unwrap(),
+                        )
+                        .unwrap()
+                    });
+
+                &G
+            }
+
+            fn order() -> &'static ::num_bigint::BigUint {
+                static ORDER: ::std::sync::LazyLock<::num_bigint::BigUint> =
+                    ::std::sync::LazyLock::new(|| $order);
+
+                &ORDER
+            }
+
+            fn cofactor() -> u32 {
+                $cofactor
+            }
+        }
+    };
+}
+
+/// Parses a hexadecimal constant; only for literals known to be valid.
+pub(crate) fn hex(digits: &str) -> BigUint {
+    BigUint::parse_bytes(digits.as_bytes(), 16).unwrap()
+}
+
+prime_field!(pub Secp256k1Field, hex("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f"));
+
+curve_params!(
+    /// secp256k1, as a parameter set. `S256Point` is the optimized
+    /// implementation used by the rest of the crate; this one shares the
+    /// generic code with the other curves and is mainly useful to check it.
+    pub Secp256k1 {
+        field: Secp256k1Field,
+        a: BigUint::from(0u32),
+        b: BigUint::from(7u32),
+        generator: (
+            hex("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"),
+            hex("483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"),
+        ),
+        order: hex("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141"),
+        cofactor: 1,
+    }
+);
+
+prime_field!(pub F223, BigUint::from(223u32));
+
+curve_params!(
+    /// The book's toy curve `y^2 = x^3 + 7` over `F_223`. Its group has 252
+    /// points; the generator `(15, 86)` spans a subgroup of prime order 7.
+    pub Toy223 {
+        field: F223,
+        a: BigUint::from(0u32),
+        b: BigUint::from(7u32),
+        generator: (BigUint::from(15u32), BigUint::from(86u32)),
+        order: BigUint::from(7u32),
+        cofactor: 36,
+    }
+);
+
+prime_field!(pub P256Field, hex("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff"));
+
+curve_params!(
+    /// NIST P-256, also known as secp256r1 and prime256v1, from FIPS 186. It
+    /// is the curve behind WebAuthn passkeys. Unlike secp256k1, a = -3.
+    pub P256 {
+        field: P256Field,
+        a: hex("ffffffff00000001000000000000000000000000fffffffffffffffffffffffc"),
+        b: hex("5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b"),
+        generator: (
+            hex("6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296"),
+            hex("4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5"),
+        ),
+        order: hex("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551"),
+        cofactor: 1,
+    }
+);
+
+// As in `elliptic_curve`, a point is either finite or the point at infinity;
+// see `note/04-making-states-unrepresentable.md`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CurvePointKind<C: CurveParams> {
+    Coordinates(TypedFieldElement<C::Field>, TypedFieldElement<C::Field>),
+    Infinity,
+}
+
+/// A point on the curve `C`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurvePoint<C: CurveParams> {
+    kind: CurvePointKind<C>,
+}
+
+type Element<C> = TypedFieldElement<<C as CurveParams>::Field>;
+
+impl<C: CurveParams> CurvePoint<C> {
+    pub fn new(x: Element<C>, y: Element<C>) -> Result<Self, PointError> {
+        if y.pow(2u32) != Self::curve_rhs(&x) {
+            return Err(PointError::NotOnCurve);
+        }
+
+        Ok(CurvePoint {
+            kind: CurvePointKind::Coordinates(x, y),
+        })
+    }
+
+    pub fn infinity() -> Self {
+        CurvePoint {
+            kind: CurvePointKind::Infinity,
+        }
+    }
+
+    pub fn generator() -> Self {
+        C::generator().clone()
+    }
+
+    /// `x^3 + a x + b`, which must equal `y^2` on the curve.
+    fn curve_rhs(x: &Element<C>) -> Element<C> {
+        x.pow(3u32) + C::a() * x + C::b()
+    }
+
+    pub fn is_at_infinity(&self) -> bool {
+        matches!(self.kind, CurvePointKind::Infinity)
+    }
+
+    pub fn x(&self) -> Option<&Element<C>> {
+        match &self.kind {
+            CurvePointKind::Coordinates(x, _) => Some(x),
+            CurvePointKind::Infinity => None,
+        }
+    }
+
+    pub fn y(&self) -> Option<&Element<C>> {
+        match &self.kind {
+            CurvePointKind::Coordinates(_, y) => Some(y),
+            CurvePointKind::Infinity => None,
+        }
+    }
+
+    /// The same point on the dynamically typed curve.
+    pub fn point(&self) -> Point {
+        let (a, b) = (C::a().to_dynamic(), C::b().to_dynamic());
+
+        match &self.kind {
+            CurvePointKind::Coordinates(x, y) => {
+                Point::new(x.to_dynamic(), y.to_dynamic(), a, b).unwrap()
+            }
+            CurvePointKind::Infinity => Point::infinity(a, b).unwrap(),
+        }
+    }
+
+    /// Decompresses a point from its x coordinate and the parity of its y
+    /// coordinate.
+    pub fn lift_x(x: Element<C>, y_is_odd: bool) -> Result<Self, PointError> {
+        let y = Self::curve_rhs(&x).sqrt().ok_or(PointError::NotOnCurve)?;
+
+        if y.is_zero() && y_is_odd {
+            // 0 is its own negation, so there is no odd root.
+            return Err(PointError::NotOnCurve);
+        }
+        if y.num().bit(0) == y_is_odd {
+            Self::new(x, y)
+        } else {
+            Self::new(x, -y)
+        }
+    }
+
+    /// The length in bytes of one encoded coordinate.
+    fn coordinate_len() -> usize {
+        C::Field::prime().bits().div_ceil(8) as usize
+    }
+
+    fn coordinate_bytes(element: &Element<C>) -> Vec<u8> {
+        let bytes = element.num().to_bytes_be();
+        let mut padded = vec![0u8; Self::coordinate_len() - bytes.len()];
+        padded.extend(bytes);
+
+        padded
+    }
+
+    /// SEC 1 encoding, compressed unless `compressed` is `Some(false)`, with
+    /// coordinates as wide as the field's prime. The point at infinity is
+    /// encoded as a single zero byte.
+    pub fn sec(&self, compressed: Option<bool>) -> Vec<u8> {
+        let CurvePointKind::Coordinates(x, y) = &self.kind else {
+            return vec![0x00];
+        };
+
+        let mut serialized = Vec::with_capacity(1 + 2 * Self::coordinate_len());
+        if compressed.unwrap_or(true) {
+            serialized.push(if y.num().bit(0) { 0x03 } else { 0x02 });
+            serialized.extend(Self::coordinate_bytes(x));
+        } else {
+            serialized.push(0x04);
+            serialized.extend(Self::coordinate_bytes(x));
+            serialized.extend(Self::coordinate_bytes(y));
+        }
+
+        serialized
+    }
+
+    pub fn parse(sec_bin: &[u8]) -> Result<Self, PointError> {
+        let len = Self::coordinate_len();
+        let coordinate = |bytes: &[u8]| {
+            TypedFieldElement::new(BigUint::from_bytes_be(bytes))
+                .map_err(|_| PointError::CannotParse)
+        };
+
+        match sec_bin.first() {
+            Some(0x04) if sec_bin.len() == 1 + 2 * len => Self::new(
+                coordinate(&sec_bin[1..=len])?,
+                coordinate(&sec_bin[1 + len..])?,
+            ),
+            Some(&marker @ (0x02 | 0x03)) if sec_bin.len() == 1 + len => {
+                Self::lift_x(coordinate(&sec_bin[1..])?, marker == 0x03)
+            }
+            _ => Err(PointError::CannotParse),
+        }
+    }
+}
+
+impl<C: CurveParams> Add for &CurvePoint<C> {
+    type Output = CurvePoint<C>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let (x1, y1, x2, y2) = match (&self.kind, &rhs.kind) {
+            (CurvePointKind::Infinity, _) => return rhs.clone(),
+            (_, CurvePointKind::Infinity) => return self.clone(),
+            (CurvePointKind::Coordinates(x1, y1), CurvePointKind::Coordinates(x2, y2)) => {
+                (x1, y1, x2, y2)
+            }
+        };
+
+        // P + (-P), including the tangent at a point with y = 0.
+        if x1 == x2 && (y1 + y2).is_zero() {
+            return CurvePoint::infinity();
+        }
+
+        let slope = if x1 == x2 {
+            // The tangent's slope (3 x^2 + a) / 2y depends on a, so this is
+            // where a curve with a != 0 differs from secp256k1.
+            let x1_squared = x1.pow(2u32);
+            (&x1_squared + &x1_squared + &x1_squared + C::a()) / (y1 + y1)
+        } else {
+            (y2 - y1) / (x2 - x1)
+        };
+
+        let x3 = slope.pow(2u32) - x1 - x2;
+        let y3 = slope * (x1 - &x3) - y1;
+
+        CurvePoint {
+            kind: CurvePointKind::Coordinates(x3, y3),
+        }
+    }
+}
+
+impl<C: CurveParams> Add for CurvePoint<C> {
+    type Output = CurvePoint<C>;
+
+    fn add(self, rhs: CurvePoint<C>) -> Self::Output {
+        &self + &rhs
+    }
+}
+
+impl<C: CurveParams> Neg for &CurvePoint<C> {
+    type Output = CurvePoint<C>;
+
+    fn neg(self) -> Self::Output {
+        match &self.kind {
+            CurvePointKind::Coordinates(x, y) => CurvePoint {
+                kind: CurvePointKind::Coordinates(x.clone(), -y),
+            },
+            CurvePointKind::Infinity => self.clone(),
+        }
+    }
+}
+
+// Plain double-and-add: variable time in the scalar.
+impl<C: CurveParams> Mul<&BigUint> for &CurvePoint<C> {
+    type Output = CurvePoint<C>;
+
+    fn mul(self, rhs: &BigUint) -> Self::Output {
+        let mut result = CurvePoint::infinity();
+
+        for bit in (0..rhs.bits()).rev() {
+            result = &result + &result;
+            if rhs.bit(bit) {
+                result = &result + self;
+            }
+        }
+
+        result
+    }
+}
+
+impl<C: CurveParams> Mul<BigUint> for &CurvePoint<C> {
+    type Output = CurvePoint<C>;
+
+    fn mul(self, rhs: BigUint) -> Self::Output {
+        self * &rhs
+    }
+}
+
+#[cfg(test)]
+mod curve_tests {
+    use super::*;
+    use crate::secp256k1::{G, S256Point};
+
+    type Toy = CurvePoint<Toy223>;
+
+    fn toy(x: u32, y: u32) -> Toy {
+        Toy::new(
+            TypedFieldElement::new(x).unwrap(),
+            TypedFieldElement::new(y).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_new_rejects_points_off_the_curve() {
+        assert!(matches!(
+            Toy::new(
+                TypedFieldElement::new(42u32).unwrap(),
+                TypedFieldElement::new(99u32).unwrap()
+            ),
+            Err(PointError::NotOnCurve)
+        ));
+    }
+
+    #[test]
+    fn test_toy_curve_arithmetic() {
+        // The additions from the book's exercises on y^2 = x^3 + 7 over F_223.
+        assert_eq!(toy(170, 142) + toy(60, 139), toy(220, 181));
+        assert_eq!(toy(47, 71) + toy(17, 56), toy(215, 68));
+        assert_eq!(toy(143, 98) + toy(76, 66), toy(47, 71));
+
+        let g = Toy::generator();
+        assert_eq!(&g * BigUint::from(2u32), toy(139, 86));
+        assert!((&g * Toy223::order()).is_at_infinity());
+        assert!((&g + &-&g).is_at_infinity());
+        assert_eq!(&Toy::infinity() + &g, g);
+    }
+
+    #[test]
+    fn test_matches_dynamic_point() {
+        let g = Toy::generator();
+        let dynamic = g.point();
+
+        for k in 0..30u32 {
+            let k = BigUint::from(k);
+            assert_eq!((&g * &k).point(), &dynamic * &k);
+        }
+    }
+
+    #[test]
+    fn test_secp256k1_matches_s256_point() {
+        let g = CurvePoint::<Secp256k1>::generator();
+        assert_eq!(g.point(), G.point());
+
+        let k = hex("3a7f5c0d2b19e8f46a1c9b07d5e3f2a1");
+        let expected: S256Point = &*G * &k;
+        let actual = &g * &k;
+
+        assert_eq!(actual.sec(Some(true)), expected.sec(Some(true)));
+        assert_eq!(actual.sec(Some(false)), expected.sec(Some(false)));
+    }
+
+    #[test]
+    fn test_p256_arithmetic() {
+        // Multiples of the generator, as computed by OpenSSL. Doubling uses
+        // the tangent slope (3 x^2 + a) / 2y with a = -3.
+        let g = CurvePoint::<P256>::generator();
+        let expected = [
+            (
+                "7cf27b188d034f7e8a52380304b51ac3c08969e277f21b35a60b48fc47669978",
+                "07775510db8ed040293d9ac69f7430dbba7dade63ce982299e04b79d227873d1",
+            ),
+            (
+                "5ecbe4d1a6330a44c8f7ef951d4bf165e6c6b721efada985fb41661bc6e7fd6c",
+                "8734640c4998ff7e374b06ce1a64a2ecd82ab036384fb83d9a79b127a27d5032",
+            ),
+        ];
+
+        for (k, (x, y)) in (2u32..).zip(expected) {
+            let point = &g * BigUint::from(k);
+            assert_eq!(point.x().unwrap().num(), &hex(x));
+            assert_eq!(point.y().unwrap().num(), &hex(y));
+        }
+
+        assert_eq!(&g + &g, &g * BigUint::from(2u32));
+        assert!((&g * P256::order()).is_at_infinity());
+    }
+
+    #[test]
+    fn test_sec_round_trip() {
+        let g = CurvePoint::<Secp256k1>::generator();
+        for compressed in [true, false] {
+            let encoded = g.sec(Some(compressed));
+            assert_eq!(CurvePoint::<Secp256k1>::parse(&encoded).unwrap(), g);
+        }
+
+        // The toy curve's coordinates fit in one byte.
+        for k in 1..7u32 {
+            let point = &Toy::generator() * BigUint::from(k);
+            for compressed in [true, false] {
+                let encoded = point.sec(Some(compressed));
+                assert_eq!(encoded.len(), if compressed { 2 } else { 3 });
+                assert_eq!(Toy::parse(&encoded).unwrap(), point);
+            }
+        }
+
+        assert_eq!(Toy::infinity().sec(None), vec![0x00]);
+        assert!(Toy::parse(&[]).is_err());
+        assert!(Toy::parse(&[0x02]).is_err());
+        assert!(Toy::parse(&[0x05, 47]).is_err());
+
+        // (6, 0) has order 2, and no point has x = 6 and an odd y.
+        assert_eq!(Toy::parse(&[0x02, 6]).unwrap(), toy(6, 0));
+        assert!(Toy::parse(&[0x03, 6]).is_err());
+    }
+}
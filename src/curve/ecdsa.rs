@@ -0,0 +1,404 @@
+use hmac::{Hmac, Mac};
+use num_bigint::BigUint;
+use sha2::Sha256;
+
+use super::{CurveParams, CurvePoint};
+use crate::secp256k1::Signature;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// An ECDSA private key on the curve `C`.
+///
+/// Signing uses the generic double-and-add multiplication, which runs in
+/// variable time; use `secp256k1::PrivateKey` for Bitcoin keys.
+#[derive(Debug, Clone)]
+pub struct EcdsaPrivateKey<C: CurveParams> {
+    secret: BigUint,
+    point: CurvePoint<C>,
+}
+
+impl<C: CurveParams> EcdsaPrivateKey<C> {
+    /// `secret` must be in `[1, n)`, where n is the order of the generator.
+    pub fn new(secret: BigUint) -> Self {
+        let point = &CurvePoint::<C>::generator() * &secret;
+
+        EcdsaPrivateKey { secret, point }
+    }
+
+    pub fn point(&self) -> &CurvePoint<C> {
+        &self.point
+    }
+
+    /// Signs the hash of a message, with the nonce derived from the key and
+    /// the hash as in RFC 6979 (using HMAC-SHA256).
+    ///
+    /// Unlike `secp256k1::PrivateKey::sign`, `s` is not normalized to the
+    /// lower half of the order: outside Bitcoin, both values are valid and
+    /// RFC 6979 specifies the one computed.
+    pub fn sign(&self, message_hash: &[u8]) -> Signature {
+        let mut nonces = NonceGenerator::<C>::new(&self.secret, message_hash);
+
+        loop {
+            // RFC 6979 moves on to the next nonce if this one fails.
+            if let Some(signature) = self.sign_with_nonce(message_hash, &nonces.next_nonce()) {
+                return signature;
+            }
+        }
+    }
+
+    /// Signs with the nonce `k` in `[1, n)`, or returns `None` if `r` or `s`
+    /// is zero, which would make the signature unverifiable.
+    fn sign_with_nonce(&self, message_hash: &[u8], k: &BigUint) -> Option<Signature> {
+        let n = C::order();
+        let z = bits_to_int::<C>(message_hash);
+
+        let r_point = &CurvePoint::<C>::generator() * k;
+        let r = r_point.x().unwrap().num() % n; // k is in [1, n)
+
+        let k_inv = k.modpow(&(n - 2u32), n);
+        let s = ((&z + &r * &self.secret) * k_inv) % n;
+
+        (r != BigUint::from(0u32) && s != BigUint::from(0u32)).then_some(Signature { r, s })
+    }
+}
+
+impl<C: CurveParams> CurvePoint<C> {
+    /// Verifies an ECDSA signature over the hash of a message.
+    ///
+    /// The point is trusted to be a valid public key; on curves with a
+    /// cofactor, that includes being in the generator's subgroup.
+    pub fn verify(&self, message_hash: &[u8], sig: &Signature) -> bool {
+        let n = C::order();
+        let zero = BigUint::from(0u32);
+        if self.is_at_infinity() || sig.r == zero || &sig.r >= n || sig.s == zero || &sig.s >= n {
+            return false;
+        }
+
+        let z = bits_to_int::<C>(message_hash);
+        let s_inv = sig.s.modpow(&(n - 2u32), n);
+        let u = (z * &s_inv) % n;
+        let v = (&sig.r * s_inv) % n;
+        // u.G + v.P = k.G
+        let total = &(&CurvePoint::<C>::generator() * &u) + &(self * &v);
+
+        total.x().is_some_and(|x| x.num() % n == sig.r)
+    }
+}
+
+/// The leftmost bits of a hash as an integer, as many as the order has
+/// ("bits2int" in RFC 6979; FIPS 186 truncates hashes the same way).
+fn bits_to_int<C: CurveParams>(bytes: &[u8]) -> BigUint {
+    let value = BigUint::from_bytes_be(bytes);
+    let bits = 8 * bytes.len() as u64;
+    let order_bits = C::order().bits();
+
+    if bits > order_bits {
+        value >> (bits - order_bits)
+    } else {
+        value
+    }
+}
+
+/// Big-endian encoding padded to the byte length of the order
+/// ("int2octets").
+fn int_to_bytes<C: CurveParams>(value: &BigUint) -> Vec<u8> {
+    let len = C::order().bits().div_ceil(8) as usize;
+    let bytes = value.to_bytes_be();
+
+    let mut padded = vec![0u8; len - bytes.len()];
+    padded.extend(bytes);
+
+    padded
+}
+
+/// The RFC 6979 (section 3.2) HMAC-DRBG, seeded with the private key and
+/// the message hash. Successive calls return successive candidates, so a
+/// signature that has to be retried gets a fresh nonce deterministically.
+struct NonceGenerator<C: CurveParams> {
+    k: Vec<u8>,
+    v: Vec<u8>,
+    curve: std::marker::PhantomData<C>,
+}
+
+impl<C: CurveParams> NonceGenerator<C> {
+    fn new(secret: &BigUint, message_hash: &[u8]) -> Self {
+        let secret_bytes = int_to_bytes::<C>(secret);
+        let hash_bytes = int_to_bytes::<C>(&(bits_to_int::<C>(message_hash) % C::order()));
+
+        let mut generator = NonceGenerator {
+            k: vec![0x00; 32],
+            v: vec![0x01; 32],
+            curve: std::marker::PhantomData,
+        };
+
+        for separator in [0x00, 0x01] {
+            generator.k = generator.hmac(&[&generator.v, &[separator], &secret_bytes, &hash_bytes]);
+            generator.v = generator.hmac(&[&generator.v]);
+        }
+
+        generator
+    }
+
+    fn hmac(&self, parts: &[&[u8]]) -> Vec<u8> {
+        let mut mac = HmacSha256::new_from_slice(&self.k).unwrap();
+        for part in parts {
+            mac.update(part);
+        }
+
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    fn next_nonce(&mut self) -> BigUint {
+        let len = C::order().bits().div_ceil(8) as usize;
+
+        loop {
+            let mut t = Vec::with_capacity(len + 32);
+            while t.len() < len {
+                self.v = self.hmac(&[&self.v]);
+                t.extend_from_slice(&self.v);
+            }
+
+            let candidate = bits_to_int::<C>(&t[..len]);
+
+            // Prepare the state for the next candidate whether or not this
+            // one is used.
+            self.k = self.hmac(&[&self.v, &[0x00]]);
+            self.v = self.hmac(&[&self.v]);
+
+            if candidate >= BigUint::from(1u32) && &candidate < C::order() {
+                return candidate;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod ecdsa_tests {
+    use super::*;
+    use crate::curve::{P256, Toy223, hex};
+    use crate::test_utils::hex_to_bytes;
+    use sha2::{Digest, Sha256};
+    use std::collections::HashMap;
+
+    /// NIST CAVP 186-4 SigGen vectors for P-256/SHA-256 and P-384/SHA-384.
+    const SIGN_VECTORS: &str = include_str!("../../test_data/ecdsa_sign_fixed_tests.txt");
+    /// NIST CAVP 186-4 SigVer vectors for every curve and hash, followed by
+    /// edge cases added by BoringSSL.
+    const VERIFY_VECTORS: &str = include_str!("../../test_data/ecdsa_verify_tests.txt");
+
+    /// The `Key = Value` blocks of a test vector file for `curve`. Blocks
+    /// are separated by blank lines and `#` starts a comment.
+    fn vectors<'a>(data: &'a str, curve: &str) -> Vec<HashMap<&'a str, &'a str>> {
+        data.split("\n\n")
+            .map(|block| {
+                block
+                    .lines()
+                    .filter(|line| !line.starts_with('#'))
+                    .filter_map(|line| line.split_once(" = "))
+                    .collect::<HashMap<_, _>>()
+            })
+            .filter(|vector| vector.get("Curve") == Some(&curve))
+            .collect()
+    }
+
+    /// A raw `r || s` signature, each half as wide as the order.
+    fn raw_signature(bytes: &[u8]) -> Signature {
+        let (r, s) = bytes.split_at(bytes.len() / 2);
+
+        Signature {
+            r: BigUint::from_bytes_be(r),
+            s: BigUint::from_bytes_be(s),
+        }
+    }
+
+    #[test]
+    fn test_p256_rfc6979_vectors() {
+        // RFC 6979, appendix A.2.5: ECDSA on P-256 with SHA-256.
+        let key = EcdsaPrivateKey::<P256>::new(hex(
+            "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+        ));
+        assert_eq!(
+            key.point().x().unwrap().num(),
+            &hex("60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6")
+        );
+        assert_eq!(
+            key.point().y().unwrap().num(),
+            &hex("7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299")
+        );
+
+        let vectors = [
+            (
+                "sample",
+                "a6e3c57dd01abe90086538398355dd4c3b17aa873382b0f24d6129493d8aad60",
+                "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716",
+                "f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8",
+            ),
+            (
+                "test",
+                "d16b6ae827f17175e040871a1c7ec3500192c4c92677336ec2537acaee0008e0",
+                "f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367",
+                "019f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083",
+            ),
+        ];
+
+        for (message, k, r, s) in vectors {
+            let hash = Sha256::digest(message.as_bytes());
+
+            let mut nonces = NonceGenerator::<P256>::new(&key.secret, &hash);
+            assert_eq!(nonces.next_nonce(), hex(k), "{message}: k");
+
+            let sig = key.sign(&hash);
+            assert_eq!(sig.r, hex(r), "{message}: r");
+            assert_eq!(sig.s, hex(s), "{message}: s");
+            assert!(key.point().verify(&hash, &sig));
+        }
+    }
+
+    #[test]
+    fn test_p256_cavp_sign() {
+        let vectors = vectors(SIGN_VECTORS, "P-256");
+        assert_eq!(vectors.len(), 15);
+
+        for (i, vector) in vectors.iter().enumerate() {
+            assert_eq!(vector["Digest"], "SHA256");
+            let key = EcdsaPrivateKey::<P256>::new(hex(vector["d"]));
+            let hash = Sha256::digest(hex_to_bytes(vector["Msg"]));
+
+            // The key pair: Q = d * G.
+            assert_eq!(
+                key.point().sec(Some(false)),
+                hex_to_bytes(vector["Q"]),
+                "vector {i}: Q"
+            );
+
+            let sig = key.sign_with_nonce(&hash, &hex(vector["k"])).unwrap();
+            let expected = raw_signature(&hex_to_bytes(vector["Sig"]));
+            assert_eq!(sig.r, expected.r, "vector {i}: r");
+            assert_eq!(sig.s, expected.s, "vector {i}: s");
+            assert!(key.point().verify(&hash, &sig), "vector {i}: verify");
+        }
+    }
+
+    #[test]
+    fn test_p256_cavp_verify() {
+        let vectors = vectors(VERIFY_VECTORS, "P-256");
+        assert_eq!(vectors.len(), 85);
+        assert_eq!(
+            vectors.iter().filter(|v| v.contains_key("Invalid")).count(),
+            64
+        );
+
+        for (i, vector) in vectors.iter().enumerate() {
+            // The digests are from a mix of SHA-1 and SHA-2 hashes; those
+            // longer than the order are truncated as FIPS 186 specifies.
+            let mut sec = vec![0x04];
+            sec.extend(hex_to_bytes(vector["X"]));
+            sec.extend(hex_to_bytes(vector["Y"]));
+            let point = CurvePoint::<P256>::parse(&sec).unwrap();
+            let sig = Signature {
+                r: hex(vector["R"]),
+                s: hex(vector["S"]),
+            };
+
+            assert_eq!(
+                point.verify(&hex_to_bytes(vector["Digest"]), &sig),
+                !vector.contains_key("Invalid"),
+                "vector {i}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_p256_verify() {
+        // Signed with random nonces by OpenSSL, through Python's
+        // `cryptography` package: (SEC public key, SHA-256 hash, r, s).
+        let vectors = [
+            (
+                "035e247613ba8ed01ca47ffe036046edfa596517db67d04e7889e2bd3b39787dda",
+                "f98c258727b8d0bd8a4ebf3b19dcde9b56ca2a10735421d0f6f4547c9737850d",
+                "6970afdf595833016e6687b494fe07ba1409e68f39484453d4c153568811af12",
+                "5a1a6320c4d109d252db54b16e1c4fde5e4d5a6f5b709d76b4aad4ccf0de584c",
+            ),
+            (
+                "023a3d0dd6791c0a73adb9041556e1bf53b14f9ba34e583875f90747cd2d23fe0a",
+                "b58abb08a38bb850cb72303725d811807c720d6b0fb6a20a5368a32cd27809e5",
+                "782b7b24dbba18cd9746121581314947920582f6f45fb422f0fa1e06ad2a3240",
+                "e4f78ce874fcbf6b8a769cdab951a75e5fc755b705e00eba201096546749de70",
+            ),
+        ];
+
+        let n = P256::order();
+        for (public_key, hash, r, s) in vectors {
+            let point = CurvePoint::<P256>::parse(&hex_to_bytes(public_key)).unwrap();
+            let hash = hex_to_bytes(hash);
+            let sig = Signature {
+                r: hex(r),
+                s: hex(s),
+            };
+            assert!(point.verify(&hash, &sig));
+
+            // s and n - s are both valid outside Bitcoin.
+            let negated = Signature {
+                r: sig.r.clone(),
+                s: n - &sig.s,
+            };
+            assert!(point.verify(&hash, &negated));
+
+            let mut tampered_hash = hash.clone();
+            tampered_hash[31] ^= 1;
+            assert!(!point.verify(&tampered_hash, &sig));
+
+            let tampered_r = Signature {
+                r: &sig.r + 1u32,
+                s: sig.s.clone(),
+            };
+            assert!(!point.verify(&hash, &tampered_r));
+
+            let out_of_range = Signature {
+                r: sig.r.clone(),
+                s: n.clone(),
+            };
+            assert!(!point.verify(&hash, &out_of_range));
+        }
+    }
+
+    #[test]
+    fn test_p256_deterministic_signatures_match_openssl() {
+        // OpenSSL's RFC 6979 signatures for the first key above over
+        // SHA-256("WebAuthn assertion").
+        let key = EcdsaPrivateKey::<P256>::new(hex(
+            "1f2e3d4c5b6a79880123456789abcdeffedcba98765432100f1e2d3c4b5a6978",
+        ));
+        assert_eq!(
+            key.point().sec(None),
+            hex_to_bytes("035e247613ba8ed01ca47ffe036046edfa596517db67d04e7889e2bd3b39787dda")
+        );
+
+        let sig = key.sign(&Sha256::digest(b"WebAuthn assertion"));
+        assert_eq!(
+            sig.r,
+            hex("55c70e0d847d49d1ff8089910e794483d8700ed327b6d010fa904bcb35b946fe")
+        );
+        assert_eq!(
+            sig.s,
+            hex("e1d43d7e0550e545154481172a3f75150be82a58086861fcc515f47061eaaca7")
+        );
+    }
+
+    #[test]
+    fn test_toy_curve_sign_and_verify() {
+        // The toy group has order 7, so every key and nonce is tiny, and a
+        // 256-bit hash is truncated to its top three bits.
+        for secret in 1..7u32 {
+            let key = EcdsaPrivateKey::<Toy223>::new(BigUint::from(secret));
+
+            for message in [b"a".as_slice(), b"b", b"toy"] {
+                let hash = Sha256::digest(message);
+                let sig = key.sign(&hash);
+
+                assert!(sig.r < BigUint::from(7u32) && sig.s < BigUint::from(7u32));
+                assert!(key.point().verify(&hash, &sig));
+            }
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use num_bigint::BigUint;
+
+use crate::elliptic_curve::Point;
+
+// Solvers for the elliptic curve discrete logarithm problem: given `base` and
+// `target`, find `k` with `k * base = target`. Both take about the square root
+// of the width of the search range in point additions, so they recover keys
+// drawn from a range of up to around 2^50 values, not real 256-bit keys.
+// That makes them useful for teaching, for recovering weak keys, and for
+// checking that a key is not small.
+
+/// Baby-step giant-step: finds `k` in `range` with `k * base = target`, or
+/// `None` if there is no such `k`.
+///
+/// With `m` the square root of the width of the range, the baby steps store
+/// `target + j * base` for `j < m` in a hash table, and the giant steps walk
+/// `(start + i * m) * base` until one of them is in the table. This always
+/// finds a solution in about `2m` additions, but holds `m` entries in memory:
+/// around 100 MB for a range of 2^40.
+pub fn baby_step_giant_step(
+    base: &Point,
+    target: &Point,
+    range: &Range<BigUint>,
+) -> Option<BigUint> {
+    if range.start >= range.end {
+        return None;
+    }
+
+    let width = &range.end - &range.start;
+    let m = width.sqrt() + 1u32;
+    let m_steps = to_u64(&m);
+
+    let mut baby_steps = HashMap::new();
+    let mut baby_step = target.clone();
+    for j in 0..m_steps {
+        baby_steps.entry(key(&baby_step)).or_insert(j);
+        baby_step = &baby_step + base;
+    }
+
+    let giant_step = base * &m;
+    let mut giant = base * &range.start;
+    // start + i * m - j covers the whole range once i * m >= width + m.
+    for i in 0..=m_steps + 1 {
+        if let Some(&j) = baby_steps.get(&key(&giant)) {
+            let offset = BigUint::from(i) * &m;
+            if offset >= BigUint::from(j) {
+                let k = &range.start + offset - j;
+                if k < range.end {
+                    return Some(k);
+                }
+            }
+        }
+
+        giant = &giant + &giant_step;
+    }
+
+    None
+}
+
+/// The number of attempts, each with a different walk, before
+/// `pollard_kangaroo` gives up.
+const KANGAROO_ATTEMPTS: u64 = 4;
+
+/// Pollard's kangaroo (or lambda) method, which searches a bounded range:
+/// finds `k` in `range` with `k * base = target`, or `None` if none was
+/// found.
+///
+/// Two pseudo-random walks jump forward by `2^i * base`, with `i` chosen by
+/// the x coordinate of the current point: a "tame" one starting from the
+/// middle of the range, where its discrete log is known, and a "wild" one
+/// starting from `target`. Once either lands on a point the other has
+/// visited, they follow the same path from there. To notice without storing
+/// every point, only the distinguished points (those with x coordinates
+/// ending in enough zero bits) are recorded; the first one both walks reach
+/// gives `k`.
+///
+/// This takes about `2m` additions for `m` the square root of the width of
+/// the range, like `baby_step_giant_step`, but needs very little memory.
+/// Being probabilistic, it can miss a solution that exists; a missed walk is
+/// retried a few times with different jumps before giving up.
+///
+/// The walks must not wrap around the group, so the range has to be much
+/// smaller than the order of `base`, as for a 2^40 range on secp256k1. For
+/// a small group, search the whole group with `baby_step_giant_step`.
+pub fn pollard_kangaroo(base: &Point, target: &Point, range: &Range<BigUint>) -> Option<BigUint> {
+    if range.start >= range.end {
+        return None;
+    }
+
+    let width = &range.end - &range.start;
+    let sqrt_width = width.sqrt();
+
+    // Jumps of 1, 2, 4, ... with a mean close to sqrt(width): the walk behind
+    // has up to half the width to catch up, then lands on the other's trail
+    // after about one mean jump's worth of steps.
+    let mut jump_count = 1;
+    while BigUint::from((1u64 << jump_count) - 1) < &sqrt_width * jump_count && jump_count < 63 {
+        jump_count += 1;
+    }
+    let mut jumps = vec![base.clone()];
+    for _ in 1..jump_count {
+        let last = jumps.last().unwrap();
+        jumps.push(last + last);
+    }
+
+    // A walk passes a distinguished point about every 2^distinguished_bits
+    // steps, a small fraction of the expected length of the walk.
+    let distinguished_bits = sqrt_width.bits().saturating_sub(5);
+    let max_steps = to_u64(&sqrt_width)
+        .saturating_mul(8)
+        .saturating_add(16 << distinguished_bits.min(32));
+
+    let tame_start = &range.start + &width / 2u32;
+
+    for attempt in 0..KANGAROO_ATTEMPTS {
+        let salt = attempt.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        let mut walks = [
+            Walk {
+                point: base * &tame_start,
+                distance: tame_start.clone(),
+                tame: true,
+            },
+            Walk {
+                point: target.clone(),
+                distance: BigUint::from(0u32),
+                tame: false,
+            },
+        ];
+        let mut distinguished = HashMap::new();
+
+        for _ in 0..max_steps {
+            for walk in &mut walks {
+                let x = x_bits(&walk.point);
+                if x.trailing_zeros() as u64 >= distinguished_bits {
+                    let seen = distinguished
+                        .entry(key(&walk.point))
+                        .or_insert((walk.tame, walk.distance.clone()));
+
+                    if seen.0 != walk.tame {
+                        // tame = k + wild, for the tame and wild distances.
+                        let (tame, wild) = if walk.tame {
+                            (&walk.distance, &seen.1)
+                        } else {
+                            (&seen.1, &walk.distance)
+                        };
+
+                        if tame >= wild {
+                            let k = tame - wild;
+                            if range.contains(&k) {
+                                return Some(k);
+                            }
+                        }
+                    }
+                }
+
+                let index = x.wrapping_add(salt) % jump_count;
+                walk.point = &walk.point + &jumps[index as usize];
+                walk.distance += 1u64 << index;
+            }
+        }
+    }
+
+    None
+}
+
+struct Walk {
+    point: Point,
+    /// The discrete log of `point` for the tame walk, and how far it is
+    /// from `target` for the wild one.
+    distance: BigUint,
+    tame: bool,
+}
+
+/// The coordinates as a hash table key, since `Point` is not `Hash`, or
+/// `None` for the point at infinity.
+fn key(point: &Point) -> Option<(BigUint, BigUint)> {
+    Some((point.x()?.num().clone(), point.y()?.num().clone()))
+}
+
+/// The low 64 bits of the x coordinate, and 0 for the point at infinity.
+fn x_bits(point: &Point) -> u64 {
+    point
+        .x()
+        .and_then(|x| x.num().iter_u64_digits().next())
+        .unwrap_or(0)
+}
+
+fn to_u64(value: &BigUint) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod ecdlp_tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::finite_field::FieldElement;
+    use crate::secp256k1::G;
+    use crate::small_curve::SmallCurve;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    type Solver = fn(&Point, &Point, &Range<BigUint>) -> Option<BigUint>;
+
+    const SOLVERS: [(&str, Solver); 2] = [
+        ("baby_step_giant_step", baby_step_giant_step),
+        ("pollard_kangaroo", pollard_kangaroo),
+    ];
+
+    fn range(start: u64, end: u64) -> Range<BigUint> {
+        BigUint::from(start)..BigUint::from(end)
+    }
+
+    #[test]
+    fn test_secp256k1_small_keys() {
+        let g = G.point();
+        let mut rng = StdRng::seed_from_u64(50);
+        let bits = 16;
+
+        let mut keys = vec![0, 1, 2, (1 << bits) - 1];
+        keys.extend((0..2).map(|_| rng.random_range(0..1u64 << bits)));
+
+        for (name, solve) in SOLVERS {
+            for &k in &keys {
+                let target = &g * BigUint::from(k);
+                assert_eq!(
+                    solve(&g, &target, &range(0, 1 << bits)),
+                    Some(BigUint::from(k)),
+                    "{name}: {k}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_secp256k1_offset_range() {
+        // A key known to lie in [2^40, 2^40 + 2^20), such as one whose top
+        // bits leaked.
+        let g = G.point();
+        let start = 1u64 << 40;
+        let k = start + 0x5a5a5;
+        let target = &g * BigUint::from(k);
+
+        for (name, solve) in SOLVERS {
+            assert_eq!(
+                solve(&g, &target, &range(start, start + (1 << 20))),
+                Some(BigUint::from(k)),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_secp256k1_key_outside_range() {
+        let g = G.point();
+        let target = &g * BigUint::from(5000u32);
+
+        for (name, solve) in SOLVERS {
+            assert_eq!(solve(&g, &target, &range(0, 4096)), None, "{name}");
+            assert_eq!(solve(&g, &target, &range(5001, 9000)), None, "{name}");
+            assert_eq!(solve(&g, &target, &range(7, 7)), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_bsgs_matches_naive_discrete_log() {
+        // y^2 = x^3 + 2x + 2 over F_17 is cyclic of order 19. Pollard's
+        // kangaroo needs a range much smaller than the group, so only BSGS can
+        // search the whole of it.
+        let prime = Arc::new(BigUint::from(17u32));
+        let curve = SmallCurve::new(
+            FieldElement::new(BigUint::from(2u32), prime.clone()).unwrap(),
+            FieldElement::new(BigUint::from(2u32), prime).unwrap(),
+        )
+        .unwrap();
+        let g = curve.point(5, 1).unwrap();
+
+        for target in curve.points() {
+            let expected = curve.discrete_log(&g, &target).map(BigUint::from);
+            assert_eq!(baby_step_giant_step(&g, &target, &range(0, 19)), expected);
+        }
+    }
+}
@@ -65,6 +65,27 @@ impl Point {
         })
     }
 
+    /// Decompresses a point from its x coordinate and the parity of its y
+    /// coordinate, on any curve over a prime field.
+    pub fn lift_x(
+        x: FieldElement,
+        y_is_odd: bool,
+        a: FieldElement,
+        b: FieldElement,
+    ) -> Result<Point, PointError> {
+        let y_squared = x.pow(3u32) + &a * &x + &b;
+        let y = y_squared.sqrt().ok_or(PointError::NotOnCurve)?;
+
+        // The roots are y and p - y, of opposite parity, unless y is 0 and
+        // there is no odd root.
+        if y.is_zero() && y_is_odd {
+            return Err(PointError::NotOnCurve);
+        }
+        let y = if y.num().bit(0) == y_is_odd { y } else { -y };
+
+        Point::new(x, y, a, b)
+    }
+
     pub fn is_at_infinity(&self) -> bool {
         matches!(self.kind, PointKind::Infinity)
     }
@@ -346,4 +367,32 @@ mod elliptic_curve_tests {
         let res3 = &p * BigUint::from(2u32);
         assert_eq!(res3, p2, "2 * P should be the same as point doubling");
     }
+
+    #[test]
+    fn test_lift_x() {
+        // y^2 = x^3 + 2x + 3 over F_97, where p = 1 mod 4 so square roots need
+        // Tonelli-Shanks. (3, 6) and (3, 91) are on the curve.
+        let prime = Arc::new(BigUint::from(97u32));
+        let element = |num: u32| FieldElement::new(BigUint::from(num), prime.clone()).unwrap();
+        let (a, b) = (element(2), element(3));
+
+        let even = Point::lift_x(element(3), false, a.clone(), b.clone()).unwrap();
+        let odd = Point::lift_x(element(3), true, a.clone(), b.clone()).unwrap();
+        assert_eq!(even.y(), Some(&element(6)));
+        assert_eq!(odd.y(), Some(&element(91)));
+
+        // 2^3 + 2 * 2 + 3 = 15 is not a square mod 97.
+        assert!(matches!(
+            Point::lift_x(element(2), false, a.clone(), b.clone()),
+            Err(PointError::NotOnCurve)
+        ));
+
+        // 30^3 + 2 * 30 + 3 = 0 mod 97, so the only point is (30, 0).
+        let zero = Point::lift_x(element(30), false, a.clone(), b.clone()).unwrap();
+        assert_eq!(zero.y(), Some(&element(0)));
+        assert!(matches!(
+            Point::lift_x(element(30), true, a, b),
+            Err(PointError::NotOnCurve)
+        ));
+    }
 }
@@ -1,14 +1,20 @@
 use std::{
-    ops::{Add, Div, Mul, Sub},
+    ops::{Add, Div, Mul, Neg, Sub},
     sync::Arc,
 };
 
 use num_bigint::BigUint;
 
+mod typed;
+
+pub(crate) use typed::prime_field;
+pub(crate) use typed::{Field, TypedFieldElement};
+
 #[derive(Debug)]
 pub enum FieldElementError {
     InvalidNum(BigUint, Arc<BigUint>),
     InvalidPrime(Arc<BigUint>),
+    FieldMismatch(Arc<BigUint>, Arc<BigUint>),
 }
 
 impl std::fmt::Display for FieldElementError {
@@ -22,6 +28,11 @@ impl std::fmt::Display for FieldElementError {
             FieldElementError::InvalidPrime(prime) => {
                 write!(f, "Invalid prime: {} is less than or equal to 1", prime)
             }
+            FieldElementError::FieldMismatch(found, expected) => write!(
+                f,
+                "Field mismatch: element of the field of order {} where {} was expected",
+                found, expected
+            ),
         }
     }
 }
@@ -65,6 +76,10 @@ impl FieldElement {
         &self.num
     }
 
+    pub fn prime(&self) -> &Arc<BigUint> {
+        &self.prime
+    }
+
     // This method uses the "Into Parameter" pattern for ergonomic API design.
     // By using a generic `E: Into<BigUint>`, this function can accept any type
     // that can be converted into a `BigUint` (e.g., `u32`, `u64`), making it
@@ -99,6 +114,137 @@ impl FieldElement {
     pub fn is_zero(&self) -> bool {
         self.num == BigUint::from(0u32)
     }
+
+    /// The Legendre symbol of the element: 1 for a non-zero square, -1 for a
+    /// non-square and 0 for zero.
+    ///
+    /// By Euler's criterion, `a^((p - 1) / 2)` is 1 exactly when `a` is a
+    /// non-zero square and `p - 1` otherwise. That only holds for a prime
+    /// modulus; for a composite one the result is meaningless.
+    pub fn legendre(&self) -> i8 {
+        if self.is_zero() {
+            return 0;
+        }
+
+        let exponent = (&*self.prime - BigUint::from(1u32)) / BigUint::from(2u32);
+        if self.num.modpow(&exponent, &self.prime) == BigUint::from(1u32) {
+            1
+        } else {
+            -1
+        }
+    }
+
+    /// Like `legendre`, only meaningful for a prime modulus.
+    pub fn is_square(&self) -> bool {
+        self.legendre() != -1
+    }
+
+    // `S256FieldElement::sqrt` can use a single exponentiation because its
+    // prime is 3 mod 4. Tonelli-Shanks handles every odd prime: write
+    // p - 1 = q * 2^s with q odd, start from r = a^((q + 1) / 2), whose square
+    // is a * t with t = a^q, and repeatedly correct r using powers of a
+    // non-square until t is 1. For a prime, each step strictly lowers the
+    // order of t, so the loop runs at most s times; for a composite modulus
+    // that can fail, so every loop is bounded and the root is checked.
+    /// Returns a square root of the element, or `None` if it is not a square.
+    ///
+    /// The other root is the negation of the one returned. The modulus must be
+    /// prime: for a composite one, this returns `None` for some squares.
+    pub fn sqrt(&self) -> Option<FieldElement> {
+        let one = BigUint::from(1u32);
+        let two = BigUint::from(2u32);
+        let element = |num: BigUint| FieldElement {
+            num,
+            prime: self.prime.clone(),
+        };
+
+        // Every element of GF(2) is its own square root.
+        if self.is_zero() || *self.prime == two {
+            return Some(self.clone());
+        }
+        if self.legendre() != 1 {
+            return None;
+        }
+
+        let mut q = &*self.prime - &one;
+        let mut s = 0u32;
+        while !q.bit(0) {
+            q >>= 1;
+            s += 1;
+        }
+
+        // Half of the non-zero elements are non-squares, so this ends quickly.
+        let mut z = two.clone();
+        while element(z.clone()).legendre() != -1 {
+            z += 1u32;
+            if z >= *self.prime {
+                return None; // only possible for a composite modulus
+            }
+        }
+
+        let mut m = s;
+        let mut c = z.modpow(&q, &self.prime);
+        let mut t = self.num.modpow(&q, &self.prime);
+        let mut r = self.num.modpow(&((&q + &one) / &two), &self.prime);
+
+        while t != one {
+            // The least i with t^(2^i) = 1; it is below m.
+            let mut i = 0;
+            let mut t_power = t.clone();
+            while t_power != one {
+                t_power = (&t_power * &t_power) % &*self.prime;
+                i += 1;
+                if i >= m {
+                    return None; // only possible for a composite modulus
+                }
+            }
+
+            let b = c.modpow(&(BigUint::from(1u32) << (m - i - 1)), &self.prime);
+            m = i;
+            c = (&b * &b) % &*self.prime;
+            t = (t * &c) % &*self.prime;
+            r = (r * b) % &*self.prime;
+        }
+
+        let root = element(r);
+        (root.pow(2u32) == *self).then_some(root)
+    }
+
+    // Each division computes a Fermat exponentiation, which costs hundreds of
+    // multiplications. Montgomery's trick inverts a whole slice with a single
+    // exponentiation: invert the product of all elements, then peel off one
+    // element at a time using the running prefix products, for a total of
+    // 3(n - 1) multiplications.
+    /// Replaces every element with its inverse.
+    ///
+    /// Panics with "Division by zero" if any element is zero, and like the
+    /// arithmetic operators if the elements are not all in the same field.
+    pub fn batch_invert(elements: &mut [FieldElement]) {
+        let Some(first) = elements.first() else {
+            return;
+        };
+
+        // prefixes[i] is the product of elements[0..=i].
+        let mut prefixes = Vec::with_capacity(elements.len());
+        prefixes.push(first.clone());
+        for (i, element) in elements.iter().enumerate().skip(1) {
+            let product = &prefixes[i - 1] * element;
+            prefixes.push(product);
+        }
+
+        let product = &prefixes[elements.len() - 1];
+        if product.is_zero() {
+            panic!("Division by zero")
+        }
+
+        let mut inverse = product.pow(&*product.prime - BigUint::from(2u32));
+        for i in (1..elements.len()).rev() {
+            let element_inverse = &inverse * &prefixes[i - 1];
+            inverse = &inverse * &elements[i];
+            elements[i] = element_inverse;
+        }
+        elements[0] = inverse;
+    }
 }
 
 macro_rules! impl_field_element_wrappers {
@@ -240,6 +386,27 @@ impl Sub<&BigUint> for &FieldElement {
 
 impl_biguint_wrappers!(Sub, sub);
 
+impl Neg for &FieldElement {
+    type Output = FieldElement;
+
+    fn neg(self) -> Self::Output {
+        let num = (&*self.prime - &self.num) % &*self.prime;
+
+        FieldElement {
+            num,
+            prime: self.prime.clone(),
+        }
+    }
+}
+
+impl Neg for FieldElement {
+    type Output = FieldElement;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
+
 impl Mul for &FieldElement {
     type Output = FieldElement;
 
@@ -555,4 +722,113 @@ mod finite_field_tests {
         let b_div = (&b1 * b2.modpow(&p_minus_2, &prime)) % &prime;
         assert_eq!(fe_div.num, b_div, "Division result mismatch with BigUint");
     }
+
+    #[test]
+    fn test_batch_invert() {
+        let p = Arc::new(BigUint::from(31u32));
+        let mut elements: Vec<FieldElement> = [3u32, 1, 17, 30, 3, 22]
+            .iter()
+            .map(|&num| FieldElement::new(BigUint::from(num), p.clone()).unwrap())
+            .collect();
+        let expected: Vec<FieldElement> = elements
+            .iter()
+            .map(|element| FieldElement::new(BigUint::from(1u32), p.clone()).unwrap() / element)
+            .collect();
+
+        FieldElement::batch_invert(&mut elements);
+        assert_eq!(elements, expected);
+
+        // An empty slice is left alone.
+        FieldElement::batch_invert(&mut []);
+    }
+
+    #[test]
+    #[should_panic(expected = "Division by zero")]
+    fn test_batch_invert_zero_element() {
+        let p = Arc::new(BigUint::from(13u32));
+        let mut elements: Vec<FieldElement> = [5u32, 0, 7]
+            .iter()
+            .map(|&num| FieldElement::new(BigUint::from(num), p.clone()).unwrap())
+            .collect();
+
+        FieldElement::batch_invert(&mut elements);
+    }
+
+    #[test]
+    fn test_legendre_and_sqrt_exhaustive() {
+        // 3 and 223 are 3 mod 4; 13, 17 and 97 have p - 1 divisible by 4, 16
+        // and 32, which need several Tonelli-Shanks steps.
+        for p in [2u32, 3, 13, 17, 97, 223] {
+            let prime = Arc::new(BigUint::from(p));
+            let mut squares = 0;
+
+            for num in 0..p {
+                let a = FieldElement::new(BigUint::from(num), prime.clone()).unwrap();
+                let has_root = (0..p).any(|y| (y * y) % p == num);
+
+                assert_eq!(a.is_square(), has_root, "{num} mod {p}");
+                match a.sqrt() {
+                    Some(root) => {
+                        assert!(has_root);
+                        assert_eq!(root.pow(2u32), a);
+                        squares += 1;
+                    }
+                    None => assert!(!has_root),
+                }
+
+                let expected = match (num, has_root) {
+                    (0, _) => 0,
+                    (_, true) => 1,
+                    (_, false) => -1,
+                };
+                assert_eq!(a.legendre(), expected);
+            }
+
+            // Zero and the (p - 1) / 2 non-zero squares.
+            assert_eq!(squares, p / 2 + 1);
+        }
+    }
+
+    #[test]
+    fn test_sqrt_composite_modulus_terminates() {
+        // Tonelli-Shanks needs a prime; these used to loop forever.
+        for (modulus, nums) in [(9u32, [7u32, 8]), (21, [8, 13]), (25, [7, 18])] {
+            let modulus = Arc::new(BigUint::from(modulus));
+            for num in nums {
+                let a = FieldElement::new(BigUint::from(num), modulus.clone()).unwrap();
+                if let Some(root) = a.sqrt() {
+                    assert_eq!(root.pow(2u32), a);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sqrt_large_primes() {
+        // 998244353 = 119 * 2^23 + 1 and 2^255 - 19 = 1 mod 4.
+        let primes = [
+            BigUint::from(998244353u32),
+            (BigUint::from(1u32) << 255u32) - BigUint::from(19u32),
+        ];
+
+        for p in primes {
+            let prime = Arc::new(p);
+            for num in [2u32, 3, 5, 10, 12345] {
+                let a = FieldElement::new(BigUint::from(num), prime.clone()).unwrap();
+                let square = a.pow(2u32);
+
+                let root = square.sqrt().unwrap();
+                assert!(
+                    root == a
+                        || &root + &a
+                            == FieldElement::new(BigUint::from(0u32), prime.clone()).unwrap()
+                );
+
+                match a.sqrt() {
+                    Some(root) => assert_eq!(root.pow(2u32), a),
+                    None => assert_eq!(a.legendre(), -1),
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,349 @@
+use std::{
+    fmt::Debug,
+    marker::PhantomData,
+    ops::{Add, Div, Mul, Neg, Sub},
+    sync::Arc,
+};
+
+use num_bigint::BigUint;
+
+use super::{FieldElement, FieldElementError};
+
+/// A prime field known at compile time.
+///
+/// Implementors are zero-sized marker types, usually declared with
+/// `prime_field!`. Because the field is part of the element's type, adding
+/// elements of two different fields is a type error rather than a panic. See
+/// `note/13-type-level-field-identity.md`.
+pub trait Field: Debug + Clone + Copy + PartialEq + Eq + 'static {
+    fn prime() -> &'static Arc<BigUint>;
+}
+
+/// Declares a marker type implementing `Field` for the given prime.
+///
+/// The prime expression is evaluated once, on first use, and must be a prime
+/// greater than 1 of type `BigUint`. Using the field panics if it is not
+/// greater than 1; primality is not checked.
+macro_rules! prime_field {
+    ($(#[$meta:meta])* $vis:vis $name:ident, $prime:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        $vis struct $name;
+
+        impl $crate::finite_field::Field for $name {
+            fn prime() -> &'static ::std::sync::Arc<::num_bigint::BigUint> {
+                static PRIME: ::std::sync::LazyLock<::std::sync::Arc<::num_bigint::BigUint>> =
+                    ::std::sync::LazyLock::new(|| {
+                        let prime: ::num_bigint::BigUint = $prime;
+                        assert!(
+                            prime > ::num_bigint::BigUint::from(1u32),
+                            "the prime of {} must be greater than 1",
+                            stringify!($name)
+                        );
+
+                        ::std::sync::Arc::new(prime)
+                    });
+
+                &PRIME
+            }
+        }
+    };
+}
+
+pub(crate) use prime_field;
+
+/// An element of the field `F`.
+///
+/// This is the statically typed counterpart of `FieldElement`: the prime is
+/// not stored in each element but looked up through `F`, and every operation
+/// is between elements of the same field by construction.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypedFieldElement<F: Field> {
+    num: BigUint,
+    field: PhantomData<F>,
+}
+
+impl<F: Field> TypedFieldElement<F> {
+    pub fn new<E: Into<BigUint>>(num: E) -> Result<Self, FieldElementError> {
+        let num = num.into();
+
+        if num >= **F::prime() {
+            return Err(FieldElementError::InvalidNum(num, F::prime().clone()));
+        }
+
+        Ok(Self::from_reduced(num))
+    }
+
+    /// `num` must already be below the prime.
+    fn from_reduced(num: BigUint) -> Self {
+        TypedFieldElement {
+            num,
+            field: PhantomData,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self::from_reduced(BigUint::from(0u32))
+    }
+
+    pub fn one() -> Self {
+        Self::from_reduced(BigUint::from(1u32))
+    }
+
+    pub fn prime() -> &'static Arc<BigUint> {
+        F::prime()
+    }
+
+    pub fn num(&self) -> &BigUint {
+        &self.num
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == BigUint::from(0u32)
+    }
+
+    pub fn pow<E: Into<BigUint>>(&self, exponent: E) -> Self {
+        Self::from_dynamic_unchecked(self.to_dynamic().pow(exponent))
+    }
+
+    pub fn legendre(&self) -> i8 {
+        self.to_dynamic().legendre()
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.to_dynamic().is_square()
+    }
+
+    /// Returns a square root of the element, or `None` if it is not a square.
+    pub fn sqrt(&self) -> Option<Self> {
+        self.to_dynamic().sqrt().map(Self::from_dynamic_unchecked)
+    }
+
+    /// Replaces every element with its inverse; see
+    /// `FieldElement::batch_invert`.
+    pub fn batch_invert(elements: &mut [Self]) {
+        let mut dynamic: Vec<FieldElement> = elements.iter().map(Self::to_dynamic).collect();
+        FieldElement::batch_invert(&mut dynamic);
+
+        for (element, inverse) in elements.iter_mut().zip(dynamic) {
+            *element = Self::from_dynamic_unchecked(inverse);
+        }
+    }
+
+    /// The same element as a dynamically typed `FieldElement`.
+    pub fn to_dynamic(&self) -> FieldElement {
+        FieldElement {
+            num: self.num.clone(),
+            prime: F::prime().clone(),
+        }
+    }
+
+    /// Converts a dynamically typed element, checking that it belongs to `F`.
+    pub fn from_dynamic(element: &FieldElement) -> Result<Self, FieldElementError> {
+        if element.prime != *F::prime() {
+            return Err(FieldElementError::FieldMismatch(
+                element.prime.clone(),
+                F::prime().clone(),
+            ));
+        }
+
+        Ok(Self::from_reduced(element.num.clone()))
+    }
+
+    fn from_dynamic_unchecked(element: FieldElement) -> Self {
+        Self::from_reduced(element.num)
+    }
+}
+
+macro_rules! impl_typed_wrappers {
+    ($trait:ident, $method:ident) => {
+        impl<F: Field> $trait<TypedFieldElement<F>> for TypedFieldElement<F> {
+            type Output = TypedFieldElement<F>;
+
+            fn $method(self, rhs: TypedFieldElement<F>) -> TypedFieldElement<F> {
+                (&self).$method(&rhs)
+            }
+        }
+
+        impl<F: Field> $trait<&TypedFieldElement<F>> for TypedFieldElement<F> {
+            type Output = TypedFieldElement<F>;
+
+            fn $method(self, rhs: &TypedFieldElement<F>) -> TypedFieldElement<F> {
+                (&self).$method(rhs)
+            }
+        }
+
+        impl<F: Field> $trait<TypedFieldElement<F>> for &TypedFieldElement<F> {
+            type Output = TypedFieldElement<F>;
+
+            fn $method(self, rhs: TypedFieldElement<F>) -> TypedFieldElement<F> {
+                self.$method(&rhs)
+            }
+        }
+    };
+}
+
+// Unlike `FieldElement`, none of these check that the operands share a prime:
+// the signatures only accept two elements of the same `F`.
+
+impl<F: Field> Add for &TypedFieldElement<F> {
+    type Output = TypedFieldElement<F>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        TypedFieldElement::from_reduced((&self.num + &rhs.num) % &**F::prime())
+    }
+}
+
+impl_typed_wrappers!(Add, add);
+
+impl<F: Field> Sub for &TypedFieldElement<F> {
+    type Output = TypedFieldElement<F>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let prime = &**F::prime();
+
+        TypedFieldElement::from_reduced((&self.num + prime - &rhs.num) % prime)
+    }
+}
+
+impl_typed_wrappers!(Sub, sub);
+
+impl<F: Field> Mul for &TypedFieldElement<F> {
+    type Output = TypedFieldElement<F>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        TypedFieldElement::from_reduced((&self.num * &rhs.num) % &**F::prime())
+    }
+}
+
+impl_typed_wrappers!(Mul, mul);
+
+impl<F: Field> Div for &TypedFieldElement<F> {
+    type Output = TypedFieldElement<F>;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        TypedFieldElement::from_dynamic_unchecked(self.to_dynamic() / rhs.to_dynamic())
+    }
+}
+
+impl_typed_wrappers!(Div, div);
+
+impl<F: Field> Neg for &TypedFieldElement<F> {
+    type Output = TypedFieldElement<F>;
+
+    fn neg(self) -> Self::Output {
+        &TypedFieldElement::zero() - self
+    }
+}
+
+impl<F: Field> Neg for TypedFieldElement<F> {
+    type Output = TypedFieldElement<F>;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
+
+impl<F: Field> std::fmt::Display for TypedFieldElement<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FieldElement({}, {})", self.num, F::prime())
+    }
+}
+
+#[cfg(test)]
+mod typed_field_element_tests {
+    use super::*;
+
+    prime_field!(F13, BigUint::from(13u32));
+    prime_field!(F31, BigUint::from(31u32));
+
+    type E13 = TypedFieldElement<F13>;
+
+    fn e13(num: u32) -> E13 {
+        E13::new(num).unwrap()
+    }
+
+    #[test]
+    fn test_new_and_eq() {
+        assert_eq!(e13(7), e13(7));
+        assert_ne!(e13(7), e13(6));
+        assert!(matches!(
+            E13::new(13u32),
+            Err(FieldElementError::InvalidNum(_, _))
+        ));
+        assert_eq!(**E13::prime(), BigUint::from(13u32));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(e13(7) + e13(12), e13(6));
+        assert_eq!(e13(7) - e13(12), e13(8));
+        assert_eq!(e13(3) * e13(12), e13(10));
+        assert_eq!(e13(2) / e13(7), e13(4));
+        assert_eq!(-e13(5), e13(8));
+        assert_eq!(-E13::zero(), E13::zero());
+        assert_eq!(e13(3).pow(3u32), e13(1));
+        assert_eq!(E13::one() + E13::zero(), e13(1));
+    }
+
+    #[test]
+    fn test_matches_dynamic_field_element() {
+        for a in 0..31u32 {
+            for b in 1..31u32 {
+                let (ta, tb) = (
+                    TypedFieldElement::<F31>::new(a).unwrap(),
+                    TypedFieldElement::<F31>::new(b).unwrap(),
+                );
+                let (da, db) = (ta.to_dynamic(), tb.to_dynamic());
+
+                assert_eq!((&ta + &tb).to_dynamic(), &da + &db);
+                assert_eq!((&ta - &tb).to_dynamic(), &da - &db);
+                assert_eq!((&ta * &tb).to_dynamic(), &da * &db);
+                assert_eq!((&ta / &tb).to_dynamic(), &da / &db);
+            }
+        }
+    }
+
+    #[test]
+    fn test_square_roots_and_inversion() {
+        let root = e13(10).sqrt().unwrap();
+        assert_eq!(root.pow(2u32), e13(10));
+        assert!(e13(5).sqrt().is_none());
+        assert_eq!(e13(5).legendre(), -1);
+        assert!(e13(4).is_square());
+
+        let mut elements = [e13(2), e13(5), e13(12)];
+        E13::batch_invert(&mut elements);
+        assert_eq!(elements, [e13(7), e13(8), e13(12)]);
+    }
+
+    #[test]
+    fn test_dynamic_conversion() {
+        let dynamic = e13(9).to_dynamic();
+        assert_eq!(
+            dynamic,
+            FieldElement::new(BigUint::from(9u32), BigUint::from(13u32)).unwrap()
+        );
+        assert_eq!(E13::from_dynamic(&dynamic).unwrap(), e13(9));
+
+        // An element of another field is rejected instead of reinterpreted.
+        assert!(matches!(
+            TypedFieldElement::<F31>::from_dynamic(&dynamic),
+            Err(FieldElementError::FieldMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Division by zero")]
+    fn test_div_by_zero() {
+        let _ = e13(1) / E13::zero();
+    }
+
+    #[test]
+    #[should_panic(expected = "the prime of F1 must be greater than 1")]
+    fn test_prime_must_be_greater_than_one() {
+        prime_field!(F1, BigUint::from(1u32));
+
+        let _ = F1::prime();
+    }
+}
@@ -0,0 +1,1080 @@
+//! FROST threshold Schnorr signatures.
+//!
+//! Any `min_signers` of `max_signers` participants can jointly produce a
+//! BIP340 signature for a shared group key, without any of them ever holding
+//! the group secret. Keys are created either by a trusted dealer that splits
+//! an existing key (`trusted_dealer_keygen`) or by a distributed key
+//! generation (`dkg_part1`, `dkg_part2`, `dkg_part3`) in which no single
+//! party learns the secret. Both use Feldman commitments to the secret
+//! polynomial, so every participant can check the share it was given.
+//!
+//! Signing takes two rounds: `commit` produces a nonce pair per signer, and
+//! once the coordinator has collected the commitments into a `SigningPackage`
+//! every signer calls `sign`. The coordinator checks each share with
+//! `verify_signature_share` and combines them with `aggregate`.
+//!
+//! Participants are identified by non-zero `u32` identifiers, which are also
+//! the points at which the secret polynomial is evaluated.
+//!
+//! This is a non-standard variant of FROST, not the RFC 9591 ciphersuite: it
+//! produces BIP340 signatures, and derives binding factors, nonces and DKG
+//! proofs of knowledge with its own BIP340-style tagged hashes
+//! ("FROST/binding", "FROST/nonce" and "FROST/pok"). Signers and coordinators
+//! must all use this implementation; it does not interoperate with others.
+
+use std::collections::BTreeMap;
+
+use num_bigint::BigUint;
+use rand::RngCore;
+
+use crate::secp256k1::{
+    G, N, PrivateKey, S256Point, SchnorrSignature, XOnlyPublicKey, random_scalar,
+    schnorr_challenge, tagged_hash, to_32_bytes,
+};
+
+/// A participant's share of the group secret, as handed out by a trusted
+/// dealer, along with the dealer's commitment to the secret polynomial.
+#[derive(Debug, Clone)]
+pub struct SecretShare {
+    pub identifier: u32,
+    pub value: BigUint,
+    pub commitment: Vec<S256Point>,
+}
+
+impl SecretShare {
+    /// Checks the share against the polynomial commitment:
+    /// `value * G == sum(commitment[j] * identifier^j)`.
+    pub fn verify(&self) -> bool {
+        self.value < *N
+            && S256Point::mul_generator(&self.value)
+                == evaluate_commitment(&self.commitment, self.identifier)
+    }
+}
+
+/// Everything a participant needs to sign.
+#[derive(Debug, Clone)]
+pub struct KeyPackage {
+    identifier: u32,
+    signing_share: BigUint,
+    verifying_share: S256Point,
+    group_public_key: S256Point,
+    min_signers: u32,
+}
+
+impl KeyPackage {
+    /// Verifies a dealer-issued share and turns it into a key package.
+    pub fn from_secret_share(share: &SecretShare) -> Result<KeyPackage, FrostError> {
+        // A share at 0 would be the group secret itself.
+        if share.identifier == 0 {
+            return Err(FrostError::InvalidIdentifier(0));
+        }
+        if share.commitment.len() < 2 {
+            return Err(FrostError::InvalidCommitment);
+        }
+        if !share.verify() {
+            return Err(FrostError::InvalidSecretShare(share.identifier));
+        }
+
+        Ok(KeyPackage {
+            identifier: share.identifier,
+            signing_share: share.value.clone(),
+            verifying_share: S256Point::mul_generator(&share.value),
+            group_public_key: share.commitment[0].clone(),
+            min_signers: share.commitment.len() as u32,
+        })
+    }
+
+    pub fn identifier(&self) -> u32 {
+        self.identifier
+    }
+
+    pub fn verifying_share(&self) -> &S256Point {
+        &self.verifying_share
+    }
+
+    pub fn group_public_key(&self) -> XOnlyPublicKey {
+        XOnlyPublicKey::from_point(&self.group_public_key).0
+    }
+
+    pub fn min_signers(&self) -> u32 {
+        self.min_signers
+    }
+}
+
+/// The public data a coordinator needs to check signature shares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKeyPackage {
+    verifying_shares: BTreeMap<u32, S256Point>,
+    group_public_key: S256Point,
+}
+
+impl PublicKeyPackage {
+    fn from_commitment(commitment: &[S256Point], identifiers: impl Iterator<Item = u32>) -> Self {
+        let verifying_shares = identifiers
+            .map(|identifier| (identifier, evaluate_commitment(commitment, identifier)))
+            .collect();
+
+        PublicKeyPackage {
+            verifying_shares,
+            group_public_key: commitment[0].clone(),
+        }
+    }
+
+    pub fn verifying_share(&self, identifier: u32) -> Option<&S256Point> {
+        self.verifying_shares.get(&identifier)
+    }
+
+    /// The x-only key that aggregated signatures verify against.
+    pub fn group_public_key(&self) -> XOnlyPublicKey {
+        XOnlyPublicKey::from_point(&self.group_public_key).0
+    }
+}
+
+/// Splits `key` into `max_signers` shares, any `min_signers` of which can
+/// sign for it.
+///
+/// The dealer learns (and already knows) the full secret, so it must be
+/// trusted and should erase the key once the shares are distributed.
+pub fn trusted_dealer_keygen<R: RngCore>(
+    key: &PrivateKey,
+    min_signers: u32,
+    max_signers: u32,
+    rng: &mut R,
+) -> Result<(Vec<SecretShare>, PublicKeyPackage), FrostError> {
+    validate_num_signers(min_signers, max_signers)?;
+
+    let coefficients = random_polynomial(key.secret().clone(), min_signers, rng);
+    let commitment = commit_polynomial(&coefficients);
+
+    let shares = (1..=max_signers)
+        .map(|identifier| SecretShare {
+            identifier,
+            value: evaluate_polynomial(&coefficients, identifier),
+            commitment: commitment.clone(),
+        })
+        .collect();
+
+    let public_key_package = PublicKeyPackage::from_commitment(&commitment, 1..=max_signers);
+
+    Ok((shares, public_key_package))
+}
+
+/// The state a participant keeps between DKG rounds one and two. It holds the
+/// secret polynomial and must not be shared.
+#[derive(Debug)]
+pub struct Round1SecretPackage {
+    identifier: u32,
+    coefficients: Vec<BigUint>,
+    commitment: Vec<S256Point>,
+    min_signers: u32,
+    max_signers: u32,
+}
+
+/// Broadcast to every other participant in DKG round one: the commitment to
+/// the participant's polynomial and a proof that it knows the constant term.
+#[derive(Debug, Clone)]
+pub struct Round1Package {
+    pub commitment: Vec<S256Point>,
+    pub proof_of_knowledge: (S256Point, BigUint),
+}
+
+/// The state a participant keeps between DKG rounds two and three.
+#[derive(Debug)]
+pub struct Round2SecretPackage {
+    identifier: u32,
+    commitment: Vec<S256Point>,
+    own_share: BigUint,
+    min_signers: u32,
+    max_signers: u32,
+}
+
+/// Sent privately to one other participant in DKG round two: that
+/// participant's evaluation of the sender's polynomial.
+#[derive(Debug, Clone)]
+pub struct Round2Package {
+    pub signing_share: BigUint,
+}
+
+/// DKG round one: picks a random polynomial and commits to it.
+pub fn dkg_part1<R: RngCore>(
+    identifier: u32,
+    min_signers: u32,
+    max_signers: u32,
+    rng: &mut R,
+) -> Result<(Round1SecretPackage, Round1Package), FrostError> {
+    validate_num_signers(min_signers, max_signers)?;
+    if identifier == 0 || identifier > max_signers {
+        return Err(FrostError::InvalidIdentifier(identifier));
+    }
+
+    let coefficients = random_polynomial(random_scalar(rng), min_signers, rng);
+    let commitment = commit_polynomial(&coefficients);
+
+    // A Schnorr proof of knowledge of the constant term stops a participant
+    // from choosing its commitment as a function of the others' (a rogue-key
+    // attack on the group key).
+    let k = random_scalar(rng);
+    let r = S256Point::mul_generator(&k);
+    let c = proof_of_knowledge_challenge(identifier, &commitment[0], &r);
+    let mu = (k + c * &coefficients[0]) % &*N;
+
+    let package = Round1Package {
+        commitment: commitment.clone(),
+        proof_of_knowledge: (r, mu),
+    };
+    let secret_package = Round1SecretPackage {
+        identifier,
+        coefficients,
+        commitment,
+        min_signers,
+        max_signers,
+    };
+
+    Ok((secret_package, package))
+}
+
+/// DKG round two: checks the other participants' round one packages and
+/// computes the share to send privately to each of them.
+///
+/// `round1_packages` holds the packages of every other participant, keyed by
+/// identifier.
+pub fn dkg_part2(
+    secret_package: Round1SecretPackage,
+    round1_packages: &BTreeMap<u32, Round1Package>,
+) -> Result<(Round2SecretPackage, BTreeMap<u32, Round2Package>), FrostError> {
+    check_round1_packages(
+        secret_package.identifier,
+        secret_package.min_signers,
+        secret_package.max_signers,
+        round1_packages,
+    )?;
+
+    let mut round2_packages = BTreeMap::new();
+
+    for (&identifier, package) in round1_packages {
+        let (r, mu) = &package.proof_of_knowledge;
+        let c = proof_of_knowledge_challenge(identifier, &package.commitment[0], r);
+
+        if *mu >= *N || &*G * mu != r + &package.commitment[0] * c {
+            return Err(FrostError::InvalidProofOfKnowledge(identifier));
+        }
+
+        round2_packages.insert(
+            identifier,
+            Round2Package {
+                signing_share: evaluate_polynomial(&secret_package.coefficients, identifier),
+            },
+        );
+    }
+
+    let own_share = evaluate_polynomial(&secret_package.coefficients, secret_package.identifier);
+    let secret_package = Round2SecretPackage {
+        identifier: secret_package.identifier,
+        commitment: secret_package.commitment,
+        own_share,
+        min_signers: secret_package.min_signers,
+        max_signers: secret_package.max_signers,
+    };
+
+    Ok((secret_package, round2_packages))
+}
+
+/// DKG round three: checks the shares received in round two and derives the
+/// participant's key package and the group's public key package.
+pub fn dkg_part3(
+    secret_package: &Round2SecretPackage,
+    round1_packages: &BTreeMap<u32, Round1Package>,
+    round2_packages: &BTreeMap<u32, Round2Package>,
+) -> Result<(KeyPackage, PublicKeyPackage), FrostError> {
+    check_round1_packages(
+        secret_package.identifier,
+        secret_package.min_signers,
+        secret_package.max_signers,
+        round1_packages,
+    )?;
+
+    let mut signing_share = secret_package.own_share.clone();
+    // The group polynomial is the sum of everyone's polynomials, so its
+    // commitment is the coefficient-wise sum of theirs.
+    let mut group_commitment = secret_package.commitment.clone();
+
+    for (&identifier, package) in round1_packages {
+        let share = round2_packages
+            .get(&identifier)
+            .ok_or(FrostError::MissingPackage(identifier))?;
+
+        let received = SecretShare {
+            identifier: secret_package.identifier,
+            value: share.signing_share.clone(),
+            commitment: package.commitment.clone(),
+        };
+        if !received.verify() {
+            return Err(FrostError::InvalidSecretShare(identifier));
+        }
+
+        signing_share = (signing_share + &share.signing_share) % &*N;
+        for (sum, coefficient) in group_commitment.iter_mut().zip(&package.commitment) {
+            *sum = &*sum + coefficient;
+        }
+    }
+
+    let public_key_package =
+        PublicKeyPackage::from_commitment(&group_commitment, 1..=secret_package.max_signers);
+    let key_package = KeyPackage {
+        identifier: secret_package.identifier,
+        verifying_share: S256Point::mul_generator(&signing_share),
+        signing_share,
+        group_public_key: group_commitment[0].clone(),
+        min_signers: secret_package.min_signers,
+    };
+
+    Ok((key_package, public_key_package))
+}
+
+/// A signer's secret nonce pair for one signing session.
+///
+/// Like `musig::SecNonce`, it is not `Clone` and `sign` consumes it: reusing
+/// a nonce across two messages reveals the signing share.
+#[derive(Debug)]
+pub struct SigningNonces {
+    hiding: BigUint,
+    binding: BigUint,
+    commitments: SigningCommitments,
+}
+
+/// The public commitments to a signer's nonces, sent to the coordinator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningCommitments {
+    pub hiding: S256Point,
+    pub binding: S256Point,
+}
+
+/// Signing round one: generates a fresh nonce pair.
+///
+/// The nonces mix the signing share into the randomness, so a weak `rng`
+/// alone does not make them predictable.
+pub fn commit<R: RngCore>(
+    key_package: &KeyPackage,
+    rng: &mut R,
+) -> (SigningNonces, SigningCommitments) {
+    let hiding = nonce_generate(&key_package.signing_share, rng);
+    let binding = nonce_generate(&key_package.signing_share, rng);
+
+    let commitments = SigningCommitments {
+        hiding: S256Point::mul_generator(&hiding),
+        binding: S256Point::mul_generator(&binding),
+    };
+    let nonces = SigningNonces {
+        hiding,
+        binding,
+        commitments: commitments.clone(),
+    };
+
+    (nonces, commitments)
+}
+
+/// The message and the commitments of the signers taking part, assembled by
+/// the coordinator and sent to each of them.
+#[derive(Debug, Clone)]
+pub struct SigningPackage {
+    commitments: BTreeMap<u32, SigningCommitments>,
+    message: Vec<u8>,
+}
+
+impl SigningPackage {
+    /// Fails if there are no commitments or one is for the identifier 0.
+    pub fn new(
+        commitments: BTreeMap<u32, SigningCommitments>,
+        message: &[u8],
+    ) -> Result<Self, FrostError> {
+        if commitments.is_empty() {
+            return Err(FrostError::NotEnoughSigners);
+        }
+        if commitments.contains_key(&0) {
+            return Err(FrostError::InvalidIdentifier(0));
+        }
+
+        Ok(SigningPackage {
+            commitments,
+            message: message.to_vec(),
+        })
+    }
+
+    /// Derives each signer's binding factor, the group commitment `R` and
+    /// the BIP340 challenge for `group_public_key`.
+    fn session(&self, group_public_key: &S256Point) -> SessionValues {
+        let group_key_bytes = group_public_key.x_only();
+
+        let mut encoded_commitments = (self.commitments.len() as u32).to_be_bytes().to_vec();
+        for (identifier, commitments) in &self.commitments {
+            encoded_commitments.extend_from_slice(&identifier.to_be_bytes());
+            encoded_commitments.extend(commitments.hiding.sec(Some(true)));
+            encoded_commitments.extend(commitments.binding.sec(Some(true)));
+        }
+
+        let mut binding_factors = BTreeMap::new();
+        let mut r = S256Point::infinity().unwrap();
+
+        for (&identifier, commitments) in &self.commitments {
+            let mut data = identifier.to_be_bytes().to_vec();
+            data.extend_from_slice(&group_key_bytes);
+            data.extend_from_slice(&encoded_commitments);
+            data.extend_from_slice(&self.message);
+            let rho = BigUint::from_bytes_be(&tagged_hash("FROST/binding", &data)) % &*N;
+
+            r = r + &commitments.hiding + &commitments.binding * &rho;
+            binding_factors.insert(identifier, rho);
+        }
+
+        let challenge = schnorr_challenge(
+            &to_32_bytes(&r.x_num().unwrap_or_default()),
+            &group_key_bytes,
+            &self.message,
+        );
+
+        SessionValues {
+            binding_factors,
+            r,
+            challenge,
+        }
+    }
+
+    fn lagrange_coefficient(&self, identifier: u32) -> BigUint {
+        let x_i = BigUint::from(identifier);
+        let mut numerator = BigUint::from(1u32);
+        let mut denominator = BigUint::from(1u32);
+
+        for &other in self
+            .commitments
+            .keys()
+            .filter(|&&other| other != identifier)
+        {
+            let x_j = BigUint::from(other);
+            numerator = numerator * &x_j % &*N;
+            denominator = denominator * ((&x_j + &*N - &x_i) % &*N) % &*N;
+        }
+
+        numerator * denominator.modpow(&(&*N - 2u32), &N) % &*N
+    }
+}
+
+struct SessionValues {
+    binding_factors: BTreeMap<u32, BigUint>,
+    r: S256Point,
+    challenge: BigUint,
+}
+
+/// One signer's contribution to the final signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureShare {
+    pub share: BigUint,
+}
+
+/// Signing round two: produces this signer's signature share.
+///
+/// BIP340 only has even-y keys and nonces, so the share and nonces are
+/// negated when the group key or the group commitment has an odd y, the same
+/// way `PrivateKey::sign_schnorr` negates a single key.
+pub fn sign(
+    signing_package: &SigningPackage,
+    nonces: SigningNonces,
+    key_package: &KeyPackage,
+) -> Result<SignatureShare, FrostError> {
+    let identifier = key_package.identifier;
+
+    if signing_package.commitments.len() < key_package.min_signers as usize {
+        return Err(FrostError::NotEnoughSigners);
+    }
+    if signing_package.commitments.get(&identifier) != Some(&nonces.commitments) {
+        return Err(FrostError::MissingCommitment(identifier));
+    }
+
+    let session = signing_package.session(&key_package.group_public_key);
+    if session.r.is_at_infinity() {
+        return Err(FrostError::InvalidCommitment);
+    }
+
+    let rho = &session.binding_factors[&identifier];
+    let k = (nonces.hiding + nonces.binding * rho) % &*N;
+    let k = if session.r.has_even_y() { k } else { &*N - k };
+
+    let secret = if key_package.group_public_key.has_even_y() {
+        key_package.signing_share.clone()
+    } else {
+        &*N - &key_package.signing_share
+    };
+
+    let lambda = signing_package.lagrange_coefficient(identifier);
+    let share = (k + lambda * &session.challenge * secret) % &*N;
+
+    Ok(SignatureShare { share })
+}
+
+/// Checks one signer's share so that a misbehaving signer can be identified.
+pub fn verify_signature_share(
+    signing_package: &SigningPackage,
+    public_key_package: &PublicKeyPackage,
+    identifier: u32,
+    signature_share: &SignatureShare,
+) -> bool {
+    let session = signing_package.session(&public_key_package.group_public_key);
+    if session.r.is_at_infinity() {
+        return false;
+    }
+
+    verify_share_in_session(
+        signing_package,
+        &session,
+        public_key_package,
+        identifier,
+        signature_share,
+    )
+}
+
+/// `verify_signature_share` for session values that have already been
+/// derived, so that `aggregate` derives them only once for all the shares.
+fn verify_share_in_session(
+    signing_package: &SigningPackage,
+    session: &SessionValues,
+    public_key_package: &PublicKeyPackage,
+    identifier: u32,
+    signature_share: &SignatureShare,
+) -> bool {
+    let (Some(commitments), Some(verifying_share)) = (
+        signing_package.commitments.get(&identifier),
+        public_key_package.verifying_shares.get(&identifier),
+    ) else {
+        return false;
+    };
+
+    if signature_share.share >= *N {
+        return false;
+    }
+
+    let rho = &session.binding_factors[&identifier];
+    let r_share = &commitments.hiding + &commitments.binding * rho;
+    let r_share = if session.r.has_even_y() {
+        r_share
+    } else {
+        -r_share
+    };
+
+    let verifying_share = if public_key_package.group_public_key.has_even_y() {
+        verifying_share.clone()
+    } else {
+        -verifying_share
+    };
+
+    let lambda = signing_package.lagrange_coefficient(identifier);
+    let expected = r_share + verifying_share * (lambda * &session.challenge % &*N);
+
+    &*G * &signature_share.share == expected
+}
+
+/// Combines the signature shares into a BIP340 signature for the group key.
+///
+/// Every share is verified first, so an invalid signature names the signer
+/// responsible instead of failing as a whole.
+pub fn aggregate(
+    signing_package: &SigningPackage,
+    signature_shares: &BTreeMap<u32, SignatureShare>,
+    public_key_package: &PublicKeyPackage,
+) -> Result<SchnorrSignature, FrostError> {
+    if signature_shares.len() != signing_package.commitments.len() {
+        return Err(FrostError::IncorrectNumberOfShares);
+    }
+
+    let session = signing_package.session(&public_key_package.group_public_key);
+    let Some(r) = session.r.x_num() else {
+        return Err(FrostError::InvalidCommitment);
+    };
+
+    let mut s = BigUint::from(0u32);
+
+    for (&identifier, signature_share) in signature_shares {
+        if !verify_share_in_session(
+            signing_package,
+            &session,
+            public_key_package,
+            identifier,
+            signature_share,
+        ) {
+            return Err(FrostError::InvalidSignatureShare(identifier));
+        }
+
+        s = (s + &signature_share.share) % &*N;
+    }
+
+    Ok(SchnorrSignature { r: r.clone(), s })
+}
+
+fn validate_num_signers(min_signers: u32, max_signers: u32) -> Result<(), FrostError> {
+    if min_signers < 2 || min_signers > max_signers {
+        return Err(FrostError::InvalidMinSigners);
+    }
+
+    Ok(())
+}
+
+fn check_round1_packages(
+    identifier: u32,
+    min_signers: u32,
+    max_signers: u32,
+    round1_packages: &BTreeMap<u32, Round1Package>,
+) -> Result<(), FrostError> {
+    if round1_packages.len() != max_signers as usize - 1 {
+        return Err(FrostError::IncorrectNumberOfPackages);
+    }
+
+    for (&other, package) in round1_packages {
+        if other == 0 || other > max_signers || other == identifier {
+            return Err(FrostError::InvalidIdentifier(other));
+        }
+        if package.commitment.len() != min_signers as usize {
+            return Err(FrostError::InvalidCommitment);
+        }
+    }
+
+    Ok(())
+}
+
+fn nonce_generate<R: RngCore>(secret: &BigUint, rng: &mut R) -> BigUint {
+    loop {
+        let mut data = [0u8; 64];
+        rng.fill_bytes(&mut data[..32]);
+        data[32..].copy_from_slice(&to_32_bytes(secret));
+
+        let nonce = BigUint::from_bytes_be(&tagged_hash("FROST/nonce", &data)) % &*N;
+        if nonce != BigUint::from(0u32) {
+            return nonce;
+        }
+    }
+}
+
+// A polynomial of degree `min_signers - 1` with the given constant term.
+fn random_polynomial<R: RngCore>(secret: BigUint, min_signers: u32, rng: &mut R) -> Vec<BigUint> {
+    let mut coefficients = vec![secret];
+    coefficients.extend((1..min_signers).map(|_| random_scalar(rng)));
+
+    coefficients
+}
+
+fn commit_polynomial(coefficients: &[BigUint]) -> Vec<S256Point> {
+    coefficients.iter().map(S256Point::mul_generator).collect()
+}
+
+// Horner's rule, mod N.
+fn evaluate_polynomial(coefficients: &[BigUint], identifier: u32) -> BigUint {
+    let x = BigUint::from(identifier);
+
+    coefficients
+        .iter()
+        .rev()
+        .fold(BigUint::from(0u32), |acc, coefficient| {
+            (acc * &x + coefficient) % &*N
+        })
+}
+
+// The public counterpart of `evaluate_polynomial`: f(identifier) * G.
+fn evaluate_commitment(commitment: &[S256Point], identifier: u32) -> S256Point {
+    let x = BigUint::from(identifier);
+    let mut power = BigUint::from(1u32);
+    let mut total = S256Point::infinity().unwrap();
+
+    for coefficient in commitment {
+        total = total + coefficient * &power;
+        power = power * &x % &*N;
+    }
+
+    total
+}
+
+fn proof_of_knowledge_challenge(identifier: u32, constant: &S256Point, r: &S256Point) -> BigUint {
+    let mut data = identifier.to_be_bytes().to_vec();
+    data.extend(constant.sec(Some(true)));
+    data.extend(r.sec(Some(true)));
+
+    BigUint::from_bytes_be(&tagged_hash("FROST/pok", &data)) % &*N
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrostError {
+    /// `min_signers` is below 2 or above `max_signers`.
+    InvalidMinSigners,
+    InvalidIdentifier(u32),
+    /// A DKG round did not receive exactly one package from every other
+    /// participant.
+    IncorrectNumberOfPackages,
+    MissingPackage(u32),
+    /// A secret share does not match the sender's polynomial commitment.
+    InvalidSecretShare(u32),
+    InvalidProofOfKnowledge(u32),
+    /// A polynomial commitment has the wrong length, or the signers' nonce
+    /// commitments sum to the point at infinity.
+    InvalidCommitment,
+    /// The signing package does not contain this signer's nonce commitments.
+    MissingCommitment(u32),
+    NotEnoughSigners,
+    /// `aggregate` did not receive exactly one signature share for every
+    /// signer in the signing package.
+    IncorrectNumberOfShares,
+    InvalidSignatureShare(u32),
+}
+
+impl std::fmt::Display for FrostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrostError::InvalidMinSigners => {
+                write!(f, "min_signers must be at least 2 and at most max_signers")
+            }
+            FrostError::InvalidIdentifier(id) => write!(f, "invalid participant identifier {id}"),
+            FrostError::IncorrectNumberOfPackages => {
+                write!(f, "expected one package from every other participant")
+            }
+            FrostError::MissingPackage(id) => write!(f, "missing package from participant {id}"),
+            FrostError::InvalidSecretShare(id) => {
+                write!(
+                    f,
+                    "secret share from participant {id} does not match its commitment"
+                )
+            }
+            FrostError::InvalidProofOfKnowledge(id) => {
+                write!(f, "invalid proof of knowledge from participant {id}")
+            }
+            FrostError::InvalidCommitment => write!(f, "invalid commitment"),
+            FrostError::MissingCommitment(id) => {
+                write!(f, "signing package has no commitments for participant {id}")
+            }
+            FrostError::NotEnoughSigners => write!(f, "not enough signers"),
+            FrostError::IncorrectNumberOfShares => {
+                write!(f, "expected one signature share from every signer")
+            }
+            FrostError::InvalidSignatureShare(id) => {
+                write!(f, "invalid signature share from participant {id}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FrostError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn run_signing(
+        key_packages: &[&KeyPackage],
+        public_key_package: &PublicKeyPackage,
+        message: &[u8],
+        rng: &mut StdRng,
+    ) -> (SigningPackage, BTreeMap<u32, SignatureShare>) {
+        let (nonces, commitments): (Vec<_>, BTreeMap<_, _>) = key_packages
+            .iter()
+            .map(|key_package| {
+                let (nonces, commitments) = commit(key_package, rng);
+                (nonces, (key_package.identifier(), commitments))
+            })
+            .unzip();
+
+        let signing_package = SigningPackage::new(commitments, message).unwrap();
+
+        let shares: BTreeMap<u32, SignatureShare> = nonces
+            .into_iter()
+            .zip(key_packages)
+            .map(|(nonces, key_package)| {
+                let share = sign(&signing_package, nonces, key_package).unwrap();
+                (key_package.identifier(), share)
+            })
+            .collect();
+
+        for (&identifier, share) in &shares {
+            assert!(verify_signature_share(
+                &signing_package,
+                public_key_package,
+                identifier,
+                share
+            ));
+        }
+
+        (signing_package, shares)
+    }
+
+    fn dealer_packages(
+        key: &PrivateKey,
+        rng: &mut StdRng,
+    ) -> (Vec<SecretShare>, Vec<KeyPackage>, PublicKeyPackage) {
+        let (shares, public_key_package) = trusted_dealer_keygen(key, 3, 5, rng).unwrap();
+        let key_packages = shares
+            .iter()
+            .map(|share| KeyPackage::from_secret_share(share).unwrap())
+            .collect();
+
+        (shares, key_packages, public_key_package)
+    }
+
+    #[test]
+    fn test_trusted_dealer_signing() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let key = PrivateKey::new(BigUint::from(123456789u32));
+        let (shares, key_packages, public_key_package) = dealer_packages(&key, &mut rng);
+
+        assert!(shares.iter().all(SecretShare::verify));
+        assert_eq!(
+            public_key_package.group_public_key(),
+            key.x_only_public_key().0
+        );
+
+        // Any three of the five participants can sign.
+        let message = b"3-of-5";
+        for signers in [[0, 1, 2], [1, 3, 4]] {
+            let signers: Vec<&KeyPackage> = signers.iter().map(|&i| &key_packages[i]).collect();
+            let (signing_package, shares) =
+                run_signing(&signers, &public_key_package, message, &mut rng);
+
+            let sig = aggregate(&signing_package, &shares, &public_key_package).unwrap();
+            assert!(
+                public_key_package
+                    .group_public_key()
+                    .verify_schnorr(message, &sig)
+            );
+        }
+    }
+
+    #[test]
+    fn test_odd_group_key() {
+        // 6G has an odd y coordinate, so every share is negated when signing.
+        let mut rng = StdRng::seed_from_u64(2);
+        let key = PrivateKey::new(BigUint::from(6u32));
+        assert!(!key.point().has_even_y());
+
+        let (_, key_packages, public_key_package) = dealer_packages(&key, &mut rng);
+        let signers: Vec<&KeyPackage> = key_packages[2..].iter().collect();
+
+        let message = b"odd group key";
+        let (signing_package, shares) =
+            run_signing(&signers, &public_key_package, message, &mut rng);
+        let sig = aggregate(&signing_package, &shares, &public_key_package).unwrap();
+
+        assert!(key.point().verify_schnorr(message, &sig));
+    }
+
+    #[test]
+    fn test_shares_interpolate_to_secret() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let secret = BigUint::from(987654321u32);
+        let (shares, _) =
+            trusted_dealer_keygen(&PrivateKey::new(secret.clone()), 3, 5, &mut rng).unwrap();
+
+        // Lagrange interpolation at zero over any three shares.
+        let commitments: BTreeMap<u32, SigningCommitments> = [1u32, 3, 5]
+            .iter()
+            .map(|&identifier| {
+                let commitments = SigningCommitments {
+                    hiding: G.clone(),
+                    binding: G.clone(),
+                };
+                (identifier, commitments)
+            })
+            .collect();
+        let signing_package = SigningPackage::new(commitments, b"").unwrap();
+
+        let recovered = [0usize, 2, 4].iter().fold(BigUint::from(0u32), |acc, &i| {
+            let lambda = signing_package.lagrange_coefficient(shares[i].identifier);
+            (acc + lambda * &shares[i].value) % &*N
+        });
+
+        assert_eq!(recovered, secret);
+    }
+
+    #[test]
+    fn test_dkg() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let (min_signers, max_signers) = (2, 3);
+
+        let mut round1_secrets = BTreeMap::new();
+        let mut round1_packages = BTreeMap::new();
+        for identifier in 1..=max_signers {
+            let (secret, package) =
+                dkg_part1(identifier, min_signers, max_signers, &mut rng).unwrap();
+            round1_secrets.insert(identifier, secret);
+            round1_packages.insert(identifier, package);
+        }
+
+        // Everyone sees every package but their own.
+        let others = |identifier: u32| -> BTreeMap<u32, Round1Package> {
+            round1_packages
+                .iter()
+                .filter(|(other, _)| **other != identifier)
+                .map(|(other, package)| (*other, package.clone()))
+                .collect()
+        };
+
+        let mut round2_secrets = BTreeMap::new();
+        let mut outgoing = BTreeMap::new();
+        for (identifier, secret) in round1_secrets {
+            let (round2_secret, packages) = dkg_part2(secret, &others(identifier)).unwrap();
+            round2_secrets.insert(identifier, round2_secret);
+            outgoing.insert(identifier, packages);
+        }
+
+        let mut key_packages = Vec::new();
+        let mut public_key_packages = Vec::new();
+        for (&identifier, round2_secret) in &round2_secrets {
+            let received: BTreeMap<u32, Round2Package> = outgoing
+                .iter()
+                .filter(|(sender, _)| **sender != identifier)
+                .map(|(sender, packages)| (*sender, packages[&identifier].clone()))
+                .collect();
+
+            let (key_package, public_key_package) =
+                dkg_part3(round2_secret, &others(identifier), &received).unwrap();
+            key_packages.push(key_package);
+            public_key_packages.push(public_key_package);
+        }
+
+        // All participants agree on the group key and each other's shares.
+        assert!(
+            public_key_packages
+                .windows(2)
+                .all(|pair| pair[0] == pair[1])
+        );
+        let public_key_package = &public_key_packages[0];
+        for key_package in &key_packages {
+            assert_eq!(
+                public_key_package.verifying_share(key_package.identifier()),
+                Some(key_package.verifying_share())
+            );
+        }
+
+        let message = b"distributed key";
+        let signers: Vec<&KeyPackage> = vec![&key_packages[0], &key_packages[2]];
+        let (signing_package, shares) =
+            run_signing(&signers, public_key_package, message, &mut rng);
+        let sig = aggregate(&signing_package, &shares, public_key_package).unwrap();
+
+        assert!(
+            public_key_package
+                .group_public_key()
+                .verify_schnorr(message, &sig)
+        );
+
+        // A tampered share is caught against the sender's commitment.
+        let secret = &round2_secrets[&1];
+        let mut received: BTreeMap<u32, Round2Package> = outgoing
+            .iter()
+            .filter(|(sender, _)| **sender != 1)
+            .map(|(sender, packages)| (*sender, packages[&1].clone()))
+            .collect();
+        received.get_mut(&3).unwrap().signing_share += 1u32;
+        assert_eq!(
+            dkg_part3(secret, &others(1), &received).unwrap_err(),
+            FrostError::InvalidSecretShare(3)
+        );
+    }
+
+    #[test]
+    fn test_dkg_rejects_bad_proof_of_knowledge() {
+        let mut rng = StdRng::seed_from_u64(5);
+
+        let (secret_1, _) = dkg_part1(1, 2, 2, &mut rng).unwrap();
+        let (_, mut package_2) = dkg_part1(2, 2, 2, &mut rng).unwrap();
+        package_2.proof_of_knowledge.1 += 1u32;
+
+        let packages = BTreeMap::from([(2, package_2)]);
+        assert_eq!(
+            dkg_part2(secret_1, &packages).unwrap_err(),
+            FrostError::InvalidProofOfKnowledge(2)
+        );
+    }
+
+    #[test]
+    fn test_signing_errors() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let key = PrivateKey::new(BigUint::from(42u32));
+        let (shares, key_packages, public_key_package) = dealer_packages(&key, &mut rng);
+
+        let mut tampered = shares[0].clone();
+        tampered.value += 1u32;
+        assert_eq!(
+            KeyPackage::from_secret_share(&tampered).unwrap_err(),
+            FrostError::InvalidSecretShare(1)
+        );
+
+        // Both pass `verify`: an empty commitment with a zero value, and a
+        // constant polynomial, which would make a 1-of-n key.
+        let short_commitments = [
+            (BigUint::from(0u32), vec![]),
+            (BigUint::from(1u32), vec![G.clone()]),
+        ];
+        for (value, commitment) in short_commitments {
+            let share = SecretShare {
+                identifier: 1,
+                value,
+                commitment,
+            };
+            assert_eq!(
+                KeyPackage::from_secret_share(&share).unwrap_err(),
+                FrostError::InvalidCommitment
+            );
+        }
+
+        let mut at_zero = shares[0].clone();
+        at_zero.identifier = 0;
+        assert_eq!(
+            KeyPackage::from_secret_share(&at_zero).unwrap_err(),
+            FrostError::InvalidIdentifier(0)
+        );
+
+        assert_eq!(
+            trusted_dealer_keygen(&key, 1, 3, &mut rng).unwrap_err(),
+            FrostError::InvalidMinSigners
+        );
+
+        // Two signers are not enough for a 3-of-5 key.
+        let (nonces, commitments) = commit(&key_packages[0], &mut rng);
+        let (_, other_commitments) = commit(&key_packages[1], &mut rng);
+        let signing_package = SigningPackage::new(
+            BTreeMap::from([(1, commitments.clone()), (2, other_commitments)]),
+            b"m",
+        )
+        .unwrap();
+        assert_eq!(
+            sign(&signing_package, nonces, &key_packages[0]).unwrap_err(),
+            FrostError::NotEnoughSigners
+        );
+
+        // A bad share is attributed to the signer that sent it.
+        let signers: Vec<&KeyPackage> = key_packages[..3].iter().collect();
+        let (signing_package, mut shares) =
+            run_signing(&signers, &public_key_package, b"m", &mut rng);
+        shares.get_mut(&2).unwrap().share += 1u32;
+
+        assert!(!verify_signature_share(
+            &signing_package,
+            &public_key_package,
+            2,
+            &shares[&2]
+        ));
+        assert_eq!(
+            aggregate(&signing_package, &shares, &public_key_package).unwrap_err(),
+            FrostError::InvalidSignatureShare(2)
+        );
+
+        shares.remove(&2);
+        assert_eq!(
+            aggregate(&signing_package, &shares, &public_key_package).unwrap_err(),
+            FrostError::IncorrectNumberOfShares
+        );
+
+        assert_eq!(
+            SigningPackage::new(BTreeMap::new(), b"m").unwrap_err(),
+            FrostError::NotEnoughSigners
+        );
+        assert_eq!(
+            SigningPackage::new(BTreeMap::from([(0, commitments)]), b"m").unwrap_err(),
+            FrostError::InvalidIdentifier(0)
+        );
+    }
+}
@@ -1,9 +1,20 @@
 pub mod base58;
 pub mod block;
+#[cfg(test)]
+mod conformance_tests;
+pub mod curve;
+pub mod ecdlp;
 mod elliptic_curve;
 mod finite_field;
+pub mod frost;
+pub mod message;
+pub mod musig;
 mod script;
 pub mod secp256k1;
+pub mod small_curve;
+pub mod taproot;
+#[cfg(test)]
+mod test_utils;
 pub mod transaction;
 mod varint;
 
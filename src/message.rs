@@ -0,0 +1,584 @@
+//! Signing and verifying messages with Bitcoin keys.
+//!
+//! Two formats are supported: the legacy BIP137 format that Bitcoin Core's
+//! `signmessage` produces, a recoverable ECDSA signature whose header byte
+//! names the address type, and BIP322, which signs a virtual transaction
+//! spending from the address so that any script can be used.
+
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+use crate::{
+    secp256k1::{PrivateKey, RecoverableSignature, S256Point, Signature, tagged_hash},
+    transaction::{
+        Transaction, Tx, TxIn, TxOut, UtxoSet, hash160, p2pkh_script, p2wpkh_program, p2wpkh_script,
+    },
+    varint::encode_varint,
+};
+
+/// The prefix Bitcoin Core hashes in front of every signed message, so a
+/// message signature can never be mistaken for a transaction signature.
+pub const MESSAGE_MAGIC: &[u8] = b"Bitcoin Signed Message:\n";
+
+/// The kinds of single-key address a BIP137 signature can be tied to. The
+/// address type picks the range of the signature's header byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressType {
+    /// P2PKH for an uncompressed public key (header 27-30).
+    P2pkhUncompressed,
+    /// P2PKH for a compressed public key (header 31-34).
+    P2pkh,
+    /// P2WPKH nested in P2SH (header 35-38).
+    P2shP2wpkh,
+    /// Native P2WPKH (header 39-42).
+    P2wpkh,
+}
+
+impl AddressType {
+    fn header_base(self) -> u8 {
+        match self {
+            AddressType::P2pkhUncompressed => 27,
+            AddressType::P2pkh => 31,
+            AddressType::P2shP2wpkh => 35,
+            AddressType::P2wpkh => 39,
+        }
+    }
+
+    fn from_header(header: u8) -> Option<AddressType> {
+        match header {
+            27..=30 => Some(AddressType::P2pkhUncompressed),
+            31..=34 => Some(AddressType::P2pkh),
+            35..=38 => Some(AddressType::P2shP2wpkh),
+            39..=42 => Some(AddressType::P2wpkh),
+            _ => None,
+        }
+    }
+
+    /// The scriptPubKey of this type of address for `public_key`.
+    pub fn script_pub_key(self, public_key: &S256Point) -> Vec<u8> {
+        let compressed = self != AddressType::P2pkhUncompressed;
+        let pub_key_hash = hash160(&public_key.sec(Some(compressed)));
+
+        match self {
+            AddressType::P2pkhUncompressed | AddressType::P2pkh => p2pkh_script(&pub_key_hash),
+            AddressType::P2shP2wpkh => {
+                let script_hash = hash160(&p2wpkh_script(&pub_key_hash));
+
+                let mut script = vec![0xa9, 0x14];
+                script.extend_from_slice(&script_hash);
+                script.push(0x87);
+
+                script
+            }
+            AddressType::P2wpkh => p2wpkh_script(&pub_key_hash),
+        }
+    }
+}
+
+/// The reason a message signature was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageError {
+    /// The header byte of a BIP137 signature is outside 27-42.
+    InvalidHeader,
+    /// No public key could be recovered from the signature.
+    InvalidSignature,
+    /// The signature is valid, but for a different address.
+    AddressMismatch,
+    /// The address is of a kind this proof format cannot be used with.
+    UnsupportedAddress,
+    /// A BIP322 proof is not well formed.
+    MalformedProof,
+    /// The BIP322 `to_sign` transaction does not verify.
+    InvalidProof,
+}
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            MessageError::InvalidHeader => "message signature has an invalid header byte",
+            MessageError::InvalidSignature => "no public key can be recovered from the signature",
+            MessageError::AddressMismatch => "message was signed by a different address",
+            MessageError::UnsupportedAddress => "address type is not supported by this format",
+            MessageError::MalformedProof => "BIP322 proof is malformed",
+            MessageError::InvalidProof => "BIP322 proof does not verify",
+        };
+
+        write!(f, "{}", message)
+    }
+}
+
+impl std::error::Error for MessageError {}
+
+/// The hash signed by `sign_message`: the double SHA-256 of the magic prefix
+/// and the message, each preceded by its length as a varint.
+pub fn signed_message_hash(message: &[u8]) -> BigUint {
+    let mut serialized = Vec::new();
+    encode_varint(&mut serialized, MESSAGE_MAGIC.len() as u64);
+    serialized.extend_from_slice(MESSAGE_MAGIC);
+    encode_varint(&mut serialized, message.len() as u64);
+    serialized.extend_from_slice(message);
+
+    BigUint::from_bytes_be(&Sha256::digest(Sha256::digest(serialized)))
+}
+
+/// Signs `message` in the legacy "Bitcoin Signed Message" format (BIP137).
+///
+/// The result is the 65-byte compact signature; wallets usually show it
+/// base64 encoded.
+pub fn sign_message(key: &PrivateKey, message: &[u8], address_type: AddressType) -> [u8; 65] {
+    let recoverable = key.sign_recoverable(&signed_message_hash(message));
+
+    let mut serialized = recoverable.serialize_compact(true);
+    serialized[0] = address_type.header_base() + recoverable.recovery_id;
+
+    serialized
+}
+
+/// Checks a BIP137 signature against the address with `script_pub_key`.
+///
+/// The public key is recovered from the signature, and the header byte says
+/// which kind of address to derive from it.
+pub fn verify_message(
+    script_pub_key: &[u8],
+    message: &[u8],
+    signature: &[u8; 65],
+) -> Result<(), MessageError> {
+    let header = signature[0];
+    let address_type = AddressType::from_header(header).ok_or(MessageError::InvalidHeader)?;
+
+    let recoverable = RecoverableSignature {
+        signature: Signature {
+            r: BigUint::from_bytes_be(&signature[1..33]),
+            s: BigUint::from_bytes_be(&signature[33..]),
+        },
+        recovery_id: (header - 27) & 3,
+    };
+    let public_key = recoverable
+        .recover(&signed_message_hash(message))
+        .map_err(|_| MessageError::InvalidSignature)?;
+
+    if address_type.script_pub_key(&public_key) != script_pub_key {
+        return Err(MessageError::AddressMismatch);
+    }
+
+    Ok(())
+}
+
+/// The BIP322 message hash: a tagged hash with tag "BIP0322-signed-message".
+pub fn bip322_message_hash(message: &[u8]) -> [u8; 32] {
+    tagged_hash("BIP0322-signed-message", message)
+}
+
+/// Builds the virtual BIP322 `to_spend` transaction, which pays to the
+/// address being proven and commits to the message in its scriptSig.
+pub fn bip322_to_spend(script_pub_key: &[u8], message: &[u8]) -> Tx {
+    // OP_0 PUSH32 <message_hash>
+    let mut script_sig = vec![0x00, 0x20];
+    script_sig.extend_from_slice(&bip322_message_hash(message));
+
+    Tx {
+        version: 0,
+        tx_ins: vec![TxIn {
+            prev_tx_hash: [0; 32],
+            prev_index: 0xffffffff,
+            script_sig,
+            sequence: 0,
+            witness: vec![],
+        }],
+        tx_outs: vec![TxOut {
+            amount: 0,
+            script_pub_key: script_pub_key.to_vec(),
+        }],
+        locktime: 0,
+    }
+}
+
+/// Builds the unsigned BIP322 `to_sign` transaction, which spends the only
+/// output of `to_spend` to an `OP_RETURN` output.
+pub fn bip322_to_sign(to_spend: &Tx) -> Tx {
+    Tx {
+        version: 0,
+        tx_ins: vec![TxIn {
+            prev_tx_hash: to_spend.hash(),
+            prev_index: 0,
+            script_sig: vec![],
+            sequence: 0,
+            witness: vec![],
+        }],
+        tx_outs: vec![TxOut {
+            amount: 0,
+            script_pub_key: vec![0x6a],
+        }],
+        locktime: 0,
+    }
+}
+
+/// Produces a signed BIP322 `to_sign` transaction, the "full" proof format.
+///
+/// P2PKH and native P2WPKH addresses are supported; `Tx::verify` does not
+/// evaluate P2SH, so nested P2WPKH addresses cannot be proven this way.
+pub fn sign_bip322_full(
+    key: &PrivateKey,
+    message: &[u8],
+    address_type: AddressType,
+) -> Result<Tx, MessageError> {
+    let script_pub_key = address_type.script_pub_key(key.point());
+    let to_spend = bip322_to_spend(&script_pub_key, message);
+    let mut to_sign = bip322_to_sign(&to_spend);
+
+    match address_type {
+        AddressType::P2pkhUncompressed | AddressType::P2pkh => {
+            let z = to_sign.sig_hash(0, &script_pub_key);
+            let sec = key.point().sec(Some(address_type == AddressType::P2pkh));
+
+            let mut script_sig = Vec::new();
+            for item in [signature_with_hash_type(key, &z), sec] {
+                script_sig.push(item.len() as u8);
+                script_sig.extend_from_slice(&item);
+            }
+            to_sign.tx_ins[0].script_sig = script_sig;
+        }
+        AddressType::P2wpkh => {
+            let pub_key_hash = p2wpkh_program(&script_pub_key).unwrap(); // built as P2WPKH just above
+            let z = to_sign.sig_hash_segwit_v0(0, &p2pkh_script(pub_key_hash), 0);
+
+            to_sign.tx_ins[0].witness = vec![
+                signature_with_hash_type(key, &z),
+                key.point().sec(Some(true)),
+            ];
+        }
+        AddressType::P2shP2wpkh => return Err(MessageError::UnsupportedAddress),
+    }
+
+    Ok(to_sign)
+}
+
+/// Checks a full BIP322 proof: `to_sign` must be the virtual transaction for
+/// this address and message, and must verify when spending `to_spend`.
+///
+/// Proofs of funds, which add further inputs, are not supported.
+pub fn verify_bip322_full(
+    script_pub_key: &[u8],
+    message: &[u8],
+    to_sign: &Tx,
+) -> Result<(), MessageError> {
+    let to_spend = bip322_to_spend(script_pub_key, message);
+
+    let [tx_in] = to_sign.tx_ins.as_slice() else {
+        return Err(MessageError::MalformedProof);
+    };
+    let [tx_out] = to_sign.tx_outs.as_slice() else {
+        return Err(MessageError::MalformedProof);
+    };
+
+    if !(to_sign.version == 0 || to_sign.version == 2)
+        || tx_in.prev_tx_hash != to_spend.hash()
+        || tx_in.prev_index != 0
+        || tx_out.amount != 0
+        || tx_out.script_pub_key != [0x6a]
+    {
+        return Err(MessageError::MalformedProof);
+    }
+
+    let mut utxos = UtxoSet::new();
+    utxos.insert((to_spend.hash(), 0), to_spend.tx_outs[0].clone());
+
+    if !to_sign.verify(&utxos) {
+        return Err(MessageError::InvalidProof);
+    }
+
+    Ok(())
+}
+
+/// Produces a "simple" BIP322 proof: the witness stack of the full proof,
+/// serialized as a count of items followed by length-prefixed items.
+///
+/// Only native P2WPKH addresses are supported, since the simple format
+/// has nowhere to put a scriptSig.
+pub fn sign_bip322_simple(
+    key: &PrivateKey,
+    message: &[u8],
+    address_type: AddressType,
+) -> Result<Vec<u8>, MessageError> {
+    if address_type != AddressType::P2wpkh {
+        return Err(MessageError::UnsupportedAddress);
+    }
+
+    let to_sign = sign_bip322_full(key, message, address_type)?;
+
+    let witness = &to_sign.tx_ins[0].witness;
+    let mut serialized = Vec::new();
+    encode_varint(&mut serialized, witness.len() as u64);
+    for item in witness {
+        encode_varint(&mut serialized, item.len() as u64);
+        serialized.extend_from_slice(item);
+    }
+
+    Ok(serialized)
+}
+
+/// Checks a simple BIP322 proof by rebuilding the full proof around its
+/// witness stack.
+pub fn verify_bip322_simple(
+    script_pub_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), MessageError> {
+    let witness = parse_witness(signature).ok_or(MessageError::MalformedProof)?;
+
+    let mut to_sign = bip322_to_sign(&bip322_to_spend(script_pub_key, message));
+    to_sign.tx_ins[0].witness = witness;
+
+    verify_bip322_full(script_pub_key, message, &to_sign)
+}
+
+fn signature_with_hash_type(key: &PrivateKey, z: &BigUint) -> Vec<u8> {
+    let mut signature = key.sign(z).der();
+    signature.push(0x01); // SIGHASH_ALL
+
+    signature
+}
+
+/// Parses a witness stack serialized as in `sign_bip322_simple`. Items
+/// longer than 252 bytes, which need a multi-byte varint, are not expected in
+/// a single-key witness and are rejected.
+fn parse_witness(serialized: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let (&count, mut rest) = serialized.split_first()?;
+    if count >= 0xfd {
+        return None;
+    }
+
+    let mut witness = Vec::new();
+    for _ in 0..count {
+        let (&len, tail) = rest.split_first()?;
+        if len >= 0xfd || tail.len() < len as usize {
+            return None;
+        }
+
+        let (item, tail) = tail.split_at(len as usize);
+        witness.push(item.to_vec());
+        rest = tail;
+    }
+
+    rest.is_empty().then_some(witness)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::hex_to_bytes;
+
+    fn hex_to_biguint(hex: &str) -> BigUint {
+        BigUint::parse_bytes(hex.as_bytes(), 16).unwrap()
+    }
+
+    fn txid(tx: &Tx) -> String {
+        tx.hash()
+            .iter()
+            .rev()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    // The key and address used by the BIP322 test vectors: WIF
+    // L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k and address
+    // bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l.
+    fn bip322_key() -> PrivateKey {
+        PrivateKey::new(hex_to_biguint(
+            "bb051cd0dda0246f33c5a9e133ebd8e7bc02a92af6c41adc131ccd7826c5b004",
+        ))
+    }
+
+    fn bip322_script_pub_key() -> Vec<u8> {
+        hex_to_bytes("00142b05d564e6a7a33c087f16e0f730d1440123799d")
+    }
+
+    #[test]
+    fn test_signed_message_hash() {
+        // Computed independently in Python from the definition.
+        assert_eq!(
+            signed_message_hash(b"Hello World"),
+            hex_to_biguint("a7af0baad5ae99b97fc69b3a0d1abcf3ef17f131cc4776e1bc11933ec8550f49")
+        );
+    }
+
+    #[test]
+    fn test_sign_and_verify_message() {
+        let key = PrivateKey::new(BigUint::from(12345u32));
+        let message = b"I control this address";
+
+        for address_type in [
+            AddressType::P2pkhUncompressed,
+            AddressType::P2pkh,
+            AddressType::P2shP2wpkh,
+            AddressType::P2wpkh,
+        ] {
+            let signature = sign_message(&key, message, address_type);
+            let header_base = address_type.header_base();
+            assert!((header_base..header_base + 4).contains(&signature[0]));
+
+            let script_pub_key = address_type.script_pub_key(key.point());
+            assert_eq!(verify_message(&script_pub_key, message, &signature), Ok(()));
+            assert_eq!(
+                verify_message(&script_pub_key, b"another message", &signature),
+                Err(MessageError::AddressMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_message_header_selects_address_type() {
+        let key = PrivateKey::new(BigUint::from(12345u32));
+        let message = b"I control this address";
+        let signature = sign_message(&key, message, AddressType::P2pkh);
+
+        // The same signature relabelled as uncompressed points at a different address.
+        let mut relabelled = signature;
+        relabelled[0] -= 4;
+        let script_pub_key = AddressType::P2pkh.script_pub_key(key.point());
+        assert_eq!(
+            verify_message(&script_pub_key, message, &relabelled),
+            Err(MessageError::AddressMismatch)
+        );
+
+        let mut bad_header = signature;
+        bad_header[0] = 43;
+        assert_eq!(
+            verify_message(&script_pub_key, message, &bad_header),
+            Err(MessageError::InvalidHeader)
+        );
+    }
+
+    #[test]
+    fn test_bip322_message_hash() {
+        assert_eq!(
+            bip322_message_hash(b""),
+            hex_to_bytes("c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1")[..]
+        );
+        assert_eq!(
+            bip322_message_hash(b"Hello World"),
+            hex_to_bytes("f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a")[..]
+        );
+    }
+
+    #[test]
+    fn test_bip322_transaction_ids() {
+        let script_pub_key = bip322_script_pub_key();
+        assert_eq!(
+            AddressType::P2wpkh.script_pub_key(bip322_key().point()),
+            script_pub_key
+        );
+
+        let cases = [
+            (
+                &b""[..],
+                "c5680aa69bb8d860bf82d4e9cd3504b55dde018de765a91bb566283c545a99a7",
+                "1e9654e951a5ba44c8604c4de6c67fd78a27e81dcadcfe1edf638ba3aaebaed6",
+            ),
+            (
+                &b"Hello World"[..],
+                "b79d196740ad5217771c1098fc4a4b51e0535c32236c71f1ea4d61a2d603352b",
+                "88737ae86f2077145f93cc4b153ae9a1cb8d56afa511988c149c5c8c9d93bddf",
+            ),
+        ];
+
+        for (message, to_spend_id, to_sign_id) in cases {
+            let to_spend = bip322_to_spend(&script_pub_key, message);
+            assert_eq!(txid(&to_spend), to_spend_id);
+            assert_eq!(txid(&bip322_to_sign(&to_spend)), to_sign_id);
+        }
+    }
+
+    #[test]
+    fn test_bip322_simple_vectors() {
+        let script_pub_key = bip322_script_pub_key();
+
+        // The base64 signatures from BIP322, decoded to hex.
+        let cases = [
+            (
+                &b""[..],
+                "024730440220336801010aaf657d79662cac98a990a43ac6f376af2c84f8f76401ccb9d0231602201693a4e683db4a91944ca5cb11527840366daf583a2c695fccf8e93483b52e34012102c7f12003196442943d8588e01aee840423cc54fc1521526a3b85c2b0cbd58872",
+            ),
+            (
+                &b"Hello World"[..],
+                "0247304402206517c8637a7bfc3a154edcba6196d64bbd5b73955cb7da7d1626bcdde466c364022022bf10d19fc0bb69b4596e306b362acaa835293cf693bb176f7324b531f5afec012102c7f12003196442943d8588e01aee840423cc54fc1521526a3b85c2b0cbd58872",
+            ),
+        ];
+
+        for (message, signature) in cases {
+            let signature = hex_to_bytes(signature);
+            assert_eq!(
+                verify_bip322_simple(&script_pub_key, message, &signature),
+                Ok(())
+            );
+        }
+
+        // Each signature only proves its own message.
+        let signature = hex_to_bytes(cases[0].1);
+        assert_eq!(
+            verify_bip322_simple(&script_pub_key, b"Hello World", &signature),
+            Err(MessageError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn test_bip322_simple_round_trip() {
+        let key = bip322_key();
+        let script_pub_key = bip322_script_pub_key();
+
+        let signature = sign_bip322_simple(&key, b"Hello World", AddressType::P2wpkh).unwrap();
+        assert_eq!(
+            verify_bip322_simple(&script_pub_key, b"Hello World", &signature),
+            Ok(())
+        );
+
+        assert_eq!(
+            sign_bip322_simple(&key, b"Hello World", AddressType::P2pkh),
+            Err(MessageError::UnsupportedAddress)
+        );
+        assert_eq!(
+            verify_bip322_simple(&script_pub_key, b"Hello World", &signature[1..]),
+            Err(MessageError::MalformedProof)
+        );
+    }
+
+    #[test]
+    fn test_bip322_full_round_trip() {
+        let key = PrivateKey::new(BigUint::from(12345u32));
+        let message = b"I control this address";
+
+        for address_type in [
+            AddressType::P2pkhUncompressed,
+            AddressType::P2pkh,
+            AddressType::P2wpkh,
+        ] {
+            let script_pub_key = address_type.script_pub_key(key.point());
+            let to_sign = sign_bip322_full(&key, message, address_type).unwrap();
+
+            assert_eq!(
+                verify_bip322_full(&script_pub_key, message, &to_sign),
+                Ok(())
+            );
+            assert_eq!(
+                verify_bip322_full(&script_pub_key, b"another message", &to_sign),
+                Err(MessageError::MalformedProof)
+            );
+
+            let other_key = PrivateKey::new(BigUint::from(54321u32));
+            let other_script_pub_key = address_type.script_pub_key(other_key.point());
+            let mut forged = bip322_to_sign(&bip322_to_spend(&other_script_pub_key, message));
+            forged.tx_ins[0].script_sig = to_sign.tx_ins[0].script_sig.clone();
+            forged.tx_ins[0].witness = to_sign.tx_ins[0].witness.clone();
+            assert_eq!(
+                verify_bip322_full(&other_script_pub_key, message, &forged),
+                Err(MessageError::InvalidProof)
+            );
+        }
+
+        assert_eq!(
+            sign_bip322_full(&key, message, AddressType::P2shP2wpkh).unwrap_err(),
+            MessageError::UnsupportedAddress
+        );
+    }
+}
@@ -0,0 +1,751 @@
+//! MuSig2 multi-signatures (BIP327).
+//!
+//! `n` signers aggregate their public keys into a single x-only key and then
+//! jointly produce one BIP340 signature for it, so an n-of-n output looks
+//! like any other single-key Taproot output on chain.
+//!
+//! Signing takes two rounds. First every signer generates a nonce pair with
+//! `nonce_gen` and shares the `PubNonce`; the nonces are combined with
+//! `nonce_agg`. Then each signer builds a `Session` for the message, produces
+//! a partial signature, and the partial signatures are aggregated.
+
+use num_bigint::BigUint;
+
+use crate::secp256k1::{
+    G, N, PrivateKey, S256Point, SchnorrSignature, XOnlyPublicKey, schnorr_challenge, tagged_hash,
+    tap_tweak_hash, to_32_bytes,
+};
+
+/// The public key aggregation state: the aggregate point `Q` plus the
+/// accumulated sign flips (`gacc`) and tweaks (`tacc`) applied to it.
+#[derive(Debug, Clone)]
+pub struct KeyAggContext {
+    pubkeys: Vec<[u8; 33]>,
+    list_hash: [u8; 32],
+    second_key: [u8; 33],
+    q: S256Point,
+    gacc: BigUint,
+    tacc: BigUint,
+}
+
+impl KeyAggContext {
+    /// Aggregates `pubkeys` in the order given. Use `key_sort` first if the
+    /// signers have not agreed on an order.
+    pub fn new(pubkeys: &[S256Point]) -> Result<Self, MuSigError> {
+        let pubkeys = pubkeys
+            .iter()
+            .map(compressed)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let list_hash = tagged_hash("KeyAgg list", &pubkeys.concat());
+        // The second distinct key gets a coefficient of 1, which saves one
+        // scalar multiplication without weakening the scheme.
+        let second_key = pubkeys
+            .iter()
+            .find(|pk| **pk != pubkeys[0])
+            .copied()
+            .unwrap_or([0u8; 33]);
+
+        let mut ctx = KeyAggContext {
+            pubkeys,
+            list_hash,
+            second_key,
+            q: S256Point::infinity().unwrap(),
+            gacc: BigUint::from(1u32),
+            tacc: BigUint::from(0u32),
+        };
+
+        let mut q = S256Point::infinity().unwrap();
+        for pk in &ctx.pubkeys {
+            let point = S256Point::parse(pk).unwrap();
+            q = q + point * ctx.coefficient(pk);
+        }
+
+        if q.is_at_infinity() {
+            return Err(MuSigError::Infinity);
+        }
+
+        ctx.q = q;
+
+        Ok(ctx)
+    }
+
+    /// The aggregate public key that the final signature verifies against.
+    pub fn aggregated_key(&self) -> XOnlyPublicKey {
+        XOnlyPublicKey::from_point(&self.q).0
+    }
+
+    /// The aggregate point, including its y coordinate, which plain tweaks
+    /// (e.g. BIP32 derivation) depend on.
+    pub fn aggregated_point(&self) -> &S256Point {
+        &self.q
+    }
+
+    fn coefficient(&self, pk: &[u8; 33]) -> BigUint {
+        if *pk == self.second_key {
+            return BigUint::from(1u32);
+        }
+
+        let mut data = self.list_hash.to_vec();
+        data.extend_from_slice(pk);
+
+        BigUint::from_bytes_be(&tagged_hash("KeyAgg coefficient", &data)) % &*N
+    }
+
+    /// Adds `tweak * G` to the aggregate key. An x-only tweak first negates
+    /// the key if its y coordinate is odd, as BIP341 tweaking does; a plain
+    /// tweak is what BIP32 derivation uses.
+    pub fn apply_tweak(&self, tweak: &BigUint, is_xonly: bool) -> Result<Self, MuSigError> {
+        if *tweak >= *N {
+            return Err(MuSigError::InvalidTweak);
+        }
+
+        let negate = is_xonly && !self.q.has_even_y();
+        let (q, g) = if negate {
+            (-&self.q, &*N - 1u32)
+        } else {
+            (self.q.clone(), BigUint::from(1u32))
+        };
+
+        let q = q + &*G * tweak;
+        if q.is_at_infinity() {
+            return Err(MuSigError::Infinity);
+        }
+
+        Ok(KeyAggContext {
+            q,
+            gacc: (&g * &self.gacc) % &*N,
+            tacc: (tweak + g * &self.tacc) % &*N,
+            ..self.clone()
+        })
+    }
+
+    /// Applies the BIP341 Taproot tweak, so the aggregate key becomes the
+    /// internal key of a P2TR output and signatures verify against its output
+    /// key.
+    pub fn tap_tweak(&self, merkle_root: Option<&[u8; 32]>) -> Result<Self, MuSigError> {
+        let tweak = tap_tweak_hash(&self.aggregated_key(), merkle_root)
+            .map_err(|_| MuSigError::InvalidTweak)?;
+
+        self.apply_tweak(&tweak, true)
+    }
+}
+
+/// Sorts public keys by their compressed encoding, giving every signer the
+/// same order without further coordination.
+pub fn key_sort(pubkeys: &[S256Point]) -> Vec<S256Point> {
+    let mut sorted = pubkeys.to_vec();
+    sorted.sort_by_key(|point| point.sec(Some(true)));
+
+    sorted
+}
+
+/// A signer's secret nonce pair.
+///
+/// It is deliberately not `Clone`: `Session::sign` consumes it, because
+/// signing two different messages with the same nonce reveals the secret key.
+#[derive(Debug)]
+pub struct SecNonce {
+    k1: BigUint,
+    k2: BigUint,
+    public_key: S256Point,
+}
+
+impl SecNonce {
+    /// Parses the 97-byte `k1 || k2 || pk` encoding BIP327 uses.
+    pub fn parse(serialized: &[u8]) -> Result<Self, MuSigError> {
+        if serialized.len() != 97 {
+            return Err(MuSigError::InvalidNonce);
+        }
+
+        let public_key =
+            S256Point::parse(&serialized[64..]).map_err(|_| MuSigError::InvalidPublicKey)?;
+
+        Ok(SecNonce {
+            k1: BigUint::from_bytes_be(&serialized[..32]),
+            k2: BigUint::from_bytes_be(&serialized[32..64]),
+            public_key,
+        })
+    }
+}
+
+/// The public half of a signer's nonce pair, sent to the other signers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PubNonce {
+    r1: S256Point,
+    r2: S256Point,
+}
+
+impl PubNonce {
+    pub fn serialize(&self) -> Vec<u8> {
+        [self.r1.sec(Some(true)), self.r2.sec(Some(true))].concat()
+    }
+
+    pub fn parse(serialized: &[u8]) -> Result<Self, MuSigError> {
+        if serialized.len() != 66 {
+            return Err(MuSigError::InvalidNonce);
+        }
+
+        let parse = |bytes| S256Point::parse(bytes).map_err(|_| MuSigError::InvalidNonce);
+
+        Ok(PubNonce {
+            r1: parse(&serialized[..33])?,
+            r2: parse(&serialized[33..])?,
+        })
+    }
+}
+
+/// The sum of all signers' public nonces. Either point may be at infinity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggNonce {
+    r1: S256Point,
+    r2: S256Point,
+}
+
+impl AggNonce {
+    pub fn serialize(&self) -> Vec<u8> {
+        [compressed_ext(&self.r1), compressed_ext(&self.r2)].concat()
+    }
+
+    pub fn parse(serialized: &[u8]) -> Result<Self, MuSigError> {
+        if serialized.len() != 66 {
+            return Err(MuSigError::InvalidNonce);
+        }
+
+        Ok(AggNonce {
+            r1: parse_ext(&serialized[..33])?,
+            r2: parse_ext(&serialized[33..])?,
+        })
+    }
+}
+
+/// Generates a nonce pair for `signer`.
+///
+/// `rand` must be fresh randomness for every call; the other inputs are
+/// optional and only add defence in depth against a weak random source.
+///
+/// Returns `None` if either nonce is zero, which needs a hash output that is
+/// a multiple of N; as in BIP340 signing, it is treated as a failure.
+pub fn nonce_gen(
+    rand: &[u8; 32],
+    signer: &PrivateKey,
+    aggregated_key: Option<&XOnlyPublicKey>,
+    msg: Option<&[u8]>,
+    extra_in: Option<&[u8]>,
+) -> Option<(SecNonce, PubNonce)> {
+    let public_key = signer.point().clone();
+    let pk = public_key.sec(Some(true));
+
+    let mut rand_mix = to_32_bytes(signer.secret());
+    for (byte, mask) in rand_mix.iter_mut().zip(tagged_hash("MuSig/aux", rand)) {
+        *byte ^= mask;
+    }
+
+    let aggpk = aggregated_key
+        .map(|key| key.serialize().to_vec())
+        .unwrap_or_default();
+    let msg_prefixed = match msg {
+        Some(msg) => [&[1u8][..], &(msg.len() as u64).to_be_bytes(), msg].concat(),
+        None => vec![0],
+    };
+    let extra_in = extra_in.unwrap_or_default();
+
+    let mut data = rand_mix.to_vec();
+    data.push(pk.len() as u8);
+    data.extend_from_slice(&pk);
+    data.push(aggpk.len() as u8);
+    data.extend_from_slice(&aggpk);
+    data.extend_from_slice(&msg_prefixed);
+    data.extend_from_slice(&(extra_in.len() as u32).to_be_bytes());
+    data.extend_from_slice(extra_in);
+
+    let [k1, k2] = [0u8, 1u8].map(|i| {
+        let mut data = data.clone();
+        data.push(i);
+        BigUint::from_bytes_be(&tagged_hash("MuSig/nonce", &data)) % &*N
+    });
+
+    if k1 == BigUint::from(0u32) || k2 == BigUint::from(0u32) {
+        return None;
+    }
+
+    let pubnonce = PubNonce {
+        r1: S256Point::mul_generator(&k1),
+        r2: S256Point::mul_generator(&k2),
+    };
+
+    Some((SecNonce { k1, k2, public_key }, pubnonce))
+}
+
+/// Sums the signers' public nonces.
+pub fn nonce_agg(pubnonces: &[PubNonce]) -> AggNonce {
+    let mut r1 = S256Point::infinity().unwrap();
+    let mut r2 = S256Point::infinity().unwrap();
+
+    for pubnonce in pubnonces {
+        r1 = r1 + &pubnonce.r1;
+        r2 = r2 + &pubnonce.r2;
+    }
+
+    AggNonce { r1, r2 }
+}
+
+/// A single signer's contribution to the final signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialSignature {
+    pub s: BigUint,
+}
+
+impl PartialSignature {
+    pub fn serialize(&self) -> [u8; 32] {
+        to_32_bytes(&self.s)
+    }
+
+    /// Range checks are left to verification and aggregation, which reject
+    /// `s >= N`.
+    pub fn parse(serialized: &[u8; 32]) -> Self {
+        PartialSignature {
+            s: BigUint::from_bytes_be(serialized),
+        }
+    }
+}
+
+/// The values every signer derives from the aggregate nonce, the aggregate
+/// key and the message.
+#[derive(Debug, Clone)]
+pub struct Session {
+    key_agg_ctx: KeyAggContext,
+    b: BigUint,
+    r: S256Point,
+    e: BigUint,
+}
+
+impl Session {
+    pub fn new(key_agg_ctx: &KeyAggContext, aggnonce: &AggNonce, msg: &[u8]) -> Self {
+        let aggregated_key = key_agg_ctx.aggregated_key().serialize();
+
+        let mut data = aggnonce.serialize();
+        data.extend_from_slice(&aggregated_key);
+        data.extend_from_slice(msg);
+        let b = BigUint::from_bytes_be(&tagged_hash("MuSig/noncecoef", &data)) % &*N;
+
+        let r = &aggnonce.r1 + &aggnonce.r2 * &b;
+        // If the nonces cancel out, G stands in for R. This cannot be forced
+        // by a dishonest signer without also making the signature invalid.
+        let r = if r.is_at_infinity() { G.clone() } else { r };
+
+        let e = schnorr_challenge(&r.x_only(), &aggregated_key, msg);
+
+        Session {
+            key_agg_ctx: key_agg_ctx.clone(),
+            b,
+            r,
+            e,
+        }
+    }
+
+    /// Produces `signer`'s partial signature, consuming its secret nonce.
+    pub fn sign(
+        &self,
+        secnonce: SecNonce,
+        signer: &PrivateKey,
+    ) -> Result<PartialSignature, MuSigError> {
+        let in_range = |k: &BigUint| *k > BigUint::from(0u32) && *k < *N;
+        if !in_range(&secnonce.k1) || !in_range(&secnonce.k2) {
+            return Err(MuSigError::InvalidNonce);
+        }
+
+        if signer.point() != &secnonce.public_key {
+            return Err(MuSigError::KeyMismatch);
+        }
+
+        let a = self.session_coefficient(signer.point())?;
+
+        let (k1, k2) = if self.r.has_even_y() {
+            (secnonce.k1, secnonce.k2)
+        } else {
+            (&*N - secnonce.k1, &*N - secnonce.k2)
+        };
+
+        let d = (self.g() * &self.key_agg_ctx.gacc * signer.secret()) % &*N;
+        let s = (k1 + &self.b * k2 + &self.e * a * d) % &*N;
+
+        Ok(PartialSignature { s })
+    }
+
+    /// Checks one signer's partial signature, so a misbehaving signer can be
+    /// identified before aggregation.
+    pub fn verify_partial(
+        &self,
+        psig: &PartialSignature,
+        pubnonce: &PubNonce,
+        public_key: &S256Point,
+    ) -> bool {
+        if psig.s >= *N {
+            return false;
+        }
+
+        let Ok(a) = self.session_coefficient(public_key) else {
+            return false;
+        };
+
+        let r_effective = &pubnonce.r1 + &pubnonce.r2 * &self.b;
+        let r_effective = if self.r.has_even_y() {
+            r_effective
+        } else {
+            -r_effective
+        };
+
+        let g = (self.g() * &self.key_agg_ctx.gacc) % &*N;
+        let expected = r_effective + public_key * ((&self.e * a * g) % &*N);
+
+        &*G * &psig.s == expected
+    }
+
+    /// Combines all partial signatures into a BIP340 signature for the
+    /// (tweaked) aggregate key.
+    pub fn aggregate(&self, psigs: &[PartialSignature]) -> Result<SchnorrSignature, MuSigError> {
+        let mut s = BigUint::from(0u32);
+
+        for (index, psig) in psigs.iter().enumerate() {
+            if psig.s >= *N {
+                return Err(MuSigError::InvalidPartialSignature(index));
+            }
+
+            s += &psig.s;
+        }
+
+        let s = (s + &self.e * self.g() * &self.key_agg_ctx.tacc) % &*N;
+
+        Ok(SchnorrSignature {
+            r: self.r.x_num().unwrap().clone(),
+            s,
+        })
+    }
+
+    fn session_coefficient(&self, public_key: &S256Point) -> Result<BigUint, MuSigError> {
+        let pk = compressed(public_key)?;
+
+        if !self.key_agg_ctx.pubkeys.contains(&pk) {
+            return Err(MuSigError::UnknownSigner);
+        }
+
+        Ok(self.key_agg_ctx.coefficient(&pk))
+    }
+
+    // The sign flip that maps the aggregate key to its even-y x-only form.
+    fn g(&self) -> BigUint {
+        if self.key_agg_ctx.q.has_even_y() {
+            BigUint::from(1u32)
+        } else {
+            &*N - 1u32
+        }
+    }
+}
+
+fn compressed(point: &S256Point) -> Result<[u8; 33], MuSigError> {
+    point
+        .sec(Some(true))
+        .try_into()
+        .map_err(|_| MuSigError::InvalidPublicKey)
+}
+
+// The point at infinity is encoded as 33 zero bytes in an aggregate nonce.
+fn compressed_ext(point: &S256Point) -> Vec<u8> {
+    if point.is_at_infinity() {
+        return vec![0u8; 33];
+    }
+
+    point.sec(Some(true))
+}
+
+fn parse_ext(serialized: &[u8]) -> Result<S256Point, MuSigError> {
+    if serialized.iter().all(|&byte| byte == 0) {
+        return Ok(S256Point::infinity().unwrap());
+    }
+
+    S256Point::parse(serialized).map_err(|_| MuSigError::InvalidNonce)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MuSigError {
+    InvalidPublicKey,
+    /// A nonce is malformed, off the curve or out of range.
+    InvalidNonce,
+    /// The aggregate key, or a tweak of it, is the point at infinity.
+    Infinity,
+    /// A tweak is not smaller than the group order.
+    InvalidTweak,
+    /// The secret nonce was generated for a different key.
+    KeyMismatch,
+    /// The public key is not one of the aggregated keys.
+    UnknownSigner,
+    /// The partial signature from the signer at this index is out of range.
+    InvalidPartialSignature(usize),
+}
+
+impl std::fmt::Display for MuSigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MuSigError::InvalidPublicKey => write!(f, "invalid public key"),
+            MuSigError::InvalidNonce => write!(f, "invalid nonce"),
+            MuSigError::Infinity => write!(f, "aggregate key is the point at infinity"),
+            MuSigError::InvalidTweak => write!(f, "tweak is not smaller than the group order"),
+            MuSigError::KeyMismatch => write!(f, "secret nonce belongs to a different key"),
+            MuSigError::UnknownSigner => write!(f, "public key is not part of the aggregate"),
+            MuSigError::InvalidPartialSignature(index) => {
+                write!(f, "partial signature {index} is out of range")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MuSigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::hex_to_bytes;
+
+    fn point(hex: &str) -> S256Point {
+        S256Point::parse(&hex_to_bytes(hex)).unwrap()
+    }
+
+    fn signers() -> Vec<PrivateKey> {
+        [11u32, 22, 33]
+            .map(|secret| PrivateKey::new(BigUint::from(secret)))
+            .to_vec()
+    }
+
+    // Runs both rounds for all `signers` and returns the final signature.
+    fn sign_all(signers: &[PrivateKey], ctx: &KeyAggContext, msg: &[u8]) -> SchnorrSignature {
+        let (secnonces, pubnonces): (Vec<_>, Vec<_>) = signers
+            .iter()
+            .enumerate()
+            .map(|(i, signer)| {
+                nonce_gen(
+                    &[i as u8; 32],
+                    signer,
+                    Some(&ctx.aggregated_key()),
+                    Some(msg),
+                    None,
+                )
+                .unwrap()
+            })
+            .unzip();
+
+        let session = Session::new(ctx, &nonce_agg(&pubnonces), msg);
+
+        let psigs: Vec<PartialSignature> = secnonces
+            .into_iter()
+            .zip(signers)
+            .map(|(secnonce, signer)| session.sign(secnonce, signer).unwrap())
+            .collect();
+
+        for ((psig, pubnonce), signer) in psigs.iter().zip(&pubnonces).zip(signers) {
+            assert!(session.verify_partial(psig, pubnonce, signer.point()));
+        }
+
+        session.aggregate(&psigs).unwrap()
+    }
+
+    // From the BIP327 `key_agg_vectors.json` valid cases.
+    #[test]
+    fn test_key_agg_vectors() {
+        let pubkeys = [
+            point("02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9"),
+            point("03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659"),
+            point("023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66"),
+        ];
+
+        let cases: [(&[usize], &str); 4] = [
+            (
+                &[0, 1, 2],
+                "90539EEDE565F5D054F32CC0C220126889ED1E5D193BAF15AEF344FE59D4610C",
+            ),
+            (
+                &[2, 1, 0],
+                "6204DE8B083426DC6EAF9502D27024D53FC826BF7D2012148A0575435DF54B2B",
+            ),
+            (
+                &[0, 0, 0],
+                "B436E3BAD62B8CD409969A224731C193D051162D8C5AE8B109306127DA3AA935",
+            ),
+            (
+                &[0, 0, 1, 1],
+                "69BC22BFA5D106306E48A20679DE1D7389386124D07571D0D872686028C26A3E",
+            ),
+        ];
+
+        for (indices, expected) in cases {
+            let keys: Vec<S256Point> = indices.iter().map(|&i| pubkeys[i].clone()).collect();
+            let ctx = KeyAggContext::new(&keys).unwrap();
+
+            assert_eq!(
+                ctx.aggregated_key().serialize().to_vec(),
+                hex_to_bytes(expected)
+            );
+        }
+    }
+
+    // From the BIP327 `sign_verify_vectors.json` valid cases.
+    #[test]
+    fn test_sign_vectors() {
+        let signer = PrivateKey::new(
+            BigUint::parse_bytes(
+                b"7FB9E0E687ADA1EEBF7ECFE2F21E73EBDB51A7D450948DFE8D76D7F2D1007671",
+                16,
+            )
+            .unwrap(),
+        );
+        let pubkeys = [
+            point("03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9"),
+            point("02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9"),
+            point("02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA661"),
+        ];
+        let secnonce = "508B81A611F100A6B2B6B29656590898AF488BCF2E1F55CF22E5CFB84421FE61\
+                        FA27FD49B1D50085B481285E1CA205D55C82CC1B31FF5CD54A489829355901F7\
+                        03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9";
+        let pubnonces = [
+            "0337C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA\
+             0287BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480",
+            "0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798\
+             0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+            "032DE2662628C90B03F5E720284EB52FF7D71F4284F627B68A853D78C78E1FFE93\
+             03E4C5524E83FFE1493B9077CF1CA6BEB2090C93D930321071AD40B2F44E599046",
+        ]
+        .map(|hex| PubNonce::parse(&hex_to_bytes(hex)).unwrap());
+        let msg = hex_to_bytes("F95466D086770E689964664219266FE5ED215C92AE20BAB5C9D79ADDDDF3C0CF");
+
+        let aggnonce = nonce_agg(&pubnonces);
+        assert_eq!(
+            aggnonce.serialize(),
+            hex_to_bytes(
+                "028465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD61\
+                 037496A3CC86926D452CAFCFD55D25972CA1675D549310DE296BFF42F72EEEA8C9"
+            )
+        );
+        assert_eq!(AggNonce::parse(&aggnonce.serialize()).unwrap(), aggnonce);
+
+        let cases: [(&[usize], &str); 2] = [
+            (
+                &[0, 1, 2],
+                "012ABBCB52B3016AC03AD82395A1A415C48B93DEF78718E62A7A90052FE224FB",
+            ),
+            (
+                &[1, 0, 2],
+                "9FF2F7AAA856150CC8819254218D3ADEEB0535269051897724F9DB3789513A52",
+            ),
+        ];
+
+        for (key_indices, expected) in cases {
+            let keys: Vec<S256Point> = key_indices.iter().map(|&i| pubkeys[i].clone()).collect();
+            let ctx = KeyAggContext::new(&keys).unwrap();
+            let session = Session::new(&ctx, &aggnonce, &msg);
+
+            let secnonce = SecNonce::parse(&hex_to_bytes(secnonce)).unwrap();
+            let psig = session.sign(secnonce, &signer).unwrap();
+
+            assert_eq!(psig.serialize().to_vec(), hex_to_bytes(expected));
+            assert!(session.verify_partial(&psig, &pubnonces[0], signer.point()));
+        }
+    }
+
+    #[test]
+    fn test_sign_and_aggregate() {
+        let signers = signers();
+        let pubkeys = key_sort(
+            &signers
+                .iter()
+                .map(|s| s.point().clone())
+                .collect::<Vec<_>>(),
+        );
+        let ctx = KeyAggContext::new(&pubkeys).unwrap();
+        let msg = b"n-of-n custody";
+
+        let sig = sign_all(&signers, &ctx, msg);
+
+        assert!(ctx.aggregated_key().verify_schnorr(msg, &sig));
+        assert!(
+            !ctx.aggregated_key()
+                .verify_schnorr(b"another message", &sig)
+        );
+    }
+
+    #[test]
+    fn test_tweaked_aggregate_key() {
+        let signers = signers();
+        let pubkeys: Vec<S256Point> = signers.iter().map(|s| s.point().clone()).collect();
+        let ctx = KeyAggContext::new(&pubkeys).unwrap();
+
+        // The Taproot-tweaked aggregate matches the single-key tweak of the
+        // untweaked aggregate, so the output is indistinguishable from a
+        // single-key P2TR output.
+        let taproot_ctx = ctx.tap_tweak(None).unwrap();
+        let (output_key, _) = ctx.aggregated_key().tap_tweak(None).unwrap();
+        assert_eq!(taproot_ctx.aggregated_key(), output_key);
+
+        let msg = b"key path spend";
+        let sig = sign_all(&signers, &taproot_ctx, msg);
+        assert!(output_key.verify_schnorr(msg, &sig));
+
+        // A plain tweak followed by an x-only tweak exercises both sign flips.
+        let chained = ctx
+            .apply_tweak(&BigUint::from(5u32), false)
+            .unwrap()
+            .apply_tweak(&BigUint::from(7u32), true)
+            .unwrap();
+        let sig = sign_all(&signers, &chained, msg);
+        assert!(chained.aggregated_key().verify_schnorr(msg, &sig));
+
+        assert_eq!(
+            ctx.apply_tweak(&N, true).unwrap_err(),
+            MuSigError::InvalidTweak
+        );
+    }
+
+    #[test]
+    fn test_signing_errors() {
+        let signers = signers();
+        let pubkeys: Vec<S256Point> = signers[..2].iter().map(|s| s.point().clone()).collect();
+        let ctx = KeyAggContext::new(&pubkeys).unwrap();
+        let msg = b"errors";
+
+        let (secnonce_0, pubnonce_0) =
+            nonce_gen(&[0u8; 32], &signers[0], None, None, None).unwrap();
+        let (secnonce_1, pubnonce_1) =
+            nonce_gen(&[1u8; 32], &signers[1], None, None, None).unwrap();
+        let (secnonce_2, _) = nonce_gen(&[2u8; 32], &signers[2], None, None, None).unwrap();
+        let session = Session::new(&ctx, &nonce_agg(&[pubnonce_0.clone(), pubnonce_1]), msg);
+
+        assert_eq!(
+            session.sign(secnonce_1, &signers[0]).unwrap_err(),
+            MuSigError::KeyMismatch
+        );
+        assert_eq!(
+            session.sign(secnonce_2, &signers[2]).unwrap_err(),
+            MuSigError::UnknownSigner
+        );
+
+        let psig = session.sign(secnonce_0, &signers[0]).unwrap();
+        assert!(session.verify_partial(&psig, &pubnonce_0, signers[0].point()));
+        assert!(!session.verify_partial(&psig, &pubnonce_0, signers[1].point()));
+
+        let forged = PartialSignature {
+            s: (&psig.s + 1u32) % &*N,
+        };
+        assert!(!session.verify_partial(&forged, &pubnonce_0, signers[0].point()));
+
+        let out_of_range = PartialSignature { s: N.clone() };
+        assert_eq!(
+            session.aggregate(&[psig, out_of_range]).unwrap_err(),
+            MuSigError::InvalidPartialSignature(1)
+        );
+
+        assert_eq!(
+            PubNonce::parse(&[0u8; 66]).unwrap_err(),
+            MuSigError::InvalidNonce
+        );
+        assert!(AggNonce::parse(&[0u8; 66]).is_ok());
+    }
+}
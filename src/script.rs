@@ -2,6 +2,8 @@ use num_bigint::BigUint;
 use ripemd::Ripemd160;
 use sha2::{Digest, Sha256};
 
+use std::ops::BitOr;
+
 use crate::secp256k1::{S256Point, Signature};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -11,6 +13,7 @@ pub enum OpCode {
     OpEqualVerify = 0x88,
     OpCheckSig = 0xac,
     OpEqual = 0x87,
+    OpReturn = 0x6a,
 }
 
 impl OpCode {
@@ -21,6 +24,7 @@ impl OpCode {
             0x88 => Some(OpCode::OpEqualVerify),
             0xac => Some(OpCode::OpCheckSig),
             0x87 => Some(OpCode::OpEqual),
+            0x6a => Some(OpCode::OpReturn),
             _ => None,
         }
     }
@@ -37,11 +41,108 @@ pub struct Script {
     pub cmds: Vec<Cmd>,
 }
 
-#[derive(Debug)]
+// Consensus limits enforced by the interpreter. These match the constants in
+// Bitcoin Core's `script.h`.
+const MAX_SCRIPT_SIZE: usize = 10_000;
+const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
+const MAX_OPS_PER_SCRIPT: usize = 201;
+const MAX_STACK_SIZE: usize = 1_000;
+
+/// Script verification flags.
+///
+/// Each flag switches on a rule that was added to the interpreter after launch,
+/// either by a soft fork or as relay policy. The bit values match Bitcoin Core's
+/// `SCRIPT_VERIFY_*` constants, but only the flags this interpreter actually
+/// honours are defined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScriptFlags(u32);
+
+impl ScriptFlags {
+    pub const NONE: ScriptFlags = ScriptFlags(0);
+    /// Signatures must carry a defined hash type and public keys must be
+    /// compressed or uncompressed SEC.
+    pub const STRICTENC: ScriptFlags = ScriptFlags(1 << 1);
+    /// Signatures must be DER encoded (BIP66).
+    pub const DERSIG: ScriptFlags = ScriptFlags(1 << 2);
+    /// Signatures must be strict DER and use a low S value (BIP146).
+    pub const LOW_S: ScriptFlags = ScriptFlags(1 << 3);
+    /// The flags applied by `Script::evaluate` and `Tx::verify`.
+    pub const STANDARD: ScriptFlags = ScriptFlags(Self::STRICTENC.0 | Self::DERSIG.0);
+
+    pub fn contains(self, other: ScriptFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for ScriptFlags {
+    type Output = ScriptFlags;
+
+    fn bitor(self, rhs: ScriptFlags) -> ScriptFlags {
+        ScriptFlags(self.0 | rhs.0)
+    }
+}
+
+/// The reason a script failed to parse or evaluate.
+///
+/// The variants follow the `ScriptError_t` taxonomy of Bitcoin Core's
+/// interpreter, so a failing input can be reported with the same reason a node
+/// would give for rejecting it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScriptError {
-    InvalidOpCode,
+    /// The script finished with an empty stack or a false value on top.
+    EvalFalse,
+    /// `OP_RETURN` was executed.
+    OpReturn,
+    /// The serialized script is larger than `MAX_SCRIPT_SIZE`.
+    ScriptSize,
+    /// A pushed element is larger than `MAX_SCRIPT_ELEMENT_SIZE`.
+    PushSize,
+    /// More than `MAX_OPS_PER_SCRIPT` non-push opcodes were executed.
+    OpCount,
+    /// The stack grew beyond `MAX_STACK_SIZE` elements.
+    StackSize,
+    /// `OP_EQUALVERIFY` found two different elements.
+    EqualVerify,
+    /// An unknown opcode, or a push running past the end of the script.
+    BadOpcode,
+    /// An opcode needed more stack elements than were available.
+    InvalidStackOperation,
+    /// The signature's trailing hash type byte is not supported.
+    SigHashType,
+    /// The signature is not a valid DER encoding.
+    SigDer,
+    /// The signature's S value is in the upper half of the group order.
+    SigHighS,
+    /// The public key is not a valid SEC encoding.
+    PubKeyType,
+}
+
+impl std::fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            ScriptError::EvalFalse => {
+                "Script evaluated without error but finished with a false/empty top stack element"
+            }
+            ScriptError::OpReturn => "OP_RETURN was encountered",
+            ScriptError::ScriptSize => "Script is too big",
+            ScriptError::PushSize => "Push value size limit exceeded",
+            ScriptError::OpCount => "Operation limit exceeded",
+            ScriptError::StackSize => "Stack size limit exceeded",
+            ScriptError::EqualVerify => "Script failed an OP_EQUALVERIFY operation",
+            ScriptError::BadOpcode => "Opcode missing or not understood",
+            ScriptError::InvalidStackOperation => "Operation not valid with the current stack size",
+            ScriptError::SigHashType => "Signature hash type missing or not understood",
+            ScriptError::SigDer => "Non-canonical DER signature",
+            ScriptError::SigHighS => "Non-canonical signature: S value is unnecessarily high",
+            ScriptError::PubKeyType => "Public key is neither compressed or uncompressed",
+        };
+
+        write!(f, "{}", message)
+    }
 }
 
+impl std::error::Error for ScriptError {}
+
 impl Script {
     pub fn parse(raw: &[u8]) -> Result<Self, ScriptError> {
         let mut cmds = Vec::new();
@@ -51,19 +152,25 @@ impl Script {
             return Ok(Script { cmds });
         }
 
+        if raw.len() > MAX_SCRIPT_SIZE {
+            return Err(ScriptError::ScriptSize);
+        }
+
         loop {
             match raw[index] {
                 0x01..=0x4b => {
                     let from = index + 1;
                     let to = from + raw[index] as usize;
 
-                    let next_bytes = raw[from..to].to_vec();
+                    // A push that runs past the end of the script is treated
+                    // the same way Bitcoin Core treats it: as a bad opcode.
+                    let next_bytes = raw.get(from..to).ok_or(ScriptError::BadOpcode)?.to_vec();
                     cmds.push(Cmd::Push(next_bytes));
 
                     index += raw[index] as usize + 1;
                 }
                 _ => {
-                    let op_code = OpCode::from_u8(raw[index]).ok_or(ScriptError::InvalidOpCode)?;
+                    let op_code = OpCode::from_u8(raw[index]).ok_or(ScriptError::BadOpcode)?;
                     cmds.push(Cmd::Op(op_code));
 
                     index += 1;
@@ -78,7 +185,6 @@ impl Script {
         Ok(Script { cmds })
     }
 
-    #[allow(dead_code)]
     pub fn serialize(&self) -> Vec<u8> {
         let mut serialized: Vec<u8> = Vec::new();
 
@@ -106,97 +212,84 @@ impl Script {
     // The `OP_CHECKSIG` opcode would then be responsible for serializing the
     // transaction and hashing it (based on the signature's SIGHASH flag) to
     // generate `z`. This simplified approach is used for learning purposes.
-    pub fn evaluate(&self, z: &BigUint) -> bool {
+    #[allow(dead_code)]
+    pub fn evaluate(&self, z: &BigUint) -> Result<(), ScriptError> {
+        self.evaluate_with_flags(z, ScriptFlags::STANDARD)
+    }
+
+    pub fn evaluate_with_flags(&self, z: &BigUint, flags: ScriptFlags) -> Result<(), ScriptError> {
         let mut stack: Vec<Vec<u8>> = Vec::new();
+        let mut op_count = 0;
 
         for cmd in &self.cmds {
             match cmd {
                 Cmd::Push(bytes) => {
+                    if bytes.len() > MAX_SCRIPT_ELEMENT_SIZE {
+                        return Err(ScriptError::PushSize);
+                    }
+
                     stack.push(bytes.clone());
                 }
-                Cmd::Op(op) => match op {
-                    OpCode::OpDup => {
-                        if let Some(top) = stack.last() {
+                Cmd::Op(op) => {
+                    op_count += 1;
+                    if op_count > MAX_OPS_PER_SCRIPT {
+                        return Err(ScriptError::OpCount);
+                    }
+
+                    match op {
+                        OpCode::OpDup => {
+                            let top = stack.last().ok_or(ScriptError::InvalidStackOperation)?;
                             stack.push(top.clone());
-                        } else {
-                            return false;
                         }
-                    }
-                    OpCode::OpEqual => {
-                        let Some(a) = stack.pop() else {
-                            return false;
-                        };
-                        let Some(b) = stack.pop() else {
-                            return false;
-                        };
-
-                        if a == b {
-                            stack.push(vec![1]);
-                        } else {
-                            stack.push(vec![]);
+                        OpCode::OpEqual => {
+                            let (a, b) = pop_two(&mut stack)?;
+
+                            if a == b {
+                                stack.push(vec![1]);
+                            } else {
+                                stack.push(vec![]);
+                            }
                         }
-                    }
-                    OpCode::OpEqualVerify => {
-                        let Some(a) = stack.pop() else {
-                            return false;
-                        };
-                        let Some(b) = stack.pop() else {
-                            return false;
-                        };
-
-                        if a != b {
-                            return false;
+                        OpCode::OpEqualVerify => {
+                            let (a, b) = pop_two(&mut stack)?;
+
+                            if a != b {
+                                return Err(ScriptError::EqualVerify);
+                            }
                         }
-                    }
-                    OpCode::OpCheckSig => {
-                        let Some(pub_key_bytes) = stack.pop() else {
-                            return false;
-                        };
-                        let Some(raw_sig) = stack.pop() else {
-                            return false;
-                        };
-
-                        let pub_key = match S256Point::parse(&pub_key_bytes) {
-                            Ok(pk) => pk,
-                            Err(_) => return false,
-                        };
-
-                        let signature_bytes = &raw_sig[..raw_sig.len() - 1];
-                        let hash_type_byte = raw_sig[raw_sig.len() - 1];
-                        assert_eq!(hash_type_byte, 1, "Only SIGHASH_ALL is supported");
-
-                        let Ok(signature) = Signature::parse_der(signature_bytes) else {
-                            return false;
-                        };
-
-                        if pub_key.verify(z, &signature) {
-                            stack.push(vec![1]);
-                        } else {
-                            stack.push(vec![]);
+                        OpCode::OpCheckSig => {
+                            let (raw_sig, pub_key_bytes) = pop_two(&mut stack)?;
+
+                            if check_sig(z, &raw_sig, &pub_key_bytes, flags)? {
+                                stack.push(vec![1]);
+                            } else {
+                                stack.push(vec![]);
+                            }
                         }
-                    }
-                    OpCode::OpHash160 => {
-                        let Some(data) = stack.pop() else {
-                            return false;
-                        };
+                        OpCode::OpHash160 => {
+                            let data = stack.pop().ok_or(ScriptError::InvalidStackOperation)?;
 
-                        let h1 = Sha256::digest(data);
-                        let h2 = Ripemd160::digest(h1);
+                            let h1 = Sha256::digest(data);
+                            let h2 = Ripemd160::digest(h1);
 
-                        stack.push(h2.to_vec());
+                            stack.push(h2.to_vec());
+                        }
+                        OpCode::OpReturn => return Err(ScriptError::OpReturn),
                     }
-                },
+                }
+            }
+
+            if stack.len() > MAX_STACK_SIZE {
+                return Err(ScriptError::StackSize);
             }
         }
 
-        if let Some(top) = stack.last() {
-            !top.is_empty()
-        } else {
-            false
+        match stack.last() {
+            Some(top) if !top.is_empty() => Ok(()),
+            _ => Err(ScriptError::EvalFalse),
         }
     }
 
-    #[allow(dead_code)]
     pub fn combine(&self, other: &Script) -> Script {
         Script {
             cmds: self.cmds.iter().chain(&other.cmds).cloned().collect(),
@@ -204,6 +297,95 @@ impl Script {
     }
 }
 
+/// Pops the top two stack elements, returning them in the order they were
+/// pushed (i.e. the second-from-top element first).
+fn pop_two(stack: &mut Vec<Vec<u8>>) -> Result<(Vec<u8>, Vec<u8>), ScriptError> {
+    if stack.len() < 2 {
+        return Err(ScriptError::InvalidStackOperation);
+    }
+
+    let top = stack.pop().unwrap();
+    let second = stack.pop().unwrap();
+
+    Ok((second, top))
+}
+
+/// Checks a `<sig><hash_type>` element against a SEC public key.
+///
+/// An empty signature is not an error: it simply fails the check, which is how
+/// scripts deliberately push false with `OP_CHECKSIG`. Badly encoded
+/// signatures and keys only raise an error when the matching flag is set;
+/// otherwise they fail the check the same way.
+fn check_sig(
+    z: &BigUint,
+    raw_sig: &[u8],
+    pub_key_bytes: &[u8],
+    flags: ScriptFlags,
+) -> Result<bool, ScriptError> {
+    let Some((&hash_type_byte, signature_bytes)) = raw_sig.split_last() else {
+        return Ok(false);
+    };
+
+    // The checks run in the same order as Bitcoin Core's
+    // `CheckSignatureEncoding`, so a signature that is wrong in several ways
+    // reports the same error.
+    let strict_der = flags.contains(ScriptFlags::DERSIG)
+        || flags.contains(ScriptFlags::LOW_S)
+        || flags.contains(ScriptFlags::STRICTENC);
+
+    let signature = if strict_der {
+        Signature::parse_der(signature_bytes).map_err(|_| ScriptError::SigDer)?
+    } else {
+        match Signature::parse_der_lax(signature_bytes) {
+            Ok(signature) => signature,
+            Err(_) => return Ok(false),
+        }
+    };
+
+    if flags.contains(ScriptFlags::LOW_S) && !signature.is_low_s() {
+        return Err(ScriptError::SigHighS);
+    }
+
+    if hash_type_byte != 1 {
+        // Only SIGHASH_ALL is supported. Like an undefined hash type in
+        // Bitcoin Core, any other one is only an error under STRICTENC;
+        // otherwise the signature just fails to verify.
+        if flags.contains(ScriptFlags::STRICTENC) {
+            return Err(ScriptError::SigHashType);
+        }
+        return Ok(false);
+    }
+
+    let Some(pub_key) = parse_pub_key(pub_key_bytes, flags) else {
+        if flags.contains(ScriptFlags::STRICTENC) {
+            return Err(ScriptError::PubKeyType);
+        }
+        return Ok(false);
+    };
+
+    Ok(pub_key.verify(z, &signature))
+}
+
+/// Parses a SEC public key. Without STRICTENC, Bitcoin Core also accepts the
+/// "hybrid" encoding: an uncompressed key whose prefix, 0x06 or 0x07, repeats
+/// the parity of y.
+fn parse_pub_key(bytes: &[u8], flags: ScriptFlags) -> Option<S256Point> {
+    match bytes {
+        [prefix @ (0x06 | 0x07), coordinates @ ..]
+            if coordinates.len() == 64 && !flags.contains(ScriptFlags::STRICTENC) =>
+        {
+            if coordinates[63] & 1 != prefix & 1 {
+                return None;
+            }
+
+            let mut uncompressed = vec![0x04];
+            uncompressed.extend_from_slice(coordinates);
+            S256Point::parse(&uncompressed).ok()
+        }
+        _ => S256Point::parse(bytes).ok(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -285,7 +467,7 @@ mod tests {
         let script = Script {
             cmds: vec![Cmd::Push(vec![0x01])],
         };
-        assert!(script.evaluate(&z));
+        assert!(script.evaluate(&z).is_ok());
     }
 
     #[test]
@@ -302,7 +484,7 @@ mod tests {
                 Cmd::Op(OpCode::OpEqual),
             ],
         };
-        assert!(script.evaluate(&z));
+        assert!(script.evaluate(&z).is_ok());
     }
 
     #[test]
@@ -320,7 +502,7 @@ mod tests {
                 Cmd::Op(OpCode::OpEqual),
             ],
         };
-        assert!(!script.evaluate(&z));
+        assert_eq!(script.evaluate(&z), Err(ScriptError::EvalFalse));
     }
 
     #[test]
@@ -330,6 +512,226 @@ mod tests {
         // Stack: []
         // Result: False
         let script = Script { cmds: vec![] };
-        assert!(!script.evaluate(&z));
+        assert_eq!(script.evaluate(&z), Err(ScriptError::EvalFalse));
+    }
+
+    #[test]
+    fn test_parse_truncated_push() {
+        // Push 3 bytes, but only 2 follow
+        let raw = vec![0x03, 0xCA, 0xFE];
+        assert_eq!(Script::parse(&raw), Err(ScriptError::BadOpcode));
+    }
+
+    #[test]
+    fn test_parse_unknown_opcode() {
+        let raw = vec![0xff];
+        assert_eq!(Script::parse(&raw), Err(ScriptError::BadOpcode));
+    }
+
+    #[test]
+    fn test_parse_script_too_big() {
+        let raw = vec![0x76; MAX_SCRIPT_SIZE + 1];
+        assert_eq!(Script::parse(&raw), Err(ScriptError::ScriptSize));
+    }
+
+    #[test]
+    fn test_evaluate_stack_underflow() {
+        let z = BigUint::from(0u32);
+        // Script: Push([0x01]), OpEqual
+        // OpEqual needs two elements but only one is available.
+        let script = Script {
+            cmds: vec![Cmd::Push(vec![0x01]), Cmd::Op(OpCode::OpEqual)],
+        };
+        assert_eq!(script.evaluate(&z), Err(ScriptError::InvalidStackOperation));
+
+        let script = Script {
+            cmds: vec![Cmd::Op(OpCode::OpDup)],
+        };
+        assert_eq!(script.evaluate(&z), Err(ScriptError::InvalidStackOperation));
+    }
+
+    #[test]
+    fn test_evaluate_equal_verify_fail() {
+        let z = BigUint::from(0u32);
+        let script = Script {
+            cmds: vec![
+                Cmd::Push(vec![0x01]),
+                Cmd::Push(vec![0x01]),
+                Cmd::Push(vec![0x02]),
+                Cmd::Op(OpCode::OpEqualVerify),
+            ],
+        };
+        assert_eq!(script.evaluate(&z), Err(ScriptError::EqualVerify));
+    }
+
+    #[test]
+    fn test_evaluate_op_return() {
+        let z = BigUint::from(0u32);
+        let script = Script {
+            cmds: vec![Cmd::Push(vec![0x01]), Cmd::Op(OpCode::OpReturn)],
+        };
+        assert_eq!(script.evaluate(&z), Err(ScriptError::OpReturn));
+    }
+
+    #[test]
+    fn test_evaluate_limits() {
+        let z = BigUint::from(0u32);
+
+        let script = Script {
+            cmds: vec![Cmd::Push(vec![0x01; MAX_SCRIPT_ELEMENT_SIZE + 1])],
+        };
+        assert_eq!(script.evaluate(&z), Err(ScriptError::PushSize));
+
+        let script = Script {
+            cmds: vec![Cmd::Push(vec![0x01]); MAX_STACK_SIZE + 1],
+        };
+        assert_eq!(script.evaluate(&z), Err(ScriptError::StackSize));
+
+        let mut cmds = vec![Cmd::Push(vec![0x01])];
+        cmds.extend(vec![Cmd::Op(OpCode::OpHash160); MAX_OPS_PER_SCRIPT + 1]);
+        let script = Script { cmds };
+        assert_eq!(script.evaluate(&z), Err(ScriptError::OpCount));
+    }
+
+    #[test]
+    fn test_evaluate_checksig_errors() {
+        let z = BigUint::from(0u32);
+        let pub_key = crate::secp256k1::G.sec(None);
+
+        // An empty signature fails the check without raising an error.
+        let script = Script {
+            cmds: vec![
+                Cmd::Push(vec![]),
+                Cmd::Push(pub_key.clone()),
+                Cmd::Op(OpCode::OpCheckSig),
+            ],
+        };
+        assert_eq!(script.evaluate(&z), Err(ScriptError::EvalFalse));
+
+        // SIGHASH_NONE (0x02) is not supported.
+        let sig = crate::secp256k1::Signature {
+            r: BigUint::from(1u32),
+            s: BigUint::from(1u32),
+        };
+        let mut raw_sig = sig.der();
+        raw_sig.push(0x02);
+        let script = Script {
+            cmds: vec![
+                Cmd::Push(raw_sig),
+                Cmd::Push(pub_key.clone()),
+                Cmd::Op(OpCode::OpCheckSig),
+            ],
+        };
+        assert_eq!(script.evaluate(&z), Err(ScriptError::SigHashType));
+
+        // A signature that is not DER encoded.
+        let script = Script {
+            cmds: vec![
+                Cmd::Push(vec![0x31, 0x00, 0x01]),
+                Cmd::Push(pub_key),
+                Cmd::Op(OpCode::OpCheckSig),
+            ],
+        };
+        assert_eq!(script.evaluate(&z), Err(ScriptError::SigDer));
+
+        // A valid signature against a public key with an unknown prefix.
+        let mut raw_sig = sig.der();
+        raw_sig.push(0x01);
+        let script = Script {
+            cmds: vec![
+                Cmd::Push(raw_sig),
+                Cmd::Push(vec![0x05; 33]),
+                Cmd::Op(OpCode::OpCheckSig),
+            ],
+        };
+        assert_eq!(script.evaluate(&z), Err(ScriptError::PubKeyType));
+    }
+
+    #[test]
+    fn test_evaluate_without_encoding_flags() {
+        let z = BigUint::from(0u32);
+
+        // Without DERSIG/STRICTENC a badly encoded signature or key simply
+        // fails the check instead of aborting evaluation.
+        let script = Script {
+            cmds: vec![
+                Cmd::Push(vec![0x31, 0x00, 0x01]),
+                Cmd::Push(crate::secp256k1::G.sec(None)),
+                Cmd::Op(OpCode::OpCheckSig),
+            ],
+        };
+        assert_eq!(
+            script.evaluate_with_flags(&z, ScriptFlags::NONE),
+            Err(ScriptError::EvalFalse)
+        );
+
+        let sig = crate::secp256k1::Signature {
+            r: BigUint::from(1u32),
+            s: BigUint::from(1u32),
+        };
+        let mut raw_sig = sig.der();
+        raw_sig.push(0x01);
+        let script = Script {
+            cmds: vec![
+                Cmd::Push(raw_sig),
+                Cmd::Push(vec![0x05; 33]),
+                Cmd::Op(OpCode::OpCheckSig),
+            ],
+        };
+        assert_eq!(
+            script.evaluate_with_flags(&z, ScriptFlags::DERSIG),
+            Err(ScriptError::EvalFalse)
+        );
+
+        // An unsupported hash type only raises SIG_HASHTYPE under STRICTENC.
+        let mut raw_sig = sig.der();
+        raw_sig.push(0x02);
+        let script = Script {
+            cmds: vec![
+                Cmd::Push(raw_sig),
+                Cmd::Push(crate::secp256k1::G.sec(None)),
+                Cmd::Op(OpCode::OpCheckSig),
+            ],
+        };
+        assert_eq!(
+            script.evaluate_with_flags(&z, ScriptFlags::DERSIG),
+            Err(ScriptError::EvalFalse)
+        );
+        assert_eq!(
+            script.evaluate_with_flags(&z, ScriptFlags::STRICTENC),
+            Err(ScriptError::SigHashType)
+        );
+    }
+
+    #[test]
+    fn test_evaluate_low_s() {
+        let z = BigUint::from(0u32);
+        let pub_key = crate::secp256k1::G.sec(None);
+
+        // s = N - 1 is as high as S can be.
+        let n_minus_1 = BigUint::parse_bytes(
+            b"fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140",
+            16,
+        )
+        .unwrap();
+        let sig = crate::secp256k1::Signature {
+            r: BigUint::from(1u32),
+            s: n_minus_1,
+        };
+        let mut raw_sig = sig.der();
+        raw_sig.push(0x01);
+        let script = Script {
+            cmds: vec![
+                Cmd::Push(raw_sig),
+                Cmd::Push(pub_key),
+                Cmd::Op(OpCode::OpCheckSig),
+            ],
+        };
+
+        assert_eq!(
+            script.evaluate_with_flags(&z, ScriptFlags::LOW_S),
+            Err(ScriptError::SigHighS)
+        );
+        assert_eq!(script.evaluate(&z), Err(ScriptError::EvalFalse));
     }
 }
@@ -1,12 +1,23 @@
-use std::ops::{Add, Div, Mul, Sub};
+use std::ops::{Add, Mul, Neg};
 use std::sync::{Arc, LazyLock};
 
 use hmac::{Hmac, Mac};
 use num_bigint::BigUint;
-use sha2::Sha256;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
 
 use crate::elliptic_curve::{Point, PointError};
-use crate::finite_field::{FieldElement, FieldElementError};
+use crate::finite_field::FieldElement;
+
+mod field;
+mod glv;
+mod jacobian;
+mod pippenger;
+mod projective;
+
+pub use field::S256FieldElement;
+use jacobian::JacobianPoint;
+use projective::GeneratorTable;
 
 type HmacSha256 = Hmac<Sha256>;
 
@@ -16,19 +27,17 @@ type HmacSha256 = Hmac<Sha256>;
 // allocations and are not `const fn`. Instead, we use `std::sync::OnceLock`
 // for thread-safe, lazy, one-time initialization. The value is computed and
 // stored the first time it's accessed. See `note/06-lazy-static-initialization.md`.
-static S256_PRIME: LazyLock<Arc<BigUint>> = LazyLock::new(|| {
+pub(crate) static S256_PRIME: LazyLock<Arc<BigUint>> = LazyLock::new(|| {
     let two_bigint = BigUint::from(2u32);
 
     Arc::new(two_bigint.pow(256) - two_bigint.pow(32) - BigUint::from(977u32))
 });
 
-static A: LazyLock<S256FieldElement> =
-    LazyLock::new(|| S256FieldElement::new(BigUint::from(0u32)).unwrap());
-
 static B: LazyLock<S256FieldElement> =
     LazyLock::new(|| S256FieldElement::new(BigUint::from(7u32)).unwrap());
 
-static N: LazyLock<BigUint> = LazyLock::new(|| {
+/// The order of the generator point `G`.
+pub static N: LazyLock<BigUint> = LazyLock::new(|| {
     BigUint::parse_bytes(
         b"fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
         16,
@@ -38,6 +47,8 @@ static N: LazyLock<BigUint> = LazyLock::new(|| {
 
 static N_MINUS_2: LazyLock<BigUint> = LazyLock::new(|| &*N - BigUint::from(2u32));
 
+static N_HALF: LazyLock<BigUint> = LazyLock::new(|| &*N / BigUint::from(2u32));
+
 // The x and y coordinates of the secp256k1 generator point G.
 static GX: LazyLock<BigUint> = LazyLock::new(|| {
     BigUint::parse_bytes(
@@ -62,6 +73,32 @@ pub static G: LazyLock<S256Point> = LazyLock::new(|| {
     S256Point::new(x, y).unwrap()
 });
 
+/// Multiples of `G` for every 4-bit window of a scalar, used by
+/// `S256Point::mul_generator`. About 100 KB, built the first time it is used.
+static G_TABLE: LazyLock<GeneratorTable> = LazyLock::new(|| GeneratorTable::new(&G));
+
+/// Odd multiples of `G` for the wNAF digits of `S256Point::mul_add`, built on
+/// first use.
+static G_ODD_MULTIPLES: LazyLock<Vec<S256Point>> =
+    LazyLock::new(|| jacobian::odd_multiples(&G, jacobian::G_WNAF_WINDOW));
+
+/// The same multiples of `lambda * G`, for the second half of a scalar split
+/// with the endomorphism.
+static G_LAMBDA_ODD_MULTIPLES: LazyLock<Vec<S256Point>> =
+    LazyLock::new(|| glv::endomorphism_multiples(&G_ODD_MULTIPLES));
+
+// The order of the generator point G is N, so k * G = (k mod N) * G. The
+// fixed-width multiplication routines take the reduced scalar as four
+// little-endian 64-bit limbs.
+fn scalar_limbs(scalar: &BigUint) -> [u64; 4] {
+    let mut limbs = [0u64; 4];
+    for (limb, digit) in limbs.iter_mut().zip((scalar % &*N).iter_u64_digits()) {
+        *limb = digit;
+    }
+
+    limbs
+}
+
 macro_rules! impl_s256_wrappers {
     ($type:ident, $trait:ident, $method:ident) => {
         impl $trait<$type> for $type {
@@ -118,140 +155,132 @@ macro_rules! impl_s256_biguint_wrappers {
     };
 }
 
+// A finite point or the point at infinity, following the same "make invalid
+// states unrepresentable" pattern as the generic `Point`. See
+// `note/04-making-states-unrepresentable.md`.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct S256FieldElement {
-    element: FieldElement,
+enum S256PointKind {
+    Coordinates(S256FieldElement, S256FieldElement),
+    Infinity,
 }
 
-impl S256FieldElement {
-    pub fn new<E: Into<BigUint>>(num: E) -> Result<Self, FieldElementError> {
-        let element = FieldElement::new(num.into(), S256_PRIME.clone())?;
-
-        Ok(S256FieldElement { element })
-    }
+/// A point on secp256k1, with coordinates in the fixed-width
+/// `S256FieldElement` rather than the generic `FieldElement`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S256Point {
+    kind: S256PointKind,
+}
 
-    pub fn pow<E: Into<BigUint>>(&self, exponent: E) -> S256FieldElement {
-        S256FieldElement {
-            element: self.element.pow(exponent),
+impl S256Point {
+    pub fn new(x: S256FieldElement, y: S256FieldElement) -> Result<S256Point, PointError> {
+        if y.square() != x.square() * x + *B {
+            return Err(PointError::NotOnCurve);
         }
-    }
 
-    pub fn num(&self) -> &BigUint {
-        self.element.num()
+        Ok(S256Point {
+            kind: S256PointKind::Coordinates(x, y),
+        })
     }
 
-    pub fn sqrt(&self) -> S256FieldElement {
-        let pow_magnitude = (&**S256_PRIME + 1u32) / 4u32;
-        S256FieldElement {
-            element: self.element.pow(pow_magnitude),
-        }
-    }
-}
-
-impl Add for &S256FieldElement {
-    type Output = S256FieldElement;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        S256FieldElement {
-            element: &self.element + &rhs.element,
-        }
+    pub fn infinity() -> Result<S256Point, PointError> {
+        Ok(S256Point {
+            kind: S256PointKind::Infinity,
+        })
     }
-}
-
-impl_s256_wrappers!(S256FieldElement, Add, add);
 
-impl Add<&BigUint> for &S256FieldElement {
-    type Output = S256FieldElement;
-
-    fn add(self, rhs: &BigUint) -> Self::Output {
-        S256FieldElement {
-            element: &self.element + rhs,
-        }
+    pub fn is_at_infinity(&self) -> bool {
+        matches!(self.kind, S256PointKind::Infinity)
     }
-}
-
-impl_s256_biguint_wrappers!(S256FieldElement, Add, add);
-
-impl Sub for &S256FieldElement {
-    type Output = S256FieldElement;
 
-    fn sub(self, rhs: Self) -> Self::Output {
-        S256FieldElement {
-            element: &self.element - &rhs.element,
+    /// This point as a point on the generic curve type.
+    pub fn point(&self) -> Point {
+        let prime = S256_PRIME.clone();
+        let a = FieldElement::new(BigUint::from(0u32), prime.clone()).unwrap();
+        let b = FieldElement::new(BigUint::from(7u32), prime.clone()).unwrap();
+
+        match &self.kind {
+            S256PointKind::Coordinates(x, y) => Point::new(
+                FieldElement::new(x.num(), prime.clone()).unwrap(),
+                FieldElement::new(y.num(), prime).unwrap(),
+                a,
+                b,
+            )
+            .unwrap(), // the same point, so it is on the same curve
+            S256PointKind::Infinity => Point::infinity(a, b).unwrap(),
         }
     }
-}
-
-impl_s256_wrappers!(S256FieldElement, Sub, sub);
 
-impl Mul for &S256FieldElement {
-    type Output = S256FieldElement;
-
-    fn mul(self, rhs: Self) -> Self::Output {
-        S256FieldElement {
-            element: &self.element * &rhs.element,
+    pub fn x(&self) -> Option<&S256FieldElement> {
+        match &self.kind {
+            S256PointKind::Coordinates(x, _) => Some(x),
+            S256PointKind::Infinity => None,
         }
     }
-}
 
-impl_s256_wrappers!(S256FieldElement, Mul, mul);
-
-impl Mul<&BigUint> for &S256FieldElement {
-    type Output = S256FieldElement;
-
-    fn mul(self, rhs: &BigUint) -> Self::Output {
-        S256FieldElement {
-            element: &self.element * rhs,
+    pub fn y(&self) -> Option<&S256FieldElement> {
+        match &self.kind {
+            S256PointKind::Coordinates(_, y) => Some(y),
+            S256PointKind::Infinity => None,
         }
     }
-}
 
-impl_s256_biguint_wrappers!(S256FieldElement, Mul, mul);
-
-impl Div for &S256FieldElement {
-    type Output = S256FieldElement;
-
-    fn div(self, rhs: Self) -> Self::Output {
-        S256FieldElement {
-            element: &self.element / &rhs.element,
-        }
+    pub fn x_num(&self) -> Option<BigUint> {
+        self.x().map(S256FieldElement::num)
     }
-}
-
-impl_s256_wrappers!(S256FieldElement, Div, div);
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct S256Point {
-    point: Point,
-}
-
-impl S256Point {
-    pub fn new(x: S256FieldElement, y: S256FieldElement) -> Result<S256Point, PointError> {
-        let point = Point::new(x.element, y.element, A.element.clone(), B.element.clone())?;
 
-        Ok(S256Point { point })
+    pub fn y_num(&self) -> Option<BigUint> {
+        self.y().map(S256FieldElement::num)
     }
 
-    pub fn infinity() -> Result<S256Point, PointError> {
-        let point = Point::infinity(A.element.clone(), B.element.clone())?;
-
-        Ok(S256Point { point })
+    /// Multiplies by a secret scalar in constant time.
+    ///
+    /// The `*` operator skips work for zero bits and takes shortcuts for
+    /// special points, so its running time depends on the scalar; it is meant
+    /// for public scalars, as in signature verification. This method performs
+    /// the same field operations for every scalar and should be used whenever
+    /// the scalar is a private key or a nonce. Reducing the scalar modulo N
+    /// still goes through `BigUint`, which makes no timing guarantees.
+    pub fn mul_secret(&self, scalar: &BigUint) -> S256Point {
+        projective::mul_constant_time(self, scalar)
     }
 
-    pub fn is_at_infinity(&self) -> bool {
-        self.point.is_at_infinity()
+    /// Multiplies `G` by a secret scalar in constant time, using a table of
+    /// multiples of `G` built on first use. This is much faster than
+    /// `G.mul_secret` and has the same timing guarantees.
+    pub fn mul_generator(scalar: &BigUint) -> S256Point {
+        G_TABLE.mul(scalar).to_affine()
     }
 
-    pub fn point(&self) -> &Point {
-        &self.point
+    /// Variable-time multiplication in Jacobian coordinates, so that only the
+    /// caller's final result needs an inversion.
+    fn mul_jacobian(&self, scalar: &BigUint) -> JacobianPoint {
+        glv::mul_glv(self, scalar)
     }
 
-    pub fn x_num(&self) -> Option<&BigUint> {
-        self.point.x().map(|element| element.num())
+    /// Computes `u * G + v * point`, the combination at the heart of
+    /// signature verification.
+    ///
+    /// Both products are accumulated together over one chain of doublings,
+    /// which is roughly twice as fast as multiplying separately and adding.
+    /// Each scalar is also split into two 128-bit halves with the curve's
+    /// endomorphism, halving the number of doublings again. Like `Mul`, this
+    /// runs in variable time and is meant for public scalars.
+    pub fn mul_add(u: &BigUint, v: &BigUint, point: &S256Point) -> S256Point {
+        Self::mul_add_jacobian(u, v, point).to_affine()
     }
 
-    pub fn y_num(&self) -> Option<&BigUint> {
-        self.point.y().map(|element| element.num())
+    fn mul_add_jacobian(u: &BigUint, v: &BigUint, point: &S256Point) -> JacobianPoint {
+        let (u1_digits, u2_digits) = glv::split_wnaf(u, jacobian::G_WNAF_WINDOW);
+        let (v1_digits, v2_digits) = glv::split_wnaf(v, jacobian::WNAF_WINDOW);
+        let point_multiples = jacobian::odd_multiples(point, jacobian::WNAF_WINDOW);
+        let lambda_point_multiples = glv::endomorphism_multiples(&point_multiples);
+
+        jacobian::interleaved_wnaf(&[
+            (&u1_digits, &G_ODD_MULTIPLES),
+            (&u2_digits, &G_LAMBDA_ODD_MULTIPLES),
+            (&v1_digits, &point_multiples),
+            (&v2_digits, &lambda_point_multiples),
+        ])
     }
 
     pub fn verify(&self, z: &BigUint, sig: &Signature) -> bool {
@@ -263,23 +292,71 @@ impl S256Point {
         let u = (z * &s_inv) % &*N;
         let v = (&sig.r * s_inv) % &*N;
         // u.G + v.P = k.G
-        let total_point = &*G * u + self * v;
+        let total_point = S256Point::mul_add(&u, &v, self);
 
         total_point.x_num().is_some_and(|x| (x % &*N) == sig.r)
     }
 
+    /// Recovers the public key that produced `sig` over `z`.
+    ///
+    /// Up to four keys can verify a given signature; `recovery_id` selects
+    /// one of them, as produced by `PrivateKey::sign_recoverable`.
+    pub fn recover(z: &BigUint, sig: &Signature, recovery_id: u8) -> Result<S256Point, PointError> {
+        if recovery_id > 3
+            || sig.r < 1u32.into()
+            || sig.r >= *N
+            || sig.s < 1u32.into()
+            || sig.s >= *N
+        {
+            return Err(PointError::CannotParse);
+        }
+
+        // Rebuild R from r: its x coordinate is r (or r + N) and the low bit
+        // of the recovery id is the parity of its y coordinate.
+        let mut r_x = sig.r.clone();
+        if recovery_id & 2 != 0 {
+            r_x += &*N;
+        }
+        let x = S256FieldElement::new(r_x).map_err(|_| PointError::NotOnCurve)?;
+        let y_squared = x.pow(BigUint::from(3u32)) + *B;
+        let y = y_squared.sqrt();
+
+        if y.pow(BigUint::from(2u32)) != y_squared {
+            return Err(PointError::NotOnCurve);
+        }
+
+        let y = if y.is_odd() == (recovery_id & 1 == 1) {
+            y
+        } else {
+            -y
+        };
+        let r_point = S256Point::new(x, y)?;
+
+        // P = r^-1 (s.R - z.G)
+        let r_inv = sig.r.modpow(&N_MINUS_2, &N);
+        let u1 = (z * &r_inv) % &*N;
+        let u2 = (&sig.s * r_inv) % &*N;
+        let public_key = S256Point::mul_add(&((&*N - u1) % &*N), &u2, &r_point);
+
+        if public_key.is_at_infinity() {
+            return Err(PointError::NotOnCurve);
+        }
+
+        Ok(public_key)
+    }
+
     pub fn sec(&self, compressed: Option<bool>) -> Vec<u8> {
         if self.is_at_infinity() {
             return vec![0x00];
         }
 
-        let x_bytes = to_32_bytes(self.x_num().unwrap());
+        let x_bytes = self.x().unwrap().to_be_bytes();
 
         if compressed.unwrap_or(true) {
             let mut serialized = Vec::<u8>::with_capacity(33);
 
             // bit(0) checks the LSB (2^0 = 1). If 1 (true), it's ODD. If 0 (false), it's EVEN.
-            let marker = if self.y_num().unwrap().bit(0) {
+            let marker = if self.y().unwrap().is_odd() {
                 0x03
             } else {
                 0x02
@@ -294,7 +371,7 @@ impl S256Point {
         let mut serialized = Vec::<u8>::with_capacity(65);
         serialized.push(0x04);
         serialized.extend(x_bytes);
-        serialized.extend(to_32_bytes(self.y_num().unwrap()));
+        serialized.extend(self.y().unwrap().to_be_bytes());
 
         serialized
     }
@@ -309,13 +386,12 @@ impl S256Point {
                 return Err(PointError::CannotParse);
             }
 
-            let x_bigint = BigUint::from_bytes_be(&sec_bin[1..33]);
-            let y_bigint = BigUint::from_bytes_be(&sec_bin[33..]);
+            let x = S256FieldElement::from_be_bytes(sec_bin[1..33].try_into().unwrap()) // length checked above
+                .map_err(|_| PointError::CannotParse)?;
+            let y = S256FieldElement::from_be_bytes(sec_bin[33..].try_into().unwrap())
+                .map_err(|_| PointError::CannotParse)?;
 
-            return S256Point::new(
-                S256FieldElement::new(x_bigint).map_err(|_| PointError::CannotParse)?,
-                S256FieldElement::new(y_bigint).map_err(|_| PointError::CannotParse)?,
-            );
+            return S256Point::new(x, y);
         }
 
         let is_odd = match sec_bin[0] {
@@ -328,17 +404,152 @@ impl S256Point {
             return Err(PointError::CannotParse);
         }
 
-        let x = S256FieldElement::new(BigUint::from_bytes_be(&sec_bin[1..]))
+        let x = S256FieldElement::from_be_bytes(sec_bin[1..].try_into().unwrap()) // length checked above
             .map_err(|_| PointError::CannotParse)?;
-        let y_squared = x.pow(BigUint::from(3u32)) + S256FieldElement::new(7u32).unwrap();
+        let y_squared = x.pow(BigUint::from(3u32)) + *B;
         let y = y_squared.sqrt();
 
-        if y.num().bit(0) == is_odd {
+        if y.is_odd() == is_odd {
             S256Point::new(x, y)
         } else {
-            let other_y = S256FieldElement::new(0u32).unwrap() - y;
+            S256Point::new(x, -y)
+        }
+    }
+
+    /// Returns the point with the given x coordinate and an even y
+    /// coordinate, as BIP340's `lift_x` does for x-only public keys.
+    pub fn lift_x(x: &BigUint) -> Result<S256Point, PointError> {
+        let x = S256FieldElement::new(x.clone()).map_err(|_| PointError::CannotParse)?;
+        let y_squared = x.pow(BigUint::from(3u32)) + *B;
+        let y = y_squared.sqrt();
+
+        if y.pow(BigUint::from(2u32)) != y_squared {
+            return Err(PointError::NotOnCurve);
+        }
+
+        if y.is_odd() {
+            S256Point::new(x, -y)
+        } else {
+            S256Point::new(x, y)
+        }
+    }
+
+    pub fn has_even_y(&self) -> bool {
+        self.y().is_some_and(|y| !y.is_odd())
+    }
+
+    /// The 32-byte x-only serialization used by BIP340 and Taproot.
+    pub fn x_only(&self) -> [u8; 32] {
+        self.x()
+            .expect("the point at infinity has no x-only encoding")
+            .to_be_bytes()
+    }
 
-            S256Point::new(x, other_y)
+    /// Verifies a BIP340 Schnorr signature over `msg`.
+    ///
+    /// Only the x coordinate of `self` is used: a point with an odd y
+    /// coordinate is treated as its even-y negation, so it verifies the same
+    /// signatures as the x-only key it serializes to.
+    pub fn verify_schnorr(&self, msg: &[u8], sig: &SchnorrSignature) -> bool {
+        let Some(px) = self.x_num() else {
+            return false;
+        };
+        let Ok(public_key) = S256Point::lift_x(&px) else {
+            return false;
+        };
+
+        if sig.r >= **S256_PRIME || sig.s >= *N {
+            return false;
+        }
+
+        let e = schnorr_challenge(&to_32_bytes(&sig.r), &public_key.x_only(), msg);
+        // R = s.G - e.P
+        let r_point = S256Point::mul_add(&sig.s, &((&*N - e) % &*N), &public_key);
+
+        r_point.has_even_y() && r_point.x_num().is_some_and(|x| x == sig.r)
+    }
+
+    /// Verifies many ECDSA signatures, each given as `(public key, z,
+    /// signature)`, and returns whether all of them are valid.
+    ///
+    /// ECDSA signatures cannot be combined algebraically, so this splits the
+    /// batch across the available cores and verifies each one on its own.
+    pub fn verify_batch(items: &[(&S256Point, &BigUint, &Signature)]) -> bool {
+        let threads = std::thread::available_parallelism().map_or(1, |count| count.get());
+        let chunk_size = items.len().div_ceil(threads).max(1);
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = items
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || chunk.iter().all(|(point, z, sig)| point.verify(z, sig)))
+                })
+                .collect();
+
+            handles.into_iter().all(|handle| handle.join().unwrap())
+        })
+    }
+
+    /// Verifies many BIP340 signatures, each given as `(public key, message,
+    /// signature)`, and returns whether all of them are valid.
+    ///
+    /// Each signature satisfies `s.G = R + e.P`. The batch checks a random
+    /// linear combination of these equations in one multi-scalar
+    /// multiplication, which is much faster than checking them one by one.
+    /// The random coefficients from `rng` make it infeasible to craft invalid
+    /// signatures whose errors cancel out, so `rng` must not be predictable
+    /// to whoever produced the signatures. A failed batch does not say which
+    /// signature is invalid.
+    pub fn verify_schnorr_batch<R: RngCore>(
+        items: &[(&S256Point, &[u8], &SchnorrSignature)],
+        rng: &mut R,
+    ) -> bool {
+        let mut s_sum = BigUint::from(0u32);
+        let mut terms = Vec::with_capacity(2 * items.len() + 1);
+
+        for (index, (point, msg, sig)) in items.iter().enumerate() {
+            let Some(px) = point.x_num() else {
+                return false;
+            };
+            let Ok(public_key) = S256Point::lift_x(&px) else {
+                return false;
+            };
+            if sig.r >= **S256_PRIME || sig.s >= *N {
+                return false;
+            }
+            let Ok(r_point) = S256Point::lift_x(&sig.r) else {
+                return false;
+            };
+
+            let e = schnorr_challenge(&to_32_bytes(&sig.r), &public_key.x_only(), msg);
+
+            // The first coefficient can be one without weakening the check.
+            let a = if index == 0 {
+                BigUint::from(1u32)
+            } else {
+                random_scalar(rng)
+            };
+
+            // a.s.G - a.R - a.e.P sums to infinity for valid signatures.
+            s_sum = (s_sum + &a * &sig.s) % &*N;
+            terms.push((&*N - &a, r_point));
+            terms.push((&*N - (a * e) % &*N, public_key));
+        }
+        terms.push((s_sum, G.clone()));
+
+        pippenger::multi_mul(&terms).is_infinity()
+    }
+}
+
+/// A uniformly random scalar in `[1, N)`.
+pub(crate) fn random_scalar<R: RngCore>(rng: &mut R) -> BigUint {
+    loop {
+        let mut bytes = [0u8; 32];
+        rng.fill_bytes(&mut bytes);
+
+        let scalar = BigUint::from_bytes_be(&bytes);
+        if scalar > BigUint::from(0u32) && scalar < *N {
+            return scalar;
         }
     }
 }
@@ -352,34 +563,190 @@ pub fn to_32_bytes(num: &BigUint) -> [u8; 32] {
     result
 }
 
+/// Computes the BIP340 tagged hash `SHA256(SHA256(tag) || SHA256(tag) || msg)`.
+pub fn tagged_hash(tag: &str, msg: &[u8]) -> [u8; 32] {
+    let tag_hash = Sha256::digest(tag.as_bytes());
+
+    let mut hasher = Sha256::new();
+    hasher.update(tag_hash);
+    hasher.update(tag_hash);
+    hasher.update(msg);
+
+    hasher.finalize().into()
+}
+
+pub(crate) fn schnorr_challenge(r_bytes: &[u8; 32], px_bytes: &[u8; 32], msg: &[u8]) -> BigUint {
+    let mut data = Vec::with_capacity(64 + msg.len());
+    data.extend_from_slice(r_bytes);
+    data.extend_from_slice(px_bytes);
+    data.extend_from_slice(msg);
+
+    BigUint::from_bytes_be(&tagged_hash("BIP0340/challenge", &data)) % &*N
+}
+
 impl Add for &S256Point {
     type Output = S256Point;
 
     fn add(self, rhs: Self) -> Self::Output {
-        S256Point {
-            point: &self.point + &rhs.point,
-        }
+        JacobianPoint::from_affine(self).add_affine(rhs).to_affine()
     }
 }
 
 impl_s256_wrappers!(S256Point, Add, add);
 
+impl Neg for &S256Point {
+    type Output = S256Point;
+
+    fn neg(self) -> Self::Output {
+        match &self.kind {
+            S256PointKind::Coordinates(x, y) => S256Point {
+                kind: S256PointKind::Coordinates(*x, -y),
+            },
+            S256PointKind::Infinity => self.clone(),
+        }
+    }
+}
+
+impl Neg for S256Point {
+    type Output = S256Point;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
+
+// Variable time in the scalar; see `S256Point::mul_secret` for secret scalars.
 impl Mul<&BigUint> for &S256Point {
     type Output = S256Point;
 
     fn mul(self, rhs: &BigUint) -> Self::Output {
-        // The order of the generator point G is N.
-        // So, k * G = (k mod n) * G.
-        // We can reduce the scalar modulo N before multiplication for efficiency.
-        let scalar = rhs % &*N;
+        self.mul_jacobian(rhs).to_affine()
+    }
+}
+
+impl_s256_biguint_wrappers!(S256Point, Mul, mul);
+
+/// Whether a point's y coordinate is even or odd.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+/// A BIP340 x-only public key.
+///
+/// Only the x coordinate is serialized; the key always stands for the point
+/// with that x coordinate and an even y coordinate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XOnlyPublicKey {
+    point: S256Point,
+}
+
+impl XOnlyPublicKey {
+    /// Drops the y coordinate of `point`, returning the x-only key and the
+    /// parity the original point had.
+    pub fn from_point(point: &S256Point) -> (XOnlyPublicKey, Parity) {
+        let x = point
+            .x_num()
+            .expect("the point at infinity has no x-only encoding");
+        let parity = if point.has_even_y() {
+            Parity::Even
+        } else {
+            Parity::Odd
+        };
+        // A point is on the curve iff its negation is, so lifting cannot fail.
+        let point = S256Point::lift_x(&x).unwrap();
+
+        (XOnlyPublicKey { point }, parity)
+    }
+
+    pub fn parse(serialized: &[u8]) -> Result<XOnlyPublicKey, PointError> {
+        if serialized.len() != 32 {
+            return Err(PointError::CannotParse);
+        }
+
+        let point = S256Point::lift_x(&BigUint::from_bytes_be(serialized))?;
+
+        Ok(XOnlyPublicKey { point })
+    }
+
+    pub fn serialize(&self) -> [u8; 32] {
+        self.point.x_only()
+    }
+
+    /// The even-y point this key stands for.
+    pub fn point(&self) -> &S256Point {
+        &self.point
+    }
+
+    pub fn verify_schnorr(&self, msg: &[u8], sig: &SchnorrSignature) -> bool {
+        self.point.verify_schnorr(msg, sig)
+    }
 
-        S256Point {
-            point: &self.point * scalar,
+    /// Derives the BIP341 Taproot output key `Q = P + H_TapTweak(P || merkle_root)G`.
+    ///
+    /// `merkle_root` is `None` for a key-path-only output. The returned parity
+    /// is that of `Q`, which a script-path spend records in its control block.
+    pub fn tap_tweak(
+        &self,
+        merkle_root: Option<&[u8; 32]>,
+    ) -> Result<(XOnlyPublicKey, Parity), TweakError> {
+        let tweak = tap_tweak_hash(self, merkle_root)?;
+        let output_point = &self.point + &*G * tweak;
+
+        if output_point.is_at_infinity() {
+            return Err(TweakError::Infinity);
         }
+
+        Ok(XOnlyPublicKey::from_point(&output_point))
+    }
+
+    /// The P2TR scriptPubKey `OP_1 <32-byte key>` paying to this output key.
+    pub fn p2tr_script_pub_key(&self) -> Vec<u8> {
+        let mut script_pub_key = vec![0x51, 0x20];
+        script_pub_key.extend_from_slice(&self.serialize());
+
+        script_pub_key
     }
 }
 
-impl_s256_biguint_wrappers!(S256Point, Mul, mul);
+pub(crate) fn tap_tweak_hash(
+    internal_key: &XOnlyPublicKey,
+    merkle_root: Option<&[u8; 32]>,
+) -> Result<BigUint, TweakError> {
+    let mut data = internal_key.serialize().to_vec();
+    if let Some(merkle_root) = merkle_root {
+        data.extend_from_slice(merkle_root);
+    }
+
+    let tweak = BigUint::from_bytes_be(&tagged_hash("TapTweak", &data));
+
+    if tweak >= *N {
+        return Err(TweakError::InvalidTweak);
+    }
+
+    Ok(tweak)
+}
+
+/// The reason a BIP341 key tweak cannot be applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TweakError {
+    /// The tweak hash is not smaller than the group order.
+    InvalidTweak,
+    /// The tweaked key is the point at infinity (or the tweaked secret is zero).
+    Infinity,
+}
+
+impl std::fmt::Display for TweakError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TweakError::InvalidTweak => write!(f, "tweak is not smaller than the group order"),
+            TweakError::Infinity => write!(f, "tweaked key is the point at infinity"),
+        }
+    }
+}
+
+impl std::error::Error for TweakError {}
 
 #[derive(Debug, Clone)]
 pub struct Signature {
@@ -414,57 +781,286 @@ impl Signature {
         result
     }
 
-    pub fn parse_der(der: &[u8]) -> Result<Self, &'static str> {
-        let mut cursor = 0;
+    /// Parses a DER signature under the strict BIP66 rules that have been
+    /// consensus since block 363,725.
+    ///
+    /// `der` must not include the trailing sighash byte. Both integers must be
+    /// positive and minimally encoded, and the lengths must account for every
+    /// byte of the input.
+    pub fn parse_der(der: &[u8]) -> Result<Self, SignatureError> {
+        // Minimum: 0x30 [len] 0x02 [1] [r] 0x02 [1] [s]
+        if der.len() < 8 {
+            return Err(SignatureError::TooShort);
+        }
+        // Maximum: two 33-byte integers plus six header bytes
+        if der.len() > 72 {
+            return Err(SignatureError::TooLong);
+        }
+        if der[0] != 0x30 {
+            return Err(SignatureError::InvalidSequenceMarker);
+        }
+        if der[1] as usize != der.len() - 2 {
+            return Err(SignatureError::InvalidLength);
+        }
 
-        if der.len() < 2 {
-            return Err("Too short");
+        let r_len = der[3] as usize;
+        if 5 + r_len >= der.len() {
+            return Err(SignatureError::InvalidLength);
         }
-        if der[cursor] != 0x30 {
-            return Err("Invalid format");
+        let s_len = der[5 + r_len] as usize;
+        if r_len + s_len + 6 != der.len() {
+            return Err(SignatureError::InvalidLength);
         }
-        cursor += 1;
 
-        let len = der[cursor] as usize;
-        cursor += 1;
-        if len + 2 != der.len() {
-            return Err("Invalid length");
+        let r = Self::parse_strict_integer(&der[2..4 + r_len])?;
+        let s = Self::parse_strict_integer(&der[4 + r_len..])?;
+
+        Ok(Signature { r, s })
+    }
+
+    // Parses `0x02 [len] [bytes]`, where the length has already been checked
+    // against the input by the caller.
+    fn parse_strict_integer(encoded: &[u8]) -> Result<BigUint, SignatureError> {
+        if encoded[0] != 0x02 {
+            return Err(SignatureError::InvalidIntegerMarker);
         }
 
-        if cursor >= der.len() || der[cursor] != 0x02 {
-            return Err("Invalid r marker");
+        let bytes = &encoded[2..];
+        if bytes.is_empty() {
+            return Err(SignatureError::ZeroLengthInteger);
         }
-        cursor += 1;
+        if bytes[0] & 0x80 != 0 {
+            return Err(SignatureError::NegativeInteger);
+        }
+        // A leading zero is only allowed when the next byte would otherwise
+        // make the number negative.
+        if bytes.len() > 1 && bytes[0] == 0x00 && bytes[1] & 0x80 == 0 {
+            return Err(SignatureError::ExcessPadding);
+        }
+
+        Ok(BigUint::from_bytes_be(bytes))
+    }
+
+    /// Parses a DER signature the way pre-BIP66 nodes (via OpenSSL) did.
+    ///
+    /// This is needed to validate historical transactions. It accepts long-form
+    /// lengths, excess zero padding, integers with the sign bit set and
+    /// trailing garbage, mirroring libsecp256k1's
+    /// `ecdsa_signature_parse_der_lax`. It still rejects integers that do not
+    /// fit in 256 bits.
+    pub fn parse_der_lax(der: &[u8]) -> Result<Self, SignatureError> {
+        let mut cursor = 0;
 
-        if cursor >= der.len() {
-            return Err("Invalid r length byte");
+        if der.get(cursor) != Some(&0x30) {
+            return Err(SignatureError::InvalidSequenceMarker);
         }
-        let r_len = der[cursor] as usize;
         cursor += 1;
 
-        if cursor + r_len > der.len() {
-            return Err("Invalid r bytes");
+        // The sequence length is read but not checked against the input.
+        Self::read_lax_length(der, &mut cursor)?;
+
+        let r = Self::parse_lax_integer(der, &mut cursor)?;
+        let s = Self::parse_lax_integer(der, &mut cursor)?;
+
+        Ok(Signature { r, s })
+    }
+
+    fn read_lax_length(der: &[u8], cursor: &mut usize) -> Result<usize, SignatureError> {
+        let first = *der.get(*cursor).ok_or(SignatureError::TooShort)?;
+        *cursor += 1;
+
+        if first & 0x80 == 0 {
+            return Ok(first as usize);
+        }
+
+        // Long form: the low bits give the number of length bytes that follow.
+        let count = (first & 0x7f) as usize;
+        let bytes = der
+            .get(*cursor..*cursor + count)
+            .ok_or(SignatureError::TooShort)?;
+        *cursor += count;
+
+        let significant: Vec<u8> = bytes.iter().copied().skip_while(|&b| b == 0).collect();
+        if significant.len() > std::mem::size_of::<usize>() {
+            return Err(SignatureError::InvalidLength);
         }
-        let r = BigUint::from_bytes_be(&der[cursor..cursor + r_len]);
-        cursor += r_len;
 
-        if cursor >= der.len() || der[cursor] != 0x02 {
-            return Err("Invalid s marker");
+        Ok(significant
+            .iter()
+            .fold(0usize, |len, &byte| (len << 8) | byte as usize))
+    }
+
+    fn parse_lax_integer(der: &[u8], cursor: &mut usize) -> Result<BigUint, SignatureError> {
+        if der.get(*cursor) != Some(&0x02) {
+            return Err(SignatureError::InvalidIntegerMarker);
         }
-        cursor += 1;
+        *cursor += 1;
+
+        let len = Self::read_lax_length(der, cursor)?;
+        let bytes = der
+            .get(*cursor..cursor.saturating_add(len))
+            .ok_or(SignatureError::InvalidLength)?;
+        *cursor += len;
 
-        if cursor >= der.len() {
-            return Err("Invalid s length byte");
+        let significant: Vec<u8> = bytes.iter().copied().skip_while(|&b| b == 0).collect();
+        if significant.len() > 32 {
+            return Err(SignatureError::IntegerTooLarge);
         }
-        let s_len = der[cursor] as usize;
-        cursor += 1;
 
-        if cursor + s_len > der.len() {
-            return Err("Invalid s bytes");
+        Ok(BigUint::from_bytes_be(&significant))
+    }
+
+    /// Whether `s` is in the lower half of the group order, as BIP62/BIP146's
+    /// LOW_S rule requires.
+    pub fn is_low_s(&self) -> bool {
+        self.s <= *N_HALF
+    }
+
+    /// Returns the low-S form of this signature.
+    ///
+    /// ECDSA signatures are malleable: if `(r, s)` is valid, so is
+    /// `(r, N - s)`. Normalizing a third-party signature lets a verifier apply
+    /// the LOW_S policy without rejecting otherwise valid signatures.
+    pub fn normalize_s(&self) -> Signature {
+        if self.is_low_s() {
+            return self.clone();
         }
-        let s = BigUint::from_bytes_be(&der[cursor..cursor + s_len]);
 
-        Ok(Signature { r, s })
+        Signature {
+            r: self.r.clone(),
+            s: &*N - &self.s,
+        }
+    }
+}
+
+/// An ECDSA signature together with the recovery id needed to recover the
+/// signing key from it.
+#[derive(Debug, Clone)]
+pub struct RecoverableSignature {
+    pub signature: Signature,
+    pub recovery_id: u8,
+}
+
+impl RecoverableSignature {
+    pub fn recover(&self, z: &BigUint) -> Result<S256Point, PointError> {
+        S256Point::recover(z, &self.signature, self.recovery_id)
+    }
+
+    /// The 65-byte compact encoding: a header byte followed by `r` and `s`.
+    ///
+    /// The header is `27 + recovery_id`, plus 4 if the key should be
+    /// serialized compressed, as in Bitcoin Core's signed messages.
+    pub fn serialize_compact(&self, compressed: bool) -> [u8; 65] {
+        let mut serialized = [0u8; 65];
+        serialized[0] = 27 + self.recovery_id + if compressed { 4 } else { 0 };
+        serialized[1..33].copy_from_slice(&to_32_bytes(&self.signature.r));
+        serialized[33..].copy_from_slice(&to_32_bytes(&self.signature.s));
+
+        serialized
+    }
+
+    /// Parses the compact encoding, returning the signature and whether the
+    /// header marks the key as compressed.
+    pub fn parse_compact(serialized: &[u8; 65]) -> Result<(Self, bool), SignatureError> {
+        let header = serialized[0];
+        if !(27..=34).contains(&header) {
+            return Err(SignatureError::InvalidRecoveryId);
+        }
+
+        let signature = Signature {
+            r: BigUint::from_bytes_be(&serialized[1..33]),
+            s: BigUint::from_bytes_be(&serialized[33..]),
+        };
+        let recoverable = RecoverableSignature {
+            signature,
+            recovery_id: (header - 27) & 3,
+        };
+
+        Ok((recoverable, header >= 31))
+    }
+}
+
+/// The reason a byte string is not an acceptable signature encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    TooShort,
+    TooLong,
+    /// The encoding does not start with the `0x30` compound marker.
+    InvalidSequenceMarker,
+    /// A length field does not match the bytes that follow it.
+    InvalidLength,
+    /// An integer does not start with the `0x02` marker.
+    InvalidIntegerMarker,
+    ZeroLengthInteger,
+    /// An integer has its sign bit set.
+    NegativeInteger,
+    /// An integer has a leading zero byte it does not need.
+    ExcessPadding,
+    /// An integer is larger than 256 bits.
+    IntegerTooLarge,
+    /// A compact signature's header byte is not a valid recovery id.
+    InvalidRecoveryId,
+}
+
+impl std::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureError::TooShort => write!(f, "DER signature is too short"),
+            SignatureError::TooLong => write!(f, "DER signature is too long"),
+            SignatureError::InvalidSequenceMarker => {
+                write!(f, "DER signature does not start with a sequence marker")
+            }
+            SignatureError::InvalidLength => {
+                write!(f, "DER signature length does not match its contents")
+            }
+            SignatureError::InvalidIntegerMarker => {
+                write!(f, "DER signature is missing an integer marker")
+            }
+            SignatureError::ZeroLengthInteger => {
+                write!(f, "DER signature contains a zero-length integer")
+            }
+            SignatureError::NegativeInteger => {
+                write!(f, "DER signature contains a negative integer")
+            }
+            SignatureError::ExcessPadding => {
+                write!(f, "DER signature contains an integer with excess padding")
+            }
+            SignatureError::IntegerTooLarge => {
+                write!(f, "DER signature contains an integer larger than 256 bits")
+            }
+            SignatureError::InvalidRecoveryId => {
+                write!(f, "compact signature has an invalid recovery id")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// A BIP340 Schnorr signature: the x coordinate of the nonce point `R` and
+/// the scalar `s`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchnorrSignature {
+    pub r: BigUint,
+    pub s: BigUint,
+}
+
+impl SchnorrSignature {
+    pub fn serialize(&self) -> [u8; 64] {
+        let mut serialized = [0u8; 64];
+        serialized[..32].copy_from_slice(&to_32_bytes(&self.r));
+        serialized[32..].copy_from_slice(&to_32_bytes(&self.s));
+
+        serialized
+    }
+
+    /// Splits a 64-byte signature into `r` and `s`. Range checks are left to
+    /// `S256Point::verify_schnorr`, which rejects `r >= P` and `s >= N`.
+    pub fn parse(serialized: &[u8; 64]) -> Self {
+        SchnorrSignature {
+            r: BigUint::from_bytes_be(&serialized[..32]),
+            s: BigUint::from_bytes_be(&serialized[32..]),
+        }
     }
 }
 
@@ -476,15 +1072,22 @@ pub struct PrivateKey {
 
 impl PrivateKey {
     pub fn new(secret: BigUint) -> Self {
-        let point = &*G * &secret;
+        let point = S256Point::mul_generator(&secret);
         PrivateKey { secret, point }
     }
 
     pub fn sign(&self, z: &BigUint) -> Signature {
+        self.sign_recoverable(z).signature
+    }
+
+    /// Signs `z` and records which of the candidate public keys
+    /// `S256Point::recover` should return for the signature.
+    pub fn sign_recoverable(&self, z: &BigUint) -> RecoverableSignature {
         loop {
             let k = self.deterministic_k(z);
-            let r_point = &*G * &k;
-            let r = r_point.x_num().unwrap() % &*N; // k is always in range [1, N) so we can safely unwrap
+            let r_point = S256Point::mul_generator(&k);
+            let r_x = r_point.x_num().unwrap(); // k is always in range [1, N) so we can safely unwrap
+            let r = &r_x % &*N;
 
             // If r is zero, the signature is invalid because it doesn't bind the key.
             // This is astronomically rare, but we must handle it by generating a new k.
@@ -492,9 +1095,16 @@ impl PrivateKey {
                 continue;
             }
 
+            // Bit 0 is the parity of R's y coordinate; bit 1 records that R's
+            // x coordinate was at least N and was reduced to get r.
+            let mut recovery_id = u8::from(r_point.y().unwrap().is_odd());
+            if r_x >= *N {
+                recovery_id |= 2;
+            }
+
             let k_inv = k.modpow(&N_MINUS_2, &N);
             // s = (z + r * secret) / k
-            let mut s = ((&r * &self.secret + z) * k_inv) % &*N;
+            let s = ((&r * &self.secret + z) * k_inv) % &*N;
 
             // If s is zero, the signature is invalid (cannot compute inverse for verification).
             // We must generate a new k and retry.
@@ -503,13 +1113,18 @@ impl PrivateKey {
             }
 
             // BIP 62: Low S values.
-            // ECDSA signatures are malleable: if (r, s) is valid, so is (r, N - s).
-            // To prevent transaction malleability, Bitcoin requires s to be in the lower half of the group order.
-            if s > &*N / BigUint::from(2u32) {
-                s = &*N - s;
+            // To prevent transaction malleability, Bitcoin requires s to be in
+            // the lower half of the group order. See `Signature::normalize_s`.
+            // Negating s corresponds to negating R, which flips its y parity.
+            let signature = Signature { s, r };
+            if !signature.is_low_s() {
+                recovery_id ^= 1;
             }
 
-            return Signature { s, r };
+            return RecoverableSignature {
+                signature: signature.normalize_s(),
+                recovery_id,
+            };
         }
     }
 
@@ -517,6 +1132,120 @@ impl PrivateKey {
         &self.point
     }
 
+    pub(crate) fn secret(&self) -> &BigUint {
+        &self.secret
+    }
+
+    /// Derives the ECDH shared secret with `public_key`, hashed the way
+    /// libsecp256k1 does by default: SHA256 of the compressed SEC encoding
+    /// of the shared point.
+    ///
+    /// Fails if `public_key` or the shared point is the point at infinity,
+    /// the latter when the secret is a multiple of N.
+    pub fn ecdh(&self, public_key: &S256Point) -> Result<[u8; 32], PointError> {
+        let shared_point = self.ecdh_point(public_key)?;
+
+        Ok(Sha256::digest(shared_point.sec(Some(true))).into())
+    }
+
+    /// Derives the unhashed ECDH shared secret: the x coordinate of the
+    /// shared point. Protocols such as BIP324 hash this themselves.
+    ///
+    /// Fails in the same cases as `ecdh`.
+    pub fn ecdh_raw_x(&self, public_key: &S256Point) -> Result<[u8; 32], PointError> {
+        let shared_point = self.ecdh_point(public_key)?;
+
+        Ok(shared_point.x().unwrap().to_be_bytes()) // not infinity, checked in ecdh_point
+    }
+
+    fn ecdh_point(&self, public_key: &S256Point) -> Result<S256Point, PointError> {
+        if public_key.is_at_infinity() {
+            return Err(PointError::NotOnCurve);
+        }
+
+        // The group has prime order, so the product of a finite point is only
+        // the point at infinity when the secret is a multiple of N.
+        let shared_point = public_key.mul_secret(&self.secret);
+        if shared_point.is_at_infinity() {
+            return Err(PointError::NotOnCurve);
+        }
+
+        Ok(shared_point)
+    }
+
+    pub fn x_only_public_key(&self) -> (XOnlyPublicKey, Parity) {
+        XOnlyPublicKey::from_point(&self.point)
+    }
+
+    /// Tweaks this key into the secret for the BIP341 output key that
+    /// `XOnlyPublicKey::tap_tweak` derives from our public key.
+    ///
+    /// The internal key is x-only, so the secret is negated first if our
+    /// point has an odd y coordinate. The result can key-path-sign with
+    /// `sign_schnorr`, which handles the parity of the output key itself.
+    pub fn tap_tweak(&self, merkle_root: Option<&[u8; 32]>) -> Result<PrivateKey, TweakError> {
+        let (internal_key, parity) = self.x_only_public_key();
+        let tweak = tap_tweak_hash(&internal_key, merkle_root)?;
+
+        let secret = match parity {
+            Parity::Even => self.secret.clone(),
+            Parity::Odd => &*N - &self.secret,
+        };
+        let tweaked = (secret + tweak) % &*N;
+
+        if tweaked == BigUint::from(0u32) {
+            return Err(TweakError::Infinity);
+        }
+
+        Ok(PrivateKey::new(tweaked))
+    }
+
+    /// Signs `msg` with BIP340 Schnorr.
+    ///
+    /// `aux_rand` is mixed into the nonce to protect against side-channel
+    /// attacks; signing is still secure if it is all zeros, but fresh
+    /// randomness should be used whenever it is available.
+    ///
+    /// Returns `None` if the derived nonce is zero, which BIP340 treats as a
+    /// signing failure. It needs a SHA256 output equal to a multiple of N, so
+    /// in practice it never happens.
+    pub fn sign_schnorr(&self, msg: &[u8], aux_rand: &[u8; 32]) -> Option<SchnorrSignature> {
+        // The x-only public key always refers to the even-y point, so the
+        // secret is negated when our point has an odd y.
+        let d = if self.point.has_even_y() {
+            self.secret.clone()
+        } else {
+            &*N - &self.secret
+        };
+        let px_bytes = self.point.x_only();
+
+        let aux_hash = tagged_hash("BIP0340/aux", aux_rand);
+        let mut t = to_32_bytes(&d);
+        for (byte, mask) in t.iter_mut().zip(aux_hash) {
+            *byte ^= mask;
+        }
+
+        let mut nonce_data = Vec::with_capacity(64 + msg.len());
+        nonce_data.extend_from_slice(&t);
+        nonce_data.extend_from_slice(&px_bytes);
+        nonce_data.extend_from_slice(msg);
+        let k = BigUint::from_bytes_be(&tagged_hash("BIP0340/nonce", &nonce_data)) % &*N;
+
+        // A zero nonce would reveal the secret.
+        if k == BigUint::from(0u32) {
+            return None;
+        }
+
+        let r_point = S256Point::mul_generator(&k);
+        let k = if r_point.has_even_y() { k } else { &*N - k };
+        let r = r_point.x_num().unwrap();
+
+        let e = schnorr_challenge(&to_32_bytes(&r), &px_bytes, msg);
+        let s = (k + e * d) % &*N;
+
+        Some(SchnorrSignature { r, s })
+    }
+
     fn deterministic_k(&self, z: &BigUint) -> BigUint {
         let mut k = [0u8; 32];
         let mut v = [1u8; 32];
@@ -574,75 +1303,11 @@ impl PrivateKey {
     }
 }
 
-#[cfg(test)]
-mod s256_field_element_tests {
-    use super::*;
-    use num_bigint::BigUint;
-
-    #[test]
-    fn test_new() {
-        // Valid case
-        let el1 = S256FieldElement::new(BigUint::from(12345u32));
-        assert!(el1.is_ok());
-
-        // Invalid case: num >= prime
-        let num2 = (**S256_PRIME).clone();
-        let el2 = S256FieldElement::new(num2);
-        assert!(el2.is_err());
-        match el2 {
-            Err(FieldElementError::InvalidNum(_, _)) => (), // Expected
-            _ => panic!("Expected InvalidNum error"),
-        }
-    }
-
-    #[test]
-    fn test_add_sub() {
-        let a = S256FieldElement::new(BigUint::from(100u32)).unwrap();
-        let b = S256FieldElement::new(BigUint::from(200u32)).unwrap();
-        let c = S256FieldElement::new(BigUint::from(300u32)).unwrap();
-
-        assert_eq!(a.clone() + b.clone(), c);
-        assert_eq!(c - b, a);
-    }
-
-    #[test]
-    fn test_add_sub_wrap_around() {
-        // p - 1
-        let p_minus_1 = S256FieldElement::new(&**S256_PRIME - BigUint::from(1u32)).unwrap();
-        // 2
-        let two = S256FieldElement::new(BigUint::from(2u32)).unwrap();
-        // (p - 1) + 2 = p + 1 = 1 (mod p)
-        let one = S256FieldElement::new(BigUint::from(1u32)).unwrap();
-
-        assert_eq!(p_minus_1.clone() + two.clone(), one);
-        assert_eq!(one - two, p_minus_1);
-    }
-
-    #[test]
-    fn test_mul_div() {
-        let a = S256FieldElement::new(BigUint::from(10u32)).unwrap();
-        let b = S256FieldElement::new(BigUint::from(20u32)).unwrap();
-        let c = S256FieldElement::new(BigUint::from(200u32)).unwrap();
-
-        assert_eq!(a.clone() * b.clone(), c);
-        assert_eq!(c / b, a);
-    }
-
-    #[test]
-    fn test_pow_and_fermats_little_theorem() {
-        // a^(p-1) === 1 (mod p)
-        let p_minus_1 = &**S256_PRIME - BigUint::from(1u32);
-        let a = S256FieldElement::new(BigUint::from(999u32)).unwrap();
-        let one = S256FieldElement::new(BigUint::from(1u32)).unwrap();
-
-        assert_eq!(a.pow(p_minus_1), one);
-    }
-}
-
 #[cfg(test)]
 mod s256_point_tests {
     use super::*;
     use num_bigint::BigUint;
+    use rand::{RngCore, SeedableRng, rngs::StdRng};
 
     #[test]
     fn test_point_creation_and_on_curve() {
@@ -673,6 +1338,65 @@ mod s256_point_tests {
         assert_eq!(&inf + &g, g);
     }
 
+    #[test]
+    fn test_point_on_generic_curve() {
+        let g = G.point();
+        assert_eq!(g.x().unwrap().num(), &*GX);
+        assert_eq!(g.y().unwrap().num(), &*GY);
+
+        // Doubling on the generic curve agrees with the fixed-width arithmetic.
+        assert_eq!(&g + &g, (&*G + &*G).point());
+        assert!(S256Point::infinity().unwrap().point().is_at_infinity());
+    }
+
+    #[test]
+    fn test_mul_generator() {
+        let mut rng = StdRng::seed_from_u64(40);
+        let mut scalars = vec![
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            &*N - 1u32,
+            N.clone(),
+        ];
+        for _ in 0..10 {
+            let mut bytes = [0u8; 32];
+            rng.fill_bytes(&mut bytes);
+            scalars.push(BigUint::from_bytes_be(&bytes));
+        }
+
+        for scalar in &scalars {
+            assert_eq!(S256Point::mul_generator(scalar), &*G * scalar);
+        }
+    }
+
+    #[test]
+    fn test_mul_add() {
+        let mut rng = StdRng::seed_from_u64(41);
+        let mut random_scalar = || {
+            let mut bytes = [0u8; 32];
+            rng.fill_bytes(&mut bytes);
+            BigUint::from_bytes_be(&bytes)
+        };
+        let point = &*G * random_scalar();
+
+        for _ in 0..10 {
+            let (u, v) = (random_scalar(), random_scalar());
+            assert_eq!(
+                S256Point::mul_add(&u, &v, &point),
+                &(&*G * &u) + &(&point * &v)
+            );
+        }
+
+        let zero = BigUint::from(0u32);
+        let k = random_scalar() % &*N;
+        let inf = S256Point::infinity().unwrap();
+        assert_eq!(S256Point::mul_add(&zero, &k, &point), &point * &k);
+        assert_eq!(S256Point::mul_add(&k, &zero, &point), &*G * &k);
+        assert_eq!(S256Point::mul_add(&k, &k, &inf), &*G * &k);
+        // k.G + (N - k).G is the point at infinity.
+        assert_eq!(S256Point::mul_add(&k, &(&*N - &k), &G), inf);
+    }
+
     #[test]
     fn test_add_inverse_points() {
         let g = G.clone();
@@ -836,6 +1560,32 @@ mod private_key_tests {
     use super::*;
     use num_bigint::BigUint;
 
+    #[test]
+    fn test_verify_batch() {
+        let keys: Vec<PrivateKey> = (1..=20u32)
+            .map(|i| PrivateKey::new(BigUint::from(i * 104729)))
+            .collect();
+        let hashes: Vec<BigUint> = (1..=20u32).map(|i| BigUint::from(i) << 200u32).collect();
+        let signatures: Vec<Signature> = keys
+            .iter()
+            .zip(&hashes)
+            .map(|(key, z)| key.sign(z))
+            .collect();
+
+        let mut items: Vec<(&S256Point, &BigUint, &Signature)> = keys
+            .iter()
+            .zip(&hashes)
+            .zip(&signatures)
+            .map(|((key, z), sig)| (&key.point, z, sig))
+            .collect();
+
+        assert!(S256Point::verify_batch(&items));
+        assert!(S256Point::verify_batch(&[]));
+
+        items[17].1 = &hashes[3];
+        assert!(!S256Point::verify_batch(&items));
+    }
+
     #[test]
     fn test_sign() {
         let secret = BigUint::parse_bytes(
@@ -886,20 +1636,126 @@ mod private_key_tests {
             assert!(pk.point().verify(&z, &sig));
         }
     }
+
+    #[test]
+    fn test_ecdh() {
+        let alice = PrivateKey::new(
+            BigUint::parse_bytes(
+                b"3b1a5c4e9f2d7c6b5a4938271605f4e3d2c1b0a998877665544332211ffeeddc",
+                16,
+            )
+            .unwrap(),
+        );
+        let bob = PrivateKey::new(
+            BigUint::parse_bytes(
+                b"11223344556677889900aabbccddeeff00112233445566778899aabbccddeeff",
+                16,
+            )
+            .unwrap(),
+        );
+
+        let shared = alice.ecdh(bob.point()).unwrap();
+        assert_eq!(shared, bob.ecdh(alice.point()).unwrap());
+
+        // Computed independently in Python: SHA256 of the compressed shared point,
+        // and the shared point's x coordinate.
+        assert_eq!(
+            BigUint::from_bytes_be(&shared),
+            BigUint::parse_bytes(
+                b"2ad88f942fa9501e13323192f56289ffe4ca99f891e94fa68d7c2cbc50bb9a6e",
+                16
+            )
+            .unwrap()
+        );
+        let raw_x = alice.ecdh_raw_x(bob.point()).unwrap();
+        assert_eq!(raw_x, bob.ecdh_raw_x(alice.point()).unwrap());
+        assert_eq!(
+            BigUint::from_bytes_be(&raw_x),
+            BigUint::parse_bytes(
+                b"4dc0365a7c25cb15a7b01a3f866d692d8dd77f235ecbfda2f0354f00ee839694",
+                16
+            )
+            .unwrap()
+        );
+
+        assert!(alice.ecdh(&S256Point::infinity().unwrap()).is_err());
+
+        // A secret of N puts the shared point at infinity.
+        let zero = PrivateKey::new(N.clone());
+        assert!(zero.ecdh(bob.point()).is_err());
+        assert!(zero.ecdh_raw_x(bob.point()).is_err());
+    }
+
+    #[test]
+    fn test_sign_recoverable() {
+        for (secret, z) in [(12345u32, 99999u32), (1, 1), (0xdeadbeef, 42), (7, 0xcafe)] {
+            let pk = PrivateKey::new(BigUint::from(secret));
+            let z = BigUint::from(z);
+            let recoverable = pk.sign_recoverable(&z);
+
+            assert!(recoverable.signature.is_low_s());
+            assert_eq!(recoverable.signature.r, pk.sign(&z).r);
+            assert_eq!(&recoverable.recover(&z).unwrap(), pk.point());
+
+            // The other parity gives a different key that also verifies.
+            let other = S256Point::recover(&z, &recoverable.signature, recoverable.recovery_id ^ 1)
+                .unwrap();
+            assert_ne!(&other, pk.point());
+            assert!(other.verify(&z, &recoverable.signature));
+        }
+    }
+
+    #[test]
+    fn test_recover_rejects_invalid_input() {
+        let pk = PrivateKey::new(BigUint::from(12345u32));
+        let z = BigUint::from(99999u32);
+        let sig = pk.sign(&z);
+
+        assert!(S256Point::recover(&z, &sig, 4).is_err());
+
+        let zero_r = Signature {
+            r: BigUint::from(0u32),
+            s: sig.s.clone(),
+        };
+        assert!(S256Point::recover(&z, &zero_r, 0).is_err());
+
+        // r + N is not below P for this r, so bit 1 cannot be set.
+        assert!(S256Point::recover(&z, &sig, 2).is_err());
+    }
+
+    #[test]
+    fn test_compact_serialization() {
+        let pk = PrivateKey::new(BigUint::from(12345u32));
+        let z = BigUint::from(99999u32);
+        let recoverable = pk.sign_recoverable(&z);
+
+        for compressed in [false, true] {
+            let serialized = recoverable.serialize_compact(compressed);
+            let expected_header = 27 + recoverable.recovery_id + if compressed { 4 } else { 0 };
+            assert_eq!(serialized[0], expected_header);
+
+            let (parsed, parsed_compressed) =
+                RecoverableSignature::parse_compact(&serialized).unwrap();
+            assert_eq!(parsed_compressed, compressed);
+            assert_eq!(parsed.recovery_id, recoverable.recovery_id);
+            assert_eq!(&parsed.recover(&z).unwrap(), pk.point());
+        }
+
+        let mut bad_header = recoverable.serialize_compact(true);
+        bad_header[0] = 35;
+        assert_eq!(
+            RecoverableSignature::parse_compact(&bad_header).unwrap_err(),
+            SignatureError::InvalidRecoveryId
+        );
+    }
 }
 
 #[cfg(test)]
 mod signature_tests {
     use super::*;
+    use crate::test_utils::hex_to_bytes;
     use num_bigint::BigUint;
 
-    fn hex_to_bytes(hex: &str) -> Vec<u8> {
-        (0..hex.len())
-            .step_by(2)
-            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
-            .collect()
-    }
-
     #[test]
     fn test_der_serialization_example() {
         let r_hex = "37206a0610995c58074999cb9767b87af4c4978db68c06e8e6e81d282047a7c6";
@@ -1004,4 +1860,371 @@ mod signature_tests {
         // Too short
         assert!(Signature::parse_der(&[0x30, 0x00]).is_err());
     }
+
+    #[test]
+    fn test_parse_der_strict_rejections() {
+        // r = 1, s = 1
+        assert!(Signature::parse_der(&hex_to_bytes("3006020101020101")).is_ok());
+
+        // Negative r (sign bit set without a padding byte)
+        assert_eq!(
+            Signature::parse_der(&hex_to_bytes("3006020181020101")).unwrap_err(),
+            SignatureError::NegativeInteger
+        );
+
+        // Excess padding on s
+        assert_eq!(
+            Signature::parse_der(&hex_to_bytes("300702010102020001")).unwrap_err(),
+            SignatureError::ExcessPadding
+        );
+
+        // Total length that does not match the input
+        assert_eq!(
+            Signature::parse_der(&hex_to_bytes("3007020101020101")).unwrap_err(),
+            SignatureError::InvalidLength
+        );
+
+        // Trailing bytes after s
+        assert_eq!(
+            Signature::parse_der(&hex_to_bytes("300602010102010100")).unwrap_err(),
+            SignatureError::InvalidLength
+        );
+
+        // Zero-length r
+        assert_eq!(
+            Signature::parse_der(&hex_to_bytes("30060200020201010000")).unwrap_err(),
+            SignatureError::InvalidLength
+        );
+        assert_eq!(
+            Signature::parse_der(&hex_to_bytes("3006020002020101")).unwrap_err(),
+            SignatureError::ZeroLengthInteger
+        );
+
+        // Wrong integer marker
+        assert_eq!(
+            Signature::parse_der(&hex_to_bytes("3006030101020101")).unwrap_err(),
+            SignatureError::InvalidIntegerMarker
+        );
+    }
+
+    #[test]
+    fn test_parse_der_lax() {
+        // Everything strict DER accepts, the lax parser accepts too.
+        let strict = hex_to_bytes("3006020101020101");
+        let sig = Signature::parse_der_lax(&strict).unwrap();
+        assert_eq!(sig.r, BigUint::from(1u32));
+        assert_eq!(sig.s, BigUint::from(1u32));
+
+        // Excess padding, a "negative" r, long-form lengths and trailing
+        // garbage were all accepted before BIP66.
+        let padded = hex_to_bytes("300702010102020001");
+        assert_eq!(
+            Signature::parse_der_lax(&padded).unwrap().s,
+            BigUint::from(1u32)
+        );
+
+        let negative = hex_to_bytes("3006020181020101");
+        assert_eq!(
+            Signature::parse_der_lax(&negative).unwrap().r,
+            BigUint::from(0x81u32)
+        );
+
+        let long_form = hex_to_bytes("30810702810101020101ffff");
+        let sig = Signature::parse_der_lax(&long_form).unwrap();
+        assert_eq!(sig.r, BigUint::from(1u32));
+        assert_eq!(sig.s, BigUint::from(1u32));
+
+        // Integers wider than 256 bits are still rejected.
+        let mut too_large = hex_to_bytes("3027022201");
+        too_large.extend([0u8; 32]);
+        too_large.extend(hex_to_bytes("020101"));
+        assert_eq!(
+            Signature::parse_der_lax(&too_large).unwrap_err(),
+            SignatureError::IntegerTooLarge
+        );
+
+        assert_eq!(
+            Signature::parse_der_lax(&hex_to_bytes("3106020101020101")).unwrap_err(),
+            SignatureError::InvalidSequenceMarker
+        );
+    }
+
+    #[test]
+    fn test_low_s_normalization() {
+        let n_half = &*N / BigUint::from(2u32);
+
+        let low = Signature {
+            r: BigUint::from(1u32),
+            s: n_half.clone(),
+        };
+        assert!(low.is_low_s());
+        assert_eq!(low.normalize_s().s, low.s);
+
+        let high = Signature {
+            r: BigUint::from(1u32),
+            s: &n_half + BigUint::from(1u32),
+        };
+        assert!(!high.is_low_s());
+        let normalized = high.normalize_s();
+        assert!(normalized.is_low_s());
+        assert_eq!(normalized.s, &*N - &high.s);
+        assert_eq!(normalized.r, high.r);
+
+        // A high-S signature and its normalized form verify against the same key.
+        let pk = PrivateKey::new(BigUint::from(12345u32));
+        let z = BigUint::from(99999u32);
+        let sig = pk.sign(&z);
+        let malleated = Signature {
+            r: sig.r.clone(),
+            s: &*N - &sig.s,
+        };
+        assert!(!malleated.is_low_s());
+        assert!(pk.point().verify(&z, &malleated));
+        assert!(pk.point().verify(&z, &malleated.normalize_s()));
+    }
+}
+
+#[cfg(test)]
+mod schnorr_tests {
+    use super::*;
+    use crate::test_utils::hex_to_bytes;
+    use num_bigint::BigUint;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    const BIP340_VECTORS: &str = include_str!("../test_data/bip340_test_vectors.csv");
+
+    #[test]
+    fn test_tagged_hash() {
+        let tag_hash = Sha256::digest(b"BIP0340/challenge");
+        let mut preimage = tag_hash.to_vec();
+        preimage.extend_from_slice(&tag_hash);
+        preimage.extend_from_slice(b"msg");
+
+        let expected: [u8; 32] = Sha256::digest(&preimage).into();
+        assert_eq!(tagged_hash("BIP0340/challenge", b"msg"), expected);
+    }
+
+    #[test]
+    fn test_bip340_vectors() {
+        for line in BIP340_VECTORS.lines().skip(1) {
+            let fields: Vec<&str> = line.splitn(8, ',').collect();
+            let (index, secret_key, public_key, aux_rand, message, signature, expected) = (
+                fields[0], fields[1], fields[2], fields[3], fields[4], fields[5], fields[6],
+            );
+
+            let msg = hex_to_bytes(message);
+            let sig_bytes: [u8; 64] = hex_to_bytes(signature).try_into().unwrap();
+            let sig = SchnorrSignature::parse(&sig_bytes);
+            assert_eq!(sig.serialize(), sig_bytes, "vector {index}: round trip");
+
+            if !secret_key.is_empty() {
+                let private_key =
+                    PrivateKey::new(BigUint::parse_bytes(secret_key.as_bytes(), 16).unwrap());
+                let aux: [u8; 32] = hex_to_bytes(aux_rand).try_into().unwrap();
+
+                assert_eq!(
+                    private_key.point().x_only().to_vec(),
+                    hex_to_bytes(public_key),
+                    "vector {index}: public key"
+                );
+                assert_eq!(
+                    private_key.sign_schnorr(&msg, &aux).unwrap(),
+                    sig,
+                    "vector {index}: signature"
+                );
+            }
+
+            let px = BigUint::from_bytes_be(&hex_to_bytes(public_key));
+            let verified = S256Point::lift_x(&px)
+                .map(|point| point.verify_schnorr(&msg, &sig))
+                .unwrap_or(false);
+
+            assert_eq!(verified, expected == "TRUE", "vector {index}: verification");
+        }
+    }
+
+    #[test]
+    fn test_bip340_vectors_as_single_batches() {
+        let mut rng = StdRng::seed_from_u64(43);
+
+        for line in BIP340_VECTORS.lines().skip(1) {
+            let fields: Vec<&str> = line.splitn(8, ',').collect();
+            let (index, public_key, message, signature, expected) =
+                (fields[0], fields[2], fields[4], fields[5], fields[6]);
+
+            let msg = hex_to_bytes(message);
+            let sig = SchnorrSignature::parse(&hex_to_bytes(signature).try_into().unwrap());
+            let px = BigUint::from_bytes_be(&hex_to_bytes(public_key));
+            let verified = S256Point::lift_x(&px)
+                .map(|point| S256Point::verify_schnorr_batch(&[(&point, &msg, &sig)], &mut rng))
+                .unwrap_or(false);
+
+            assert_eq!(verified, expected == "TRUE", "vector {index}: verification");
+        }
+    }
+
+    #[test]
+    fn test_verify_schnorr_batch() {
+        let mut rng = StdRng::seed_from_u64(43);
+        let keys: Vec<PrivateKey> = (1..=20u32)
+            .map(|i| PrivateKey::new(BigUint::from(i * 7919)))
+            .collect();
+        let messages: Vec<Vec<u8>> = (0..20u8).map(|i| vec![i; usize::from(i)]).collect();
+        let signatures: Vec<SchnorrSignature> = keys
+            .iter()
+            .zip(&messages)
+            .map(|(key, msg)| key.sign_schnorr(msg, &[0u8; 32]).unwrap())
+            .collect();
+
+        let mut items: Vec<(&S256Point, &[u8], &SchnorrSignature)> = keys
+            .iter()
+            .zip(&messages)
+            .zip(&signatures)
+            .map(|((key, msg), sig)| (key.point(), msg.as_slice(), sig))
+            .collect();
+
+        assert!(S256Point::verify_schnorr_batch(&items, &mut rng));
+        assert!(S256Point::verify_schnorr_batch(&[], &mut rng));
+
+        // One bad signature anywhere fails the whole batch.
+        let forged = SchnorrSignature {
+            r: signatures[5].r.clone(),
+            s: (&signatures[5].s + 1u32) % &*N,
+        };
+        items[5].2 = &forged;
+        assert!(!S256Point::verify_schnorr_batch(&items, &mut rng));
+
+        items[5].2 = &signatures[5];
+        items[12].1 = b"another message";
+        assert!(!S256Point::verify_schnorr_batch(&items, &mut rng));
+    }
+
+    #[test]
+    fn test_lift_x() {
+        let lifted = S256Point::lift_x(&G.x_num().unwrap()).unwrap();
+        assert!(lifted.has_even_y());
+        assert_eq!(lifted.x_num(), G.x_num());
+
+        // x = 5 has no point on secp256k1: 5^3 + 7 = 132 is not a square mod P.
+        assert!(S256Point::lift_x(&BigUint::from(5u32)).is_err());
+
+        // x >= P is not a field element.
+        assert!(S256Point::lift_x(&S256_PRIME).is_err());
+    }
+
+    #[test]
+    fn test_verify_schnorr_ignores_y_parity() {
+        let private_key = PrivateKey::new(BigUint::from(12345u32));
+        let msg = b"x-only keys";
+        let sig = private_key.sign_schnorr(msg, &[7u8; 32]).unwrap();
+
+        let point = private_key.point();
+        let negated = S256Point::new(
+            S256FieldElement::new(point.x_num().unwrap()).unwrap(),
+            S256FieldElement::new(&**S256_PRIME - point.y_num().unwrap()).unwrap(),
+        )
+        .unwrap();
+
+        assert!(point.verify_schnorr(msg, &sig));
+        assert!(negated.verify_schnorr(msg, &sig));
+        assert!(!point.verify_schnorr(b"another message", &sig));
+    }
+}
+
+#[cfg(test)]
+mod taproot_tweak_tests {
+    use super::*;
+    use crate::test_utils::hex_to_bytes;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_x_only_round_trip() {
+        let (key, parity) = XOnlyPublicKey::from_point(&G);
+        assert_eq!(parity, Parity::Even);
+        assert_eq!(XOnlyPublicKey::parse(&key.serialize()).unwrap(), key);
+        assert!(key.point().has_even_y());
+
+        // 6G has an odd y coordinate, so the x-only key is its negation.
+        let six_g = &*G * BigUint::from(6u32);
+        let (key, parity) = XOnlyPublicKey::from_point(&six_g);
+        assert_eq!(parity, Parity::Odd);
+        assert_eq!(key.point().x_num(), six_g.x_num());
+        assert_ne!(key.point(), &six_g);
+
+        assert!(matches!(
+            XOnlyPublicKey::parse(&[0u8; 33]),
+            Err(PointError::CannotParse)
+        ));
+    }
+
+    // From the BIP341 wallet test vectors (`scriptPubKey`, entries 0 and 1).
+    #[test]
+    fn test_tap_tweak_public_key() {
+        let internal_key = XOnlyPublicKey::parse(&hex_to_bytes(
+            "d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d",
+        ))
+        .unwrap();
+        let (output_key, _) = internal_key.tap_tweak(None).unwrap();
+        assert_eq!(
+            output_key.p2tr_script_pub_key(),
+            hex_to_bytes("512053a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343")
+        );
+
+        let internal_key = XOnlyPublicKey::parse(&hex_to_bytes(
+            "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+        ))
+        .unwrap();
+        let merkle_root: [u8; 32] =
+            hex_to_bytes("5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21")
+                .try_into()
+                .unwrap();
+        let (output_key, parity) = internal_key.tap_tweak(Some(&merkle_root)).unwrap();
+        assert_eq!(
+            output_key.serialize().to_vec(),
+            hex_to_bytes("147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3")
+        );
+        assert_eq!(parity, Parity::Odd);
+    }
+
+    // From the BIP341 wallet test vectors (`keyPathSpending`, input 0).
+    #[test]
+    fn test_tap_tweak_private_key() {
+        let internal_secret = BigUint::parse_bytes(
+            b"6b973d88838f27366ed61c9ad6367663045cb456e28335c109e30717ae0c6baa",
+            16,
+        )
+        .unwrap();
+        let private_key = PrivateKey::new(internal_secret);
+        let tweaked = private_key.tap_tweak(None).unwrap();
+
+        assert_eq!(
+            tweaked.secret,
+            BigUint::parse_bytes(
+                b"2405b971772ad26915c8dcdf10f238753a9b837e5f8e6a86fd7c0cce5b7296d9",
+                16,
+            )
+            .unwrap()
+        );
+
+        let (internal_key, _) = private_key.x_only_public_key();
+        let (output_key, _) = internal_key.tap_tweak(None).unwrap();
+        assert_eq!(tweaked.x_only_public_key().0, output_key);
+
+        let sig = tweaked.sign_schnorr(b"key path", &[0u8; 32]).unwrap();
+        assert!(output_key.verify_schnorr(b"key path", &sig));
+    }
+
+    #[test]
+    fn test_tap_tweak_odd_internal_key() {
+        // Negating an odd-y internal secret must still match the public tweak.
+        let private_key = PrivateKey::new(BigUint::from(6u32));
+        let (internal_key, parity) = private_key.x_only_public_key();
+        assert_eq!(parity, Parity::Odd);
+
+        let merkle_root = [0x42u8; 32];
+        let tweaked = private_key.tap_tweak(Some(&merkle_root)).unwrap();
+        let (output_key, _) = internal_key.tap_tweak(Some(&merkle_root)).unwrap();
+
+        assert_eq!(tweaked.x_only_public_key().0, output_key);
+    }
 }
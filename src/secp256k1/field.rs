@@ -0,0 +1,592 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use num_bigint::BigUint;
+
+use super::S256_PRIME;
+use crate::finite_field::FieldElementError;
+
+// P = 2^256 - 2^32 - 977 as four little-endian 64-bit limbs.
+const P_LIMBS: [u64; 4] = [
+    0xfffffffefffffc2f,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+];
+
+// 2^256 mod P. Because P is this close to 2^256, anything that overflows the
+// top limb can be folded back in by multiplying it by this 33-bit constant.
+const R: u64 = 0x1000003d1;
+
+// P - 2, the exponent that inverts an element by Fermat's little theorem.
+const P_MINUS_2: [u64; 4] = [
+    0xfffffffefffffc2d,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+];
+
+// (P + 1) / 4. P = 3 (mod 4), so raising a square to this power gives a
+// square root.
+const SQRT_EXPONENT: [u64; 4] = [
+    0xffffffffbfffff0c,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0x3fffffffffffffff,
+];
+
+/// An element of the secp256k1 base field.
+///
+/// The value is held in four 64-bit limbs, least significant first, and is
+/// always fully reduced below P. Keeping it canonical means equality is a
+/// plain comparison of limbs, and no operation needs to allocate.
+///
+/// Addition, subtraction and multiplication run without branching on the
+/// values, so they are safe to use on secrets. `pow`, and through it
+/// division and `sqrt`, branch on the bits of the exponent only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct S256FieldElement {
+    limbs: [u64; 4],
+}
+
+impl S256FieldElement {
+    pub const ZERO: S256FieldElement = S256FieldElement { limbs: [0; 4] };
+    pub const ONE: S256FieldElement = S256FieldElement {
+        limbs: [1, 0, 0, 0],
+    };
+
+    /// A field element from a small constant.
+    pub(crate) const fn from_u64(num: u64) -> S256FieldElement {
+        S256FieldElement {
+            limbs: [num, 0, 0, 0],
+        }
+    }
+
+    pub fn new<E: Into<BigUint>>(num: E) -> Result<Self, FieldElementError> {
+        let num = num.into();
+
+        if num >= **S256_PRIME {
+            return Err(FieldElementError::InvalidNum(num, S256_PRIME.clone()));
+        }
+
+        let mut limbs = [0u64; 4];
+        for (limb, digit) in limbs.iter_mut().zip(num.iter_u64_digits()) {
+            *limb = digit;
+        }
+
+        Ok(S256FieldElement { limbs })
+    }
+
+    /// Parses a 32-byte big-endian number, failing if it is not below P.
+    pub fn from_be_bytes(bytes: &[u8; 32]) -> Result<Self, FieldElementError> {
+        let mut limbs = [0u64; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.rchunks_exact(8)) {
+            *limb = u64::from_be_bytes(chunk.try_into().unwrap()); // chunks are exactly 8 bytes
+        }
+
+        if !less_than_p(&limbs) {
+            return Err(FieldElementError::InvalidNum(
+                BigUint::from_bytes_be(bytes),
+                S256_PRIME.clone(),
+            ));
+        }
+
+        Ok(S256FieldElement { limbs })
+    }
+
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (chunk, limb) in bytes.rchunks_exact_mut(8).zip(self.limbs) {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn num(&self) -> BigUint {
+        BigUint::from_bytes_be(&self.to_be_bytes())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs == [0; 4]
+    }
+
+    pub fn is_odd(&self) -> bool {
+        self.limbs[0] & 1 == 1
+    }
+
+    /// Returns `b` if `choice` is 1 and `a` if it is 0, without branching.
+    pub(crate) fn conditional_select(
+        a: &S256FieldElement,
+        b: &S256FieldElement,
+        choice: u64,
+    ) -> S256FieldElement {
+        let choose_b = mask(choice);
+
+        S256FieldElement {
+            limbs: std::array::from_fn(|i| (b.limbs[i] & choose_b) | (a.limbs[i] & !choose_b)),
+        }
+    }
+
+    pub fn pow<E: Into<BigUint>>(&self, exponent: E) -> S256FieldElement {
+        let exponent: Vec<u64> = exponent.into().iter_u64_digits().collect();
+
+        self.pow_limbs(&exponent)
+    }
+
+    pub fn square(&self) -> S256FieldElement {
+        self * self
+    }
+
+    pub fn sqrt(&self) -> S256FieldElement {
+        self.pow_limbs(&SQRT_EXPONENT)
+    }
+
+    fn invert(&self) -> S256FieldElement {
+        self.pow_limbs(&P_MINUS_2)
+    }
+
+    /// Replaces every element with its inverse, using a single inversion and
+    /// 3(n - 1) multiplications (Montgomery's trick).
+    ///
+    /// Panics with "Division by zero" if any element is zero.
+    pub fn batch_invert(elements: &mut [S256FieldElement]) {
+        let Some(first) = elements.first() else {
+            return;
+        };
+
+        // prefixes[i] is the product of elements[0..=i].
+        let mut prefixes = Vec::with_capacity(elements.len());
+        let mut product = *first;
+        prefixes.push(product);
+        for element in &elements[1..] {
+            product = product * element;
+            prefixes.push(product);
+        }
+
+        if product.is_zero() {
+            panic!("Division by zero")
+        }
+
+        // inverse starts as 1 / (e0 * ... * e(n-1)). Multiplying by the
+        // prefix before e(i) leaves 1 / e(i), and multiplying by e(i) drops
+        // it from the running inverse.
+        let mut inverse = product.invert();
+        for i in (1..elements.len()).rev() {
+            let element = elements[i];
+            elements[i] = inverse * prefixes[i - 1];
+            inverse = inverse * element;
+        }
+        elements[0] = inverse;
+    }
+
+    /// Left-to-right square-and-multiply over little-endian exponent limbs.
+    fn pow_limbs(&self, exponent: &[u64]) -> S256FieldElement {
+        let mut result = S256FieldElement::ONE;
+
+        for limb in exponent.iter().rev() {
+            for bit in (0..64).rev() {
+                result = result.square();
+                if (limb >> bit) & 1 == 1 {
+                    result = result * self;
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Reduces an arbitrary `BigUint` into the field, as the generic
+    /// `FieldElement` does for its `BigUint` operands.
+    fn reduce(num: &BigUint) -> S256FieldElement {
+        S256FieldElement::new(num % &**S256_PRIME).unwrap() // reduced below P just above
+    }
+}
+
+fn less_than_p(limbs: &[u64; 4]) -> bool {
+    for i in (0..4).rev() {
+        if limbs[i] != P_LIMBS[i] {
+            return limbs[i] < P_LIMBS[i];
+        }
+    }
+
+    false
+}
+
+/// Adds a small value to a 256-bit number, returning the carry out of the top
+/// limb.
+fn add_small(limbs: &mut [u64; 4], value: u64) -> u64 {
+    let mut carry = value;
+    for limb in limbs.iter_mut() {
+        let (sum, overflow) = limb.overflowing_add(carry);
+        *limb = sum;
+        carry = u64::from(overflow);
+    }
+
+    carry
+}
+
+/// Expands a 0 or 1 into a mask of all zeros or all ones.
+fn mask(bit: u64) -> u64 {
+    bit.wrapping_neg()
+}
+
+/// Brings a value below 2^256 into [0, P). Subtracting P is the same as
+/// adding 2^256 - P = R and dropping the overflow, and the value was at least
+/// P exactly when that addition overflows.
+fn reduce_once(limbs: [u64; 4]) -> S256FieldElement {
+    let mut reduced = limbs;
+    let keep_reduced = mask(add_small(&mut reduced, R));
+
+    S256FieldElement {
+        limbs: std::array::from_fn(|i| (reduced[i] & keep_reduced) | (limbs[i] & !keep_reduced)),
+    }
+}
+
+/// Reduces a 512-bit product. The high half is worth `high * 2^256`, which is
+/// congruent to `high * R`, so it is folded into the low half twice: once for
+/// the full high half, and once for the few bits that overflow from that.
+fn reduce_wide(wide: [u64; 8]) -> S256FieldElement {
+    let mut limbs = [0u64; 4];
+    let mut carry: u128 = 0;
+    for i in 0..4 {
+        let value = u128::from(wide[i]) + u128::from(wide[i + 4]) * u128::from(R) + carry;
+        limbs[i] = value as u64;
+        carry = value >> 64;
+    }
+
+    // `carry` is at most 34 bits here, so `carry * R` fits in 67 bits.
+    let mut overflow = carry * u128::from(R);
+    for limb in limbs.iter_mut() {
+        let value = u128::from(*limb) + overflow;
+        *limb = value as u64;
+        overflow = value >> 64;
+    }
+
+    // If the second fold wrapped past 2^256, the low limbs are now tiny and
+    // one more R cannot overflow again.
+    add_small(&mut limbs, R & mask(overflow as u64));
+
+    reduce_once(limbs)
+}
+
+macro_rules! impl_field_wrappers {
+    ($trait:ident, $method:ident) => {
+        impl $trait<S256FieldElement> for S256FieldElement {
+            type Output = S256FieldElement;
+
+            fn $method(self, rhs: S256FieldElement) -> S256FieldElement {
+                (&self).$method(&rhs)
+            }
+        }
+
+        impl $trait<&S256FieldElement> for S256FieldElement {
+            type Output = S256FieldElement;
+
+            fn $method(self, rhs: &S256FieldElement) -> S256FieldElement {
+                (&self).$method(rhs)
+            }
+        }
+
+        impl $trait<S256FieldElement> for &S256FieldElement {
+            type Output = S256FieldElement;
+
+            fn $method(self, rhs: S256FieldElement) -> S256FieldElement {
+                self.$method(&rhs)
+            }
+        }
+    };
+}
+
+macro_rules! impl_biguint_wrappers {
+    ($trait:ident, $method:ident) => {
+        impl $trait<&BigUint> for &S256FieldElement {
+            type Output = S256FieldElement;
+
+            fn $method(self, rhs: &BigUint) -> S256FieldElement {
+                self.$method(&S256FieldElement::reduce(rhs))
+            }
+        }
+
+        impl $trait<BigUint> for &S256FieldElement {
+            type Output = S256FieldElement;
+
+            fn $method(self, rhs: BigUint) -> S256FieldElement {
+                self.$method(&rhs)
+            }
+        }
+
+        impl $trait<BigUint> for S256FieldElement {
+            type Output = S256FieldElement;
+
+            fn $method(self, rhs: BigUint) -> S256FieldElement {
+                (&self).$method(&rhs)
+            }
+        }
+
+        impl $trait<&BigUint> for S256FieldElement {
+            type Output = S256FieldElement;
+
+            fn $method(self, rhs: &BigUint) -> S256FieldElement {
+                (&self).$method(rhs)
+            }
+        }
+    };
+}
+
+impl Add for &S256FieldElement {
+    type Output = S256FieldElement;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut limbs = [0u64; 4];
+        let mut carry = false;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let (sum, overflow1) = self.limbs[i].overflowing_add(rhs.limbs[i]);
+            let (sum, overflow2) = sum.overflowing_add(u64::from(carry));
+            *limb = sum;
+            carry = overflow1 || overflow2;
+        }
+
+        // Both operands are below P, so a sum that wrapped past 2^256 is
+        // small enough that adding R for the lost 2^256 cannot wrap again.
+        add_small(&mut limbs, R & mask(u64::from(carry)));
+
+        reduce_once(limbs)
+    }
+}
+
+impl_field_wrappers!(Add, add);
+impl_biguint_wrappers!(Add, add);
+
+impl Sub for &S256FieldElement {
+    type Output = S256FieldElement;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let mut limbs = [0u64; 4];
+        let mut borrow = false;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let (difference, underflow1) = self.limbs[i].overflowing_sub(rhs.limbs[i]);
+            let (difference, underflow2) = difference.overflowing_sub(u64::from(borrow));
+            *limb = difference;
+            borrow = underflow1 || underflow2;
+        }
+
+        // A borrow left us 2^256 too high; adding P means subtracting R.
+        let mut borrow = R & mask(u64::from(borrow));
+        for limb in limbs.iter_mut() {
+            let (difference, underflow) = limb.overflowing_sub(borrow);
+            *limb = difference;
+            borrow = u64::from(underflow);
+        }
+
+        S256FieldElement { limbs }
+    }
+}
+
+impl_field_wrappers!(Sub, sub);
+
+impl Mul for &S256FieldElement {
+    type Output = S256FieldElement;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut wide = [0u64; 8];
+        for i in 0..4 {
+            let mut carry: u128 = 0;
+            for j in 0..4 {
+                let value = u128::from(self.limbs[i]) * u128::from(rhs.limbs[j])
+                    + u128::from(wide[i + j])
+                    + carry;
+                wide[i + j] = value as u64;
+                carry = value >> 64;
+            }
+            wide[i + 4] = carry as u64;
+        }
+
+        reduce_wide(wide)
+    }
+}
+
+impl_field_wrappers!(Mul, mul);
+impl_biguint_wrappers!(Mul, mul);
+
+impl Div for &S256FieldElement {
+    type Output = S256FieldElement;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        if rhs.is_zero() {
+            panic!("Division by zero")
+        }
+
+        self.mul(&rhs.invert())
+    }
+}
+
+impl_field_wrappers!(Div, div);
+
+impl Neg for &S256FieldElement {
+    type Output = S256FieldElement;
+
+    fn neg(self) -> Self::Output {
+        S256FieldElement::ZERO - self
+    }
+}
+
+impl Neg for S256FieldElement {
+    type Output = S256FieldElement;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::to_32_bytes;
+
+    #[test]
+    fn test_new() {
+        // Valid case
+        let el1 = S256FieldElement::new(BigUint::from(12345u32));
+        assert!(el1.is_ok());
+
+        // Invalid case: num >= prime
+        let num2 = (**S256_PRIME).clone();
+        let el2 = S256FieldElement::new(num2);
+        assert!(el2.is_err());
+        match el2 {
+            Err(FieldElementError::InvalidNum(_, _)) => (), // Expected
+            _ => panic!("Expected InvalidNum error"),
+        }
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let a = S256FieldElement::new(BigUint::from(100u32)).unwrap();
+        let b = S256FieldElement::new(BigUint::from(200u32)).unwrap();
+        let c = S256FieldElement::new(BigUint::from(300u32)).unwrap();
+
+        assert_eq!(a + b, c);
+        assert_eq!(c - b, a);
+    }
+
+    #[test]
+    fn test_add_sub_wrap_around() {
+        // p - 1
+        let p_minus_1 = S256FieldElement::new(&**S256_PRIME - BigUint::from(1u32)).unwrap();
+        // 2
+        let two = S256FieldElement::new(BigUint::from(2u32)).unwrap();
+        // (p - 1) + 2 = p + 1 = 1 (mod p)
+        let one = S256FieldElement::new(BigUint::from(1u32)).unwrap();
+
+        assert_eq!(p_minus_1 + two, one);
+        assert_eq!(one - two, p_minus_1);
+    }
+
+    #[test]
+    fn test_mul_div() {
+        let a = S256FieldElement::new(BigUint::from(10u32)).unwrap();
+        let b = S256FieldElement::new(BigUint::from(20u32)).unwrap();
+        let c = S256FieldElement::new(BigUint::from(200u32)).unwrap();
+
+        assert_eq!(a * b, c);
+        assert_eq!(c / b, a);
+    }
+
+    #[test]
+    fn test_pow_and_fermats_little_theorem() {
+        // a^(p-1) === 1 (mod p)
+        let p_minus_1 = &**S256_PRIME - BigUint::from(1u32);
+        let a = S256FieldElement::new(BigUint::from(999u32)).unwrap();
+        let one = S256FieldElement::new(BigUint::from(1u32)).unwrap();
+
+        assert_eq!(a.pow(p_minus_1), one);
+    }
+
+    #[test]
+    fn test_arithmetic_matches_biguint() {
+        use rand::{RngCore, SeedableRng, rngs::StdRng};
+
+        let prime = &**S256_PRIME;
+        let mut rng = StdRng::seed_from_u64(37);
+
+        // Random values plus the ones most likely to break carry handling.
+        let mut values = vec![
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            prime - 1u32,
+            prime - 2u32,
+            (BigUint::from(1u32) << 256) - prime,
+            BigUint::from(u64::MAX),
+        ];
+        for _ in 0..20 {
+            let mut bytes = [0u8; 32];
+            rng.fill_bytes(&mut bytes);
+            values.push(BigUint::from_bytes_be(&bytes) % prime);
+        }
+
+        for a in &values {
+            let fa = S256FieldElement::new(a.clone()).unwrap();
+            assert_eq!(fa.num(), *a);
+            assert_eq!(
+                S256FieldElement::from_be_bytes(&fa.to_be_bytes()).unwrap(),
+                fa
+            );
+
+            for b in &values {
+                let fb = S256FieldElement::new(b.clone()).unwrap();
+
+                assert_eq!((fa + fb).num(), (a + b) % prime);
+                assert_eq!((fa - fb).num(), (a + prime - b) % prime);
+                assert_eq!((fa * fb).num(), (a * b) % prime);
+                assert_eq!((fa * b).num(), (a * b) % prime);
+            }
+        }
+    }
+
+    #[test]
+    fn test_div_and_sqrt() {
+        let a = S256FieldElement::new(BigUint::from(123456789u32)).unwrap();
+        let b = S256FieldElement::new(&**S256_PRIME - 5u32).unwrap();
+
+        assert_eq!(a / b * b, a);
+        assert_eq!(a.square().sqrt().square(), a.square());
+    }
+
+    #[test]
+    fn test_from_be_bytes_rejects_unreduced() {
+        assert!(S256FieldElement::from_be_bytes(&[0xff; 32]).is_err());
+        assert!(S256FieldElement::from_be_bytes(&to_32_bytes(&S256_PRIME)).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Division by zero")]
+    fn test_div_by_zero() {
+        let _ = S256FieldElement::ONE / S256FieldElement::ZERO;
+    }
+
+    #[test]
+    fn test_batch_invert() {
+        let mut elements: Vec<S256FieldElement> = (1..=20u64)
+            .map(|i| {
+                S256FieldElement::from_u64(i.wrapping_mul(0x9e3779b97f4a7c15))
+                    - S256FieldElement::ONE
+            })
+            .collect();
+        let expected: Vec<S256FieldElement> = elements
+            .iter()
+            .map(|element| S256FieldElement::ONE / element)
+            .collect();
+
+        S256FieldElement::batch_invert(&mut elements);
+        assert_eq!(elements, expected);
+
+        S256FieldElement::batch_invert(&mut []);
+    }
+
+    #[test]
+    #[should_panic(expected = "Division by zero")]
+    fn test_batch_invert_zero_element() {
+        let mut elements = [S256FieldElement::ONE, S256FieldElement::ZERO];
+        S256FieldElement::batch_invert(&mut elements);
+    }
+}
@@ -0,0 +1,170 @@
+use std::sync::LazyLock;
+
+use num_bigint::{BigInt, BigUint, Sign};
+
+use super::jacobian::{self, JacobianPoint};
+use super::{N, S256FieldElement, S256Point, S256PointKind, scalar_limbs};
+
+// secp256k1 has an endomorphism that is cheap to evaluate: for a cube root of
+// unity beta modulo P, (x, y) -> (beta * x, y) maps every point to lambda
+// times itself, where lambda is a cube root of unity modulo N. Splitting a
+// scalar k into k1 + k2 * lambda with both halves around 128 bits turns one
+// 256-bit multiplication into two 128-bit ones sharing their doublings
+// (Gallant, Lambert and Vanstone, "Faster Point Multiplication on Elliptic
+// Curves with Efficient Endomorphisms").
+
+pub(crate) static BETA: LazyLock<S256FieldElement> = LazyLock::new(|| {
+    let beta = BigUint::parse_bytes(
+        b"7ae96a2b657c07106e64479eac3434e99cf0497512f58995c1396c28719501ee",
+        16,
+    )
+    .unwrap();
+    S256FieldElement::new(beta).unwrap()
+});
+
+// A short basis of the lattice of (a, b) with a + b * lambda = 0 mod N:
+// (A1, B1) and (A2, B2) with B2 = A1. B1 is negative; MINUS_B1 is -B1.
+static A1: LazyLock<BigInt> = LazyLock::new(|| hex_bigint(b"3086d221a7d46bcde86c90e49284eb15"));
+static MINUS_B1: LazyLock<BigInt> =
+    LazyLock::new(|| hex_bigint(b"e4437ed6010e88286f547fa90abfe4c3"));
+static A2: LazyLock<BigInt> = LazyLock::new(|| hex_bigint(b"114ca50f7a8e2f3f657c1108d9d44cfd8"));
+
+fn hex_bigint(hex: &[u8]) -> BigInt {
+    BigInt::parse_bytes(hex, 16).unwrap()
+}
+
+/// Splits `scalar` into `(k1, k2)` with `k1 + k2 * lambda = scalar mod N`,
+/// both at most 128 bits in absolute value.
+///
+/// The scalar is written in the basis above with rounded coefficients
+/// `c1 = round(B2 * k / N)` and `c2 = round(-B1 * k / N)`; what is left over
+/// is a short lattice vector away from `(k, 0)`.
+pub(crate) fn split_scalar(scalar: &BigUint) -> (BigInt, BigInt) {
+    let n = BigInt::from(N.clone());
+    let half_n = &n / 2;
+    let k = BigInt::from(scalar % &*N);
+
+    let c1 = (&*A1 * &k + &half_n) / &n;
+    let c2 = (&*MINUS_B1 * &k + &half_n) / &n;
+
+    let k1 = &k - &c1 * &*A1 - &c2 * &*A2;
+    let k2 = &c1 * &*MINUS_B1 - &c2 * &*A1;
+
+    (k1, k2)
+}
+
+/// The wNAF digits of a signed scalar, negated if it is negative.
+fn signed_wnaf(scalar: &BigInt, window: usize) -> Vec<i64> {
+    let digits = jacobian::wnaf(&scalar_limbs(scalar.magnitude()), window);
+
+    match scalar.sign() {
+        Sign::Minus => digits.into_iter().map(|digit| -digit).collect(),
+        _ => digits,
+    }
+}
+
+/// The wNAF digits of the two halves of `scalar`: the first for a point, the
+/// second for its image under the endomorphism.
+pub(crate) fn split_wnaf(scalar: &BigUint, window: usize) -> (Vec<i64>, Vec<i64>) {
+    let (k1, k2) = split_scalar(scalar);
+
+    (signed_wnaf(&k1, window), signed_wnaf(&k2, window))
+}
+
+/// The image of an affine point under the endomorphism, `lambda * point`.
+pub(crate) fn endomorphism(point: &S256Point) -> S256Point {
+    match &point.kind {
+        S256PointKind::Coordinates(x, y) => S256Point {
+            kind: S256PointKind::Coordinates(*x * *BETA, *y),
+        },
+        S256PointKind::Infinity => point.clone(),
+    }
+}
+
+/// The images under the endomorphism of a table of multiples.
+pub(crate) fn endomorphism_multiples(multiples: &[S256Point]) -> Vec<S256Point> {
+    multiples.iter().map(endomorphism).collect()
+}
+
+/// Variable-time multiplication of an arbitrary point, splitting the scalar
+/// with the endomorphism and interleaving the two halves with wNAF.
+pub(crate) fn mul_glv(point: &S256Point, scalar: &BigUint) -> JacobianPoint {
+    let (k1_digits, k2_digits) = split_wnaf(scalar, jacobian::WNAF_WINDOW);
+    let multiples = jacobian::odd_multiples(point, jacobian::WNAF_WINDOW);
+    let lambda_multiples = endomorphism_multiples(&multiples);
+
+    jacobian::interleaved_wnaf(&[(&k1_digits, &multiples), (&k2_digits, &lambda_multiples)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::G;
+    use rand::{RngCore, SeedableRng, rngs::StdRng};
+
+    // The endomorphism maps a point to LAMBDA times itself. Only the
+    // decomposition basis is needed to compute with it.
+    static LAMBDA: LazyLock<BigUint> = LazyLock::new(|| {
+        BigUint::parse_bytes(
+            b"5363ad4cc05c30e0a5261c028812645a122e22ea20816678df02967c1b23bd72",
+            16,
+        )
+        .unwrap()
+    });
+
+    fn random_scalars(seed: u64, count: usize) -> Vec<BigUint> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut scalars = vec![
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            &*N - 1u32,
+            LAMBDA.clone(),
+            &*N / 2u32,
+        ];
+        for _ in 0..count {
+            let mut bytes = [0u8; 32];
+            rng.fill_bytes(&mut bytes);
+            scalars.push(BigUint::from_bytes_be(&bytes));
+        }
+
+        scalars
+    }
+
+    #[test]
+    fn test_endomorphism_constants() {
+        assert_eq!(BETA.pow(3u32), S256FieldElement::ONE);
+        assert_eq!(LAMBDA.modpow(&BigUint::from(3u32), &N), BigUint::from(1u32));
+
+        // lambda * G, by plain double-and-add on the generic curve, is G with
+        // its x coordinate multiplied by beta.
+        let image = S256Point::new(*G.x().unwrap() * *BETA, *G.y().unwrap()).unwrap();
+        assert_eq!(&G.point() * &*LAMBDA, image.point());
+        assert_eq!(endomorphism(&G), image);
+    }
+
+    #[test]
+    fn test_split_scalar() {
+        let n = BigInt::from(N.clone());
+        let lambda = BigInt::from(LAMBDA.clone());
+
+        for scalar in random_scalars(42, 50) {
+            let (k1, k2) = split_scalar(&scalar);
+            assert!(k1.bits() <= 128 && k2.bits() <= 128);
+
+            let recombined = ((k1 + k2 * &lambda) % &n + &n) % &n;
+            assert_eq!(recombined, BigInt::from(&scalar % &*N));
+        }
+    }
+
+    #[test]
+    fn test_mul_glv_matches_binary_method() {
+        let point = &*G * BigUint::from(0xabcdefu32);
+        let generic_point = point.point();
+
+        for scalar in random_scalars(43, 5) {
+            // The generic curve multiplies by plain double-and-add.
+            let expected = &generic_point * &scalar;
+            assert_eq!(mul_glv(&point, &scalar).to_affine().point(), expected);
+        }
+    }
+}
@@ -0,0 +1,463 @@
+use super::{S256FieldElement, S256Point, S256PointKind};
+
+// Window width for wNAF multiplication. Digits are odd and below 2^(w-1) in
+// absolute value, so 2^(w-2) = 8 odd multiples of the point are precomputed.
+pub(crate) const WNAF_WINDOW: usize = 5;
+
+// The odd multiples of G are computed once, so G can afford a wider window:
+// 64 precomputed points and one addition per 9 bits on average.
+pub(crate) const G_WNAF_WINDOW: usize = 8;
+
+/// A secp256k1 point in Jacobian coordinates.
+///
+/// `(X, Y, Z)` stands for the affine point `(X / Z^2, Y / Z^3)`, and `Z = 0`
+/// is the point at infinity. Adding and doubling in this form needs no field
+/// inversion, so a long chain of operations pays for only one, in
+/// `to_affine`. Many triples represent the same point, which is why this type
+/// stays internal and `S256Point` remains the canonical affine form.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct JacobianPoint {
+    x: S256FieldElement,
+    y: S256FieldElement,
+    z: S256FieldElement,
+}
+
+impl JacobianPoint {
+    pub(crate) const INFINITY: JacobianPoint = JacobianPoint {
+        x: S256FieldElement::ONE,
+        y: S256FieldElement::ONE,
+        z: S256FieldElement::ZERO,
+    };
+
+    pub(crate) fn from_affine(point: &S256Point) -> JacobianPoint {
+        match &point.kind {
+            S256PointKind::Coordinates(x, y) => JacobianPoint {
+                x: *x,
+                y: *y,
+                z: S256FieldElement::ONE,
+            },
+            S256PointKind::Infinity => JacobianPoint::INFINITY,
+        }
+    }
+
+    pub(crate) fn is_infinity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    /// Converts back to affine coordinates with a single inversion.
+    pub(crate) fn to_affine(self) -> S256Point {
+        if self.is_infinity() {
+            return S256Point::infinity().unwrap();
+        }
+
+        self.to_affine_with(&(S256FieldElement::ONE / self.z))
+    }
+
+    /// Converts many points to affine coordinates with a single inversion
+    /// shared by all of them.
+    pub(crate) fn batch_to_affine(points: &[JacobianPoint]) -> Vec<S256Point> {
+        let mut z_inverses: Vec<S256FieldElement> = points
+            .iter()
+            .filter(|point| !point.is_infinity())
+            .map(|point| point.z)
+            .collect();
+        S256FieldElement::batch_invert(&mut z_inverses);
+
+        let mut z_inverses = z_inverses.iter();
+        points
+            .iter()
+            .map(|point| match point.is_infinity() {
+                true => S256Point::infinity().unwrap(),
+                false => point.to_affine_with(z_inverses.next().unwrap()),
+            })
+            .collect()
+    }
+
+    fn to_affine_with(self, z_inv: &S256FieldElement) -> S256Point {
+        let z_inv_squared = z_inv.square();
+
+        S256Point {
+            kind: S256PointKind::Coordinates(
+                self.x * z_inv_squared,
+                self.y * z_inv_squared * z_inv,
+            ),
+        }
+    }
+
+    /// Doubles the point ("dbl-2009-l" from the Explicit-Formulas Database,
+    /// which relies on the curve having a = 0).
+    pub(crate) fn double(&self) -> JacobianPoint {
+        if self.is_infinity() || self.y.is_zero() {
+            return JacobianPoint::INFINITY;
+        }
+
+        let a = self.x.square();
+        let b = self.y.square();
+        let c = b.square();
+        let d = (self.x + b).square() - a - c;
+        let d = d + d;
+        let e = a + a + a;
+        let f = e.square();
+
+        let x = f - d - d;
+        let c8 = c + c;
+        let c8 = c8 + c8;
+        let c8 = c8 + c8;
+        let y = e * (d - x) - c8;
+        let z = self.y * self.z;
+
+        JacobianPoint { x, y, z: z + z }
+    }
+
+    /// Adds two points ("add-2007-bl").
+    pub(crate) fn add(&self, rhs: &JacobianPoint) -> JacobianPoint {
+        if self.is_infinity() {
+            return *rhs;
+        }
+        if rhs.is_infinity() {
+            return *self;
+        }
+
+        let z1z1 = self.z.square();
+        let z2z2 = rhs.z.square();
+        let u1 = self.x * z2z2;
+        let u2 = rhs.x * z1z1;
+        let s1 = self.y * rhs.z * z2z2;
+        let s2 = rhs.y * self.z * z1z1;
+
+        let h = u2 - u1;
+        let r = s2 - s1;
+        if h.is_zero() {
+            // Same x coordinate: either the same point or its negation.
+            return if r.is_zero() {
+                self.double()
+            } else {
+                JacobianPoint::INFINITY
+            };
+        }
+        let r = r + r;
+
+        let i = (h + h).square();
+        let j = h * i;
+        let v = u1 * i;
+
+        let x = r.square() - j - v - v;
+        let s1j = s1 * j;
+        let y = r * (v - x) - s1j - s1j;
+        let z = ((self.z + rhs.z).square() - z1z1 - z2z2) * h;
+
+        JacobianPoint { x, y, z }
+    }
+
+    /// Adds a point whose Z is known to be one ("madd-2007-bl"), which saves
+    /// several multiplications over `add`.
+    pub(crate) fn add_affine(&self, rhs: &S256Point) -> JacobianPoint {
+        let S256PointKind::Coordinates(x2, y2) = &rhs.kind else {
+            return *self;
+        };
+        if self.is_infinity() {
+            return JacobianPoint::from_affine(rhs);
+        }
+
+        let z1z1 = self.z.square();
+        let u2 = x2 * z1z1;
+        let s2 = y2 * self.z * z1z1;
+
+        let h = u2 - self.x;
+        let r = s2 - self.y;
+        if h.is_zero() {
+            return if r.is_zero() {
+                self.double()
+            } else {
+                JacobianPoint::INFINITY
+            };
+        }
+        let r = r + r;
+
+        let hh = h.square();
+        let i = hh + hh;
+        let i = i + i;
+        let j = h * i;
+        let v = self.x * i;
+
+        let x = r.square() - j - v - v;
+        let y1j = self.y * j;
+        let y = r * (v - x) - y1j - y1j;
+        let z = (self.z + h).square() - z1z1 - hh;
+
+        JacobianPoint { x, y, z }
+    }
+}
+
+/// The width-`window` non-adjacent form of a 256-bit scalar, least
+/// significant digit first.
+///
+/// Every digit is zero or odd with absolute value below `2^(window - 1)`, and
+/// any two non-zero digits are at least `window` positions apart, so a
+/// multiplication needs one addition per `window + 1` bits on average instead
+/// of one per two. The result has up to 257 digits, since rounding the top
+/// window up can carry past bit 255.
+pub(crate) fn wnaf(scalar: &[u64; 4], window: usize) -> Vec<i64> {
+    let mut digits = vec![0i64; 257];
+    let mut carry = 0u64;
+    let mut bit = 0;
+
+    while bit < 256 {
+        if bits_at(scalar, bit, 1) == carry {
+            bit += 1;
+            continue;
+        }
+
+        let now = window.min(256 - bit);
+        let word = bits_at(scalar, bit, now) + carry;
+
+        // A window at or above 2^(window - 1) becomes a negative digit, and
+        // the borrowed 2^window is carried into the next window.
+        carry = (word >> (window - 1)) & 1;
+        digits[bit] = word as i64 - ((carry << window) as i64);
+
+        bit += now;
+    }
+    digits[256] = carry as i64;
+
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+
+    digits
+}
+
+/// `count` bits (at most 63) of `scalar` starting at `bit`.
+pub(crate) fn bits_at(scalar: &[u64; 4], bit: usize, count: usize) -> u64 {
+    let (limb, offset) = (bit / 64, bit % 64);
+    let mut value = scalar[limb] >> offset;
+    if offset + count > 64 && limb + 1 < 4 {
+        value |= scalar[limb + 1] << (64 - offset);
+    }
+
+    value & ((1 << count) - 1)
+}
+
+/// `point, 3 * point, 5 * point, ...`: the odd multiples a width-`window`
+/// wNAF digit can select.
+///
+/// They are returned in affine form, normalized together with one inversion,
+/// so that every digit costs a mixed addition instead of a full one.
+pub(crate) fn odd_multiples(point: &S256Point, window: usize) -> Vec<S256Point> {
+    let point = JacobianPoint::from_affine(point);
+    let two_point = point.double();
+
+    let mut multiples = vec![point];
+    for i in 1..1 << (window - 2) {
+        multiples.push(multiples[i - 1].add(&two_point));
+    }
+
+    JacobianPoint::batch_to_affine(&multiples)
+}
+
+/// Adds `digit * point` to `result` for a wNAF digit, given the odd
+/// multiples of the point.
+pub(crate) fn add_wnaf_digit(
+    result: &JacobianPoint,
+    multiples: &[S256Point],
+    digit: i64,
+) -> JacobianPoint {
+    match digit {
+        0 => *result,
+        d if d > 0 => result.add_affine(&multiples[(d / 2) as usize]),
+        d => result.add_affine(&-&multiples[(-d / 2) as usize]),
+    }
+}
+
+/// The sum of several wNAF multiplications, each given as the scalar's digits
+/// and the odd multiples of its point.
+///
+/// This is Strauss-Shamir interleaving: all terms share a single chain of
+/// doublings, and each term only adds its digit at every position. Summing
+/// two products costs about the same as computing one of them separately.
+pub(crate) fn interleaved_wnaf(terms: &[(&[i64], &[S256Point])]) -> JacobianPoint {
+    let length = terms
+        .iter()
+        .map(|(digits, _)| digits.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut result = JacobianPoint::INFINITY;
+    for position in (0..length).rev() {
+        result = result.double();
+        for (digits, multiples) in terms {
+            if let Some(&digit) = digits.get(position) {
+                result = add_wnaf_digit(&result, multiples, digit);
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elliptic_curve::Point;
+    use crate::secp256k1::{G, N, scalar_limbs};
+    use num_bigint::BigUint;
+    use rand::{RngCore, SeedableRng, rngs::StdRng};
+
+    fn from_generic(point: &Point) -> S256Point {
+        S256Point::new(
+            S256FieldElement::new(point.x().unwrap().num().clone()).unwrap(),
+            S256FieldElement::new(point.y().unwrap().num().clone()).unwrap(),
+        )
+        .unwrap()
+    }
+
+    /// `k * G` by repeated addition on the generic affine curve.
+    fn generic_multiple(k: u32) -> S256Point {
+        let g = G.point();
+        let mut sum = g.clone();
+        for _ in 1..k {
+            sum = &sum + &g;
+        }
+
+        from_generic(&sum)
+    }
+
+    #[test]
+    fn test_matches_generic_affine_arithmetic() {
+        let g = JacobianPoint::from_affine(&G);
+
+        let mut sum = g;
+        for k in 2..=12 {
+            sum = sum.add(&g);
+            let expected = generic_multiple(k);
+
+            assert_eq!(sum.to_affine(), expected);
+            assert_eq!(
+                JacobianPoint::from_affine(&generic_multiple(k - 1))
+                    .add_affine(&G)
+                    .to_affine(),
+                expected
+            );
+        }
+
+        assert_eq!(
+            g.double().double().double().to_affine(),
+            generic_multiple(8)
+        );
+    }
+
+    #[test]
+    fn test_scalar_multiplication_matches_generic() {
+        let scalar = BigUint::from(0xdeadbeefu32);
+
+        assert_eq!(&*G * &scalar, from_generic(&(&G.point() * &scalar)));
+    }
+
+    #[test]
+    fn test_special_cases() {
+        let g = JacobianPoint::from_affine(&G);
+        let inf = JacobianPoint::INFINITY;
+
+        assert!(g.add(&JacobianPoint::from_affine(&-&*G)).is_infinity());
+        assert!(g.add_affine(&-&*G).is_infinity());
+        assert!(inf.double().is_infinity());
+        assert_eq!(inf.add(&g).to_affine(), *G);
+        assert_eq!(g.add(&inf).to_affine(), *G);
+        assert_eq!(inf.add_affine(&G).to_affine(), *G);
+        assert!(inf.to_affine().is_at_infinity());
+
+        // Adding a point to a different representation of itself doubles it.
+        let two_g = g.double();
+        let two_g_normalized = JacobianPoint::from_affine(&two_g.to_affine());
+        assert_eq!(
+            two_g.add(&two_g_normalized).to_affine(),
+            two_g.double().to_affine()
+        );
+    }
+
+    #[test]
+    fn test_batch_to_affine() {
+        let g = JacobianPoint::from_affine(&G);
+        let points = [
+            g.double(),
+            JacobianPoint::INFINITY,
+            g,
+            g.double().add(&g),
+            JacobianPoint::INFINITY,
+        ];
+
+        let expected: Vec<S256Point> = points.iter().map(|point| point.to_affine()).collect();
+        assert_eq!(JacobianPoint::batch_to_affine(&points), expected);
+        assert!(JacobianPoint::batch_to_affine(&[]).is_empty());
+    }
+
+    fn from_wnaf(digits: &[i64]) -> BigUint {
+        let mut value = num_bigint::BigInt::from(0);
+        for &digit in digits.iter().rev() {
+            value = value * 2 + digit;
+        }
+
+        value.to_biguint().unwrap()
+    }
+
+    #[test]
+    fn test_wnaf_digits() {
+        let mut rng = StdRng::seed_from_u64(40);
+        let mut scalars = vec![
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            BigUint::from(31u32),
+            &*N - 1u32,
+        ];
+        for _ in 0..20 {
+            let mut bytes = [0u8; 32];
+            rng.fill_bytes(&mut bytes);
+            scalars.push(BigUint::from_bytes_be(&bytes) % &*N);
+        }
+
+        for scalar in &scalars {
+            for window in [2, 4, 5, 8] {
+                let digits = wnaf(&scalar_limbs(scalar), window);
+                assert_eq!(from_wnaf(&digits), *scalar);
+
+                let bound = 1i64 << (window - 1);
+                let mut last_nonzero: Option<usize> = None;
+                for (position, &digit) in digits.iter().enumerate() {
+                    if digit == 0 {
+                        continue;
+                    }
+                    assert!(digit % 2 != 0 && digit.abs() < bound);
+                    if let Some(last) = last_nonzero {
+                        assert!(position - last >= window);
+                    }
+                    last_nonzero = Some(position);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mul_wnaf_matches_constant_time() {
+        let mut rng = StdRng::seed_from_u64(41);
+        let point = &*G * BigUint::from(0xabcdefu32);
+        let multiples = odd_multiples(&point, WNAF_WINDOW);
+        let mul_wnaf = |point: &S256Point, scalar: &BigUint| {
+            let digits = wnaf(&scalar_limbs(scalar), WNAF_WINDOW);
+            assert_eq!(point, &multiples[0]);
+            interleaved_wnaf(&[(&digits, &multiples)])
+        };
+
+        for _ in 0..10 {
+            let mut bytes = [0u8; 32];
+            rng.fill_bytes(&mut bytes);
+            let scalar = BigUint::from_bytes_be(&bytes);
+
+            assert_eq!(
+                mul_wnaf(&point, &scalar).to_affine(),
+                point.mul_secret(&scalar)
+            );
+        }
+
+        assert!(mul_wnaf(&point, &N).is_infinity());
+    }
+}
@@ -0,0 +1,107 @@
+use num_bigint::{BigUint, Sign};
+
+use super::jacobian::{self, JacobianPoint};
+use super::{S256Point, glv, scalar_limbs};
+
+/// The sum of `scalar * point` over all terms, by Pippenger's bucket method.
+///
+/// Each scalar is first split with the endomorphism into two signed halves of
+/// about 128 bits, with the signs moved onto the points. The halves are then
+/// processed `window` bits at a time from the top: every point is added to the
+/// bucket of its digit, and the buckets are summed with a running total so that
+/// bucket `d` is counted `d` times. The doublings and bucket sums are shared by
+/// all terms, so for large inputs each term costs little more than one
+/// addition per window.
+pub(crate) fn multi_mul(terms: &[(BigUint, S256Point)]) -> JacobianPoint {
+    let mut halves: Vec<([u64; 4], S256Point)> = Vec::with_capacity(2 * terms.len());
+    for (scalar, point) in terms {
+        let (k1, k2) = glv::split_scalar(scalar);
+        for (half, base) in [(k1, point.clone()), (k2, glv::endomorphism(point))] {
+            let base = match half.sign() {
+                Sign::Minus => -base,
+                _ => base,
+            };
+            halves.push((scalar_limbs(half.magnitude()), base));
+        }
+    }
+
+    let window = window_bits(halves.len());
+    let windows = 129usize.div_ceil(window);
+
+    let mut result = JacobianPoint::INFINITY;
+    for index in (0..windows).rev() {
+        for _ in 0..window {
+            result = result.double();
+        }
+
+        let mut buckets = vec![JacobianPoint::INFINITY; (1 << window) - 1];
+        for (limbs, point) in &halves {
+            let digit = jacobian::bits_at(limbs, index * window, window) as usize;
+            if digit != 0 {
+                buckets[digit - 1] = buckets[digit - 1].add_affine(point);
+            }
+        }
+
+        // running is the sum of buckets d and above, and adding it once per
+        // bucket counts every point d times.
+        let mut running = JacobianPoint::INFINITY;
+        let mut total = JacobianPoint::INFINITY;
+        for bucket in buckets.iter().rev() {
+            running = running.add(bucket);
+            total = total.add(&running);
+        }
+
+        result = result.add(&total);
+    }
+
+    result
+}
+
+/// The window width for `count` half-scalars. Wider windows mean fewer passes
+/// but `2^window` bucket additions per pass, so the width grows with the
+/// logarithm of the input size.
+fn window_bits(count: usize) -> usize {
+    let log2 = (usize::BITS - count.leading_zeros()) as usize;
+    log2.saturating_sub(2).clamp(2, 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::{G, N, random_scalar};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn test_multi_mul_matches_sum_of_products() {
+        let mut rng = StdRng::seed_from_u64(43);
+
+        for count in [0, 1, 2, 5, 40] {
+            let terms: Vec<(BigUint, S256Point)> = (0..count)
+                .map(|_| (random_scalar(&mut rng), &*G * random_scalar(&mut rng)))
+                .collect();
+
+            let expected = terms
+                .iter()
+                .fold(S256Point::infinity().unwrap(), |sum, (scalar, point)| {
+                    &sum + &(point * scalar)
+                });
+
+            assert_eq!(multi_mul(&terms).to_affine(), expected);
+        }
+    }
+
+    #[test]
+    fn test_multi_mul_special_terms() {
+        let inf = S256Point::infinity().unwrap();
+        let k = BigUint::from(12345u32);
+
+        // Terms that cancel, a zero scalar and the point at infinity.
+        let terms = vec![
+            (k.clone(), G.clone()),
+            (&*N - &k, G.clone()),
+            (BigUint::from(0u32), &*G * BigUint::from(2u32)),
+            (k.clone(), inf.clone()),
+        ];
+        assert_eq!(multi_mul(&terms).to_affine(), inf);
+    }
+}
@@ -0,0 +1,266 @@
+use num_bigint::BigUint;
+
+use super::{S256FieldElement, S256Point, S256PointKind, scalar_limbs};
+
+// 3 * b for y^2 = x^3 + 7, the constant the complete formulas are written in.
+const B3: S256FieldElement = S256FieldElement::from_u64(21);
+
+// Bits consumed per step of the fixed-window multiplication.
+const WINDOW_BITS: usize = 4;
+const TABLE_SIZE: usize = 1 << WINDOW_BITS;
+
+/// A secp256k1 point in homogeneous projective coordinates, where `(X, Y, Z)`
+/// stands for the affine point `(X / Z, Y / Z)` and `(0, 1, 0)` is the point
+/// at infinity.
+///
+/// This form has complete addition formulas (Renes, Costello and Batina,
+/// "Complete addition formulas for prime order elliptic curves", 2016):
+/// one sequence of field operations is correct for every pair of inputs,
+/// including doubling and the point at infinity. With no special cases to
+/// branch on, the operations take the same time whatever the points are,
+/// which is what secret scalar multiplication needs.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ProjectivePoint {
+    x: S256FieldElement,
+    y: S256FieldElement,
+    z: S256FieldElement,
+}
+
+impl ProjectivePoint {
+    pub(crate) const INFINITY: ProjectivePoint = ProjectivePoint {
+        x: S256FieldElement::ZERO,
+        y: S256FieldElement::ONE,
+        z: S256FieldElement::ZERO,
+    };
+
+    pub(crate) fn from_affine(point: &S256Point) -> ProjectivePoint {
+        match &point.kind {
+            S256PointKind::Coordinates(x, y) => ProjectivePoint {
+                x: *x,
+                y: *y,
+                z: S256FieldElement::ONE,
+            },
+            S256PointKind::Infinity => ProjectivePoint::INFINITY,
+        }
+    }
+
+    pub(crate) fn to_affine(self) -> S256Point {
+        if self.z.is_zero() {
+            return S256Point::infinity().unwrap();
+        }
+
+        let z_inv = S256FieldElement::ONE / self.z;
+
+        S256Point {
+            kind: S256PointKind::Coordinates(self.x * z_inv, self.y * z_inv),
+        }
+    }
+
+    /// Complete addition for curves with a = 0 (Algorithm 7 of the paper).
+    pub(crate) fn add(&self, rhs: &ProjectivePoint) -> ProjectivePoint {
+        let (x1, y1, z1) = (self.x, self.y, self.z);
+        let (x2, y2, z2) = (rhs.x, rhs.y, rhs.z);
+
+        let t0 = x1 * x2;
+        let t1 = y1 * y2;
+        let t2 = z1 * z2;
+        let t3 = (x1 + y1) * (x2 + y2) - (t0 + t1);
+        let t4 = (y1 + z1) * (y2 + z2) - (t1 + t2);
+        let y3 = (x1 + z1) * (x2 + z2) - (t0 + t2);
+
+        let t0 = t0 + t0 + t0;
+        let t2 = B3 * t2;
+        let z3 = t1 + t2;
+        let t1 = t1 - t2;
+        let y3 = B3 * y3;
+
+        ProjectivePoint {
+            x: t3 * t1 - t4 * y3,
+            y: t1 * z3 + y3 * t0,
+            z: z3 * t4 + t0 * t3,
+        }
+    }
+
+    /// Doubling for curves with a = 0 (Algorithm 9 of the paper).
+    pub(crate) fn double(&self) -> ProjectivePoint {
+        let (x, y, z) = (self.x, self.y, self.z);
+
+        let t0 = y.square();
+        let z3 = t0 + t0;
+        let z3 = z3 + z3;
+        let z3 = z3 + z3;
+        let t1 = y * z;
+        let t2 = B3 * z.square();
+        let x3 = t2 * z3;
+        let y3 = t0 + t2;
+        let z3 = t1 * z3;
+        let t2 = t2 + t2 + t2;
+        let t0 = t0 - t2;
+        let y3 = t0 * y3 + x3;
+        let t1 = x * y;
+        let x3 = t0 * t1;
+
+        ProjectivePoint {
+            x: x3 + x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    /// Returns `b` if `choice` is 1 and `a` if it is 0, without branching.
+    fn conditional_select(
+        a: &ProjectivePoint,
+        b: &ProjectivePoint,
+        choice: u64,
+    ) -> ProjectivePoint {
+        ProjectivePoint {
+            x: S256FieldElement::conditional_select(&a.x, &b.x, choice),
+            y: S256FieldElement::conditional_select(&a.y, &b.y, choice),
+            z: S256FieldElement::conditional_select(&a.z, &b.z, choice),
+        }
+    }
+}
+
+/// 1 if `a == b`, otherwise 0, without branching.
+fn ct_eq(a: u64, b: u64) -> u64 {
+    let difference = a ^ b;
+
+    ((difference | difference.wrapping_neg()) >> 63) ^ 1
+}
+
+/// Reads `table[index]` by touching every entry, so the memory access
+/// pattern does not reveal the index.
+fn lookup(table: &[ProjectivePoint; TABLE_SIZE], index: u64) -> ProjectivePoint {
+    let mut selected = ProjectivePoint::INFINITY;
+    for (i, entry) in table.iter().enumerate() {
+        selected = ProjectivePoint::conditional_select(&selected, entry, ct_eq(i as u64, index));
+    }
+
+    selected
+}
+
+/// Fixed-window scalar multiplication that runs the same sequence of
+/// operations for every scalar: all 64 four-bit windows are processed, a
+/// zero window adds the point at infinity instead of being skipped, and the
+/// table entry is read with `lookup`.
+pub(crate) fn mul_constant_time(point: &S256Point, scalar: &BigUint) -> S256Point {
+    let scalar = scalar_limbs(scalar);
+
+    let base = ProjectivePoint::from_affine(point);
+    let mut table = [ProjectivePoint::INFINITY; TABLE_SIZE];
+    for i in 1..TABLE_SIZE {
+        table[i] = table[i - 1].add(&base);
+    }
+
+    let mut result = ProjectivePoint::INFINITY;
+    for window in (0..256 / WINDOW_BITS).rev() {
+        for _ in 0..WINDOW_BITS {
+            result = result.double();
+        }
+
+        let bit = window * WINDOW_BITS;
+        let digit = (scalar[bit / 64] >> (bit % 64)) & (TABLE_SIZE as u64 - 1);
+        result = result.add(&lookup(&table, digit));
+    }
+
+    result.to_affine()
+}
+
+/// Precomputed multiples of a fixed base point: `windows[i][d]` is
+/// `d * 16^i * base`. Multiplying by a scalar then takes one table read and
+/// one addition per 4-bit window, and no doublings at all.
+pub(crate) struct GeneratorTable {
+    windows: Vec<[ProjectivePoint; TABLE_SIZE]>,
+}
+
+impl GeneratorTable {
+    pub(crate) fn new(base: &S256Point) -> GeneratorTable {
+        let mut windows = Vec::with_capacity(256 / WINDOW_BITS);
+        let mut window_base = ProjectivePoint::from_affine(base);
+
+        for _ in 0..256 / WINDOW_BITS {
+            let mut entries = [ProjectivePoint::INFINITY; TABLE_SIZE];
+            for d in 1..TABLE_SIZE {
+                entries[d] = entries[d - 1].add(&window_base);
+            }
+            windows.push(entries);
+
+            for _ in 0..WINDOW_BITS {
+                window_base = window_base.double();
+            }
+        }
+
+        GeneratorTable { windows }
+    }
+
+    /// Multiplies the base point by `scalar` in constant time, reading every
+    /// table entry with `lookup` like `mul_constant_time` does.
+    pub(crate) fn mul(&self, scalar: &BigUint) -> ProjectivePoint {
+        let scalar = scalar_limbs(scalar);
+
+        let mut result = ProjectivePoint::INFINITY;
+        for (window, entries) in self.windows.iter().enumerate() {
+            let bit = window * WINDOW_BITS;
+            let digit = (scalar[bit / 64] >> (bit % 64)) & (TABLE_SIZE as u64 - 1);
+            result = result.add(&lookup(entries, digit));
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::{G, N};
+    use rand::{RngCore, SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn test_complete_formulas_handle_special_cases() {
+        let g = ProjectivePoint::from_affine(&G);
+        let neg_g = ProjectivePoint::from_affine(&-&*G);
+        let inf = ProjectivePoint::INFINITY;
+
+        assert_eq!(g.add(&g).to_affine(), &*G + &*G);
+        assert_eq!(g.double().to_affine(), &*G + &*G);
+        assert!(g.add(&neg_g).to_affine().is_at_infinity());
+        assert_eq!(g.add(&inf).to_affine(), *G);
+        assert_eq!(inf.add(&g).to_affine(), *G);
+        assert!(inf.add(&inf).to_affine().is_at_infinity());
+        assert!(inf.double().to_affine().is_at_infinity());
+    }
+
+    #[test]
+    fn test_matches_variable_time_multiplication() {
+        let mut rng = StdRng::seed_from_u64(39);
+        let point = &*G * BigUint::from(0xc0ffeeu32);
+
+        let mut scalars = vec![
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            BigUint::from(15u32),
+            BigUint::from(16u32),
+            &*N - 1u32,
+            N.clone(),
+        ];
+        for _ in 0..10 {
+            let mut bytes = [0u8; 32];
+            rng.fill_bytes(&mut bytes);
+            scalars.push(BigUint::from_bytes_be(&bytes));
+        }
+
+        let point_table = GeneratorTable::new(&point);
+        for scalar in &scalars {
+            assert_eq!(mul_constant_time(&G, scalar), &*G * scalar);
+            assert_eq!(mul_constant_time(&point, scalar), &point * scalar);
+            assert_eq!(point_table.mul(scalar).to_affine(), &point * scalar);
+        }
+    }
+
+    #[test]
+    fn test_ct_eq() {
+        assert_eq!(ct_eq(5, 5), 1);
+        assert_eq!(ct_eq(5, 6), 0);
+        assert_eq!(ct_eq(0, u64::MAX), 0);
+    }
+}
@@ -0,0 +1,459 @@
+use std::collections::HashSet;
+use std::sync::{Arc, OnceLock};
+
+use num_bigint::BigUint;
+
+use crate::elliptic_curve::{Point, PointError};
+use crate::finite_field::FieldElement;
+
+#[derive(Debug)]
+pub enum SmallCurveError {
+    /// `a` and `b` are elements of different fields.
+    FieldMismatch,
+    /// `4a^3 + 27b^2 = 0`: the curve has a cusp or a node and its points do
+    /// not form a group.
+    Singular,
+    /// The prime is too large to enumerate every point.
+    PrimeTooLarge,
+    /// The modulus is not a prime above 3. Square roots, and so `points`,
+    /// need a prime, and over `F_2` and `F_3` the curve cannot be written as
+    /// `y^2 = x^3 + a x + b`.
+    NotPrime,
+}
+
+impl std::fmt::Display for SmallCurveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmallCurveError::FieldMismatch => write!(f, "a and b are in different fields"),
+            SmallCurveError::Singular => write!(f, "The curve is singular"),
+            SmallCurveError::PrimeTooLarge => {
+                write!(f, "The prime is too large to enumerate the curve")
+            }
+            SmallCurveError::NotPrime => write!(f, "The modulus must be a prime above 3"),
+        }
+    }
+}
+
+impl std::error::Error for SmallCurveError {}
+
+/// Most methods enumerate every point or walk a whole subgroup, so primes are
+/// capped well below the point where that becomes impractical.
+const MAX_PRIME: u32 = 1 << 12;
+
+/// The curve `y^2 = x^3 + a x + b` over a small prime field, with brute-force
+/// tools for exploring its group: the kind of curve the book's exercises use
+/// before moving on to secp256k1.
+#[derive(Debug, Clone)]
+pub struct SmallCurve {
+    a: FieldElement,
+    b: FieldElement,
+    /// The number of points, counted on first use.
+    order: OnceLock<u64>,
+}
+
+/// A cyclic subgroup: the multiples `0, g, 2g, ...` of a generator `g`.
+#[derive(Debug, Clone)]
+pub struct Subgroup {
+    pub generator: Point,
+    /// The elements in the order `0, g, 2g, ...`, starting with the point at
+    /// infinity.
+    pub elements: Vec<Point>,
+}
+
+impl Subgroup {
+    pub fn order(&self) -> u64 {
+        self.elements.len() as u64
+    }
+}
+
+impl SmallCurve {
+    pub fn new(a: FieldElement, b: FieldElement) -> Result<SmallCurve, SmallCurveError> {
+        if a.prime() != b.prime() {
+            return Err(SmallCurveError::FieldMismatch);
+        }
+        if **a.prime() > BigUint::from(MAX_PRIME) {
+            return Err(SmallCurveError::PrimeTooLarge);
+        }
+        // Below MAX_PRIME, so trial division is quick.
+        let prime = a.prime().iter_u32_digits().next().unwrap_or(0);
+        if prime <= 3
+            || (2..)
+                .take_while(|d| d * d <= prime)
+                .any(|d| prime.is_multiple_of(d))
+        {
+            return Err(SmallCurveError::NotPrime);
+        }
+
+        let discriminant = a.pow(3u32) * BigUint::from(4u32) + b.pow(2u32) * BigUint::from(27u32);
+        if discriminant.is_zero() {
+            return Err(SmallCurveError::Singular);
+        }
+
+        Ok(SmallCurve {
+            a,
+            b,
+            order: OnceLock::new(),
+        })
+    }
+
+    fn prime(&self) -> &Arc<BigUint> {
+        self.a.prime()
+    }
+
+    fn element(&self, num: u32) -> FieldElement {
+        FieldElement::new(BigUint::from(num), self.prime().clone()).unwrap()
+    }
+
+    pub fn infinity(&self) -> Point {
+        Point::infinity(self.a.clone(), self.b.clone()).unwrap()
+    }
+
+    pub fn point(&self, x: u32, y: u32) -> Result<Point, PointError> {
+        Point::new(
+            self.element(x),
+            self.element(y),
+            self.a.clone(),
+            self.b.clone(),
+        )
+    }
+
+    /// Every point on the curve, starting with the point at infinity and then
+    /// by increasing x, the smaller y first.
+    pub fn points(&self) -> Vec<Point> {
+        let prime = self.prime().iter_u32_digits().next().unwrap_or(0);
+        let mut points = vec![self.infinity()];
+
+        for x in 0..prime {
+            let x = self.element(x);
+            let y_squared = x.pow(3u32) + &self.a * &x + &self.b;
+            let Some(root) = y_squared.sqrt() else {
+                continue;
+            };
+
+            let other_root = -&root;
+            let mut roots = vec![root];
+            if !other_root.is_zero() {
+                roots.push(other_root);
+            }
+            roots.sort_by(|left, right| left.num().cmp(right.num()));
+
+            for y in roots {
+                points.push(Point::new(x.clone(), y, self.a.clone(), self.b.clone()).unwrap());
+            }
+        }
+
+        points
+    }
+
+    /// The number of points on the curve, including the point at infinity.
+    pub fn order(&self) -> u64 {
+        *self.order.get_or_init(|| self.points().len() as u64)
+    }
+
+    /// The smallest `n > 0` with `n * point` at infinity.
+    pub fn point_order(&self, point: &Point) -> u64 {
+        order_dividing(point, self.order())
+    }
+
+    /// The points whose multiples reach every point on the curve. The list is
+    /// empty when the group is not cyclic, as for the book's `y^2 = x^3 + 7`
+    /// over `F_223`.
+    pub fn generators(&self) -> Vec<Point> {
+        let order = self.order();
+        // The largest cyclic subgroup is the whole group if it is cyclic.
+        let Some(group) = self
+            .subgroups()
+            .pop()
+            .filter(|group| group.order() == order)
+        else {
+            return Vec::new();
+        };
+
+        let mut generators: Vec<Point> = group
+            .elements
+            .into_iter()
+            .enumerate()
+            .filter(|(k, _)| gcd(*k as u64, order) == 1)
+            .map(|(_, point)| point)
+            .collect();
+        generators.sort_by_key(coordinates);
+
+        generators
+    }
+
+    /// The subgroup generated by `point`.
+    pub fn subgroup(&self, point: &Point) -> Subgroup {
+        let mut elements = vec![self.infinity()];
+        let mut multiple = point.clone();
+
+        while !multiple.is_at_infinity() {
+            elements.push(multiple.clone());
+            multiple = &multiple + point;
+        }
+
+        Subgroup {
+            generator: point.clone(),
+            elements,
+        }
+    }
+
+    /// Every cyclic subgroup, each once, by increasing order.
+    ///
+    /// Each subgroup is listed with the first point of `points` that
+    /// generates it. When the group itself is not cyclic, its non-cyclic
+    /// subgroups (including the whole group) are not included.
+    pub fn subgroups(&self) -> Vec<Subgroup> {
+        let mut subgroups: Vec<Subgroup> = Vec::new();
+        // The generators of the subgroups found so far. A point generates
+        // exactly one cyclic subgroup, so any other point's is new.
+        let mut covered = HashSet::new();
+
+        for point in self.points() {
+            if covered.contains(&coordinates(&point)) {
+                continue;
+            }
+
+            let subgroup = self.subgroup(&point);
+            // k * g generates the same subgroup as g when gcd(k, n) = 1.
+            let order = subgroup.order();
+            for (k, element) in subgroup.elements.iter().enumerate() {
+                if gcd(k as u64, order) == 1 {
+                    covered.insert(coordinates(element));
+                }
+            }
+
+            subgroups.push(subgroup);
+        }
+
+        subgroups.sort_by_key(Subgroup::order);
+        subgroups
+    }
+
+    /// Solves `k * base = target` for the smallest `k >= 0` by trying every
+    /// multiple of `base` in turn, or returns `None` if `target` is not one.
+    ///
+    /// This takes up to one addition per element of the subgroup.
+    pub fn discrete_log(&self, base: &Point, target: &Point) -> Option<u64> {
+        let mut multiple = self.infinity();
+        let mut k = 0;
+
+        loop {
+            if multiple == *target {
+                return Some(k);
+            }
+
+            multiple = &multiple + base;
+            k += 1;
+
+            if multiple.is_at_infinity() {
+                return None;
+            }
+        }
+    }
+}
+
+fn coordinates(point: &Point) -> Option<(BigUint, BigUint)> {
+    Some((point.x()?.num().clone(), point.y()?.num().clone()))
+}
+
+/// The order of `point`, given a multiple `n` of it such as the group order.
+///
+/// Starting from `n`, each prime factor is divided out for as long as the
+/// smaller multiple still sends `point` to infinity, which takes a few scalar
+/// multiplications instead of one addition per multiple.
+fn order_dividing(point: &Point, n: u64) -> u64 {
+    let mut order = n;
+
+    for factor in prime_factors(n) {
+        while order.is_multiple_of(factor)
+            && (point * BigUint::from(order / factor)).is_at_infinity()
+        {
+            order /= factor;
+        }
+    }
+
+    order
+}
+
+/// The distinct prime factors of `n`, by trial division.
+fn prime_factors(mut n: u64) -> Vec<u64> {
+    let mut factors = Vec::new();
+    let mut divisor = 2;
+
+    while divisor * divisor <= n {
+        if n.is_multiple_of(divisor) {
+            factors.push(divisor);
+            while n.is_multiple_of(divisor) {
+                n /= divisor;
+            }
+        }
+        divisor += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+
+    factors
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod small_curve_tests {
+    use super::*;
+
+    fn curve(a: u32, b: u32, prime: u32) -> SmallCurve {
+        let prime = Arc::new(BigUint::from(prime));
+
+        SmallCurve::new(
+            FieldElement::new(BigUint::from(a), prime.clone()).unwrap(),
+            FieldElement::new(BigUint::from(b), prime).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_new_rejects_bad_curves() {
+        let p13 = Arc::new(BigUint::from(13u32));
+        let p17 = Arc::new(BigUint::from(17u32));
+        let zero = |prime: &Arc<BigUint>| FieldElement::new(BigUint::from(0u32), prime.clone());
+
+        assert!(matches!(
+            SmallCurve::new(zero(&p13).unwrap(), zero(&p17).unwrap()),
+            Err(SmallCurveError::FieldMismatch)
+        ));
+        // y^2 = x^3 has a cusp at the origin.
+        assert!(matches!(
+            SmallCurve::new(zero(&p13).unwrap(), zero(&p13).unwrap()),
+            Err(SmallCurveError::Singular)
+        ));
+
+        let large = Arc::new(BigUint::from(4099u32));
+        assert!(matches!(
+            SmallCurve::new(
+                zero(&large).unwrap(),
+                FieldElement::new(BigUint::from(7u32), large).unwrap()
+            ),
+            Err(SmallCurveError::PrimeTooLarge)
+        ));
+
+        for modulus in [2u32, 3, 9, 21] {
+            let modulus = Arc::new(BigUint::from(modulus));
+            assert!(matches!(
+                SmallCurve::new(
+                    FieldElement::new(BigUint::from(1u32), modulus.clone()).unwrap(),
+                    FieldElement::new(BigUint::from(1u32), modulus).unwrap()
+                ),
+                Err(SmallCurveError::NotPrime)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_book_curve_group_structure() {
+        // y^2 = x^3 + 7 over F_223 has 252 points, but no point has order
+        // 252: the group is not cyclic.
+        let curve = curve(0, 7, 223);
+        let points = curve.points();
+
+        assert_eq!(curve.order(), 252);
+        assert!(points[0].is_at_infinity());
+        assert!(curve.generators().is_empty());
+
+        assert_eq!(curve.point_order(&curve.point(15, 86).unwrap()), 7);
+        assert_eq!(curve.point_order(&curve.point(47, 71).unwrap()), 21);
+        assert_eq!(curve.point_order(&curve.infinity()), 1);
+
+        // The number of points of each order, from an independent
+        // enumeration in Python.
+        let mut counts = std::collections::BTreeMap::new();
+        for point in &points {
+            *counts.entry(curve.point_order(point)).or_insert(0) += 1;
+        }
+        let expected = [
+            (1, 1),
+            (2, 3),
+            (3, 8),
+            (6, 24),
+            (7, 6),
+            (14, 18),
+            (21, 48),
+            (42, 144),
+        ];
+        assert_eq!(counts.into_iter().collect::<Vec<_>>(), expected);
+
+        // Every point's order divides the group order (Lagrange).
+        assert!(
+            points
+                .iter()
+                .all(|point| 252 % curve.point_order(point) == 0)
+        );
+    }
+
+    #[test]
+    fn test_cyclic_curve() {
+        // y^2 = x^3 + 2x + 2 over F_17 has prime order 19, so every point
+        // but the point at infinity generates it.
+        let curve = curve(2, 2, 17);
+
+        assert_eq!(curve.order(), 19);
+        assert_eq!(curve.generators().len(), 18);
+
+        let subgroups = curve.subgroups();
+        let orders: Vec<u64> = subgroups.iter().map(Subgroup::order).collect();
+        assert_eq!(orders, [1, 19]);
+    }
+
+    #[test]
+    fn test_curve_near_max_prime() {
+        // y^2 = x^3 + 2x + 3 over F_4093, the largest prime below the cap, has
+        // 4024 = 8 * 503 points and is Z/2 x Z/2012. Counts of points of each
+        // order, and so of cyclic subgroups, from an enumeration in Python.
+        let curve = curve(2, 3, 4093);
+
+        assert_eq!(curve.order(), 4024);
+        assert!(curve.generators().is_empty());
+
+        let orders: Vec<u64> = curve.subgroups().iter().map(Subgroup::order).collect();
+        assert_eq!(
+            orders,
+            [1, 2, 2, 2, 4, 4, 503, 1006, 1006, 1006, 2012, 2012]
+        );
+    }
+
+    #[test]
+    fn test_subgroups() {
+        let curve = curve(0, 7, 223);
+
+        let subgroup = curve.subgroup(&curve.point(15, 86).unwrap());
+        assert_eq!(subgroup.order(), 7);
+        assert_eq!(subgroup.elements[2], curve.point(139, 86).unwrap());
+
+        let orders: Vec<u64> = curve.subgroups().iter().map(Subgroup::order).collect();
+        let expected = [
+            1, 2, 2, 2, 3, 3, 3, 3, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 7, 14, 14, 14, 21, 21, 21,
+            21, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42,
+        ];
+        assert_eq!(orders, expected);
+    }
+
+    #[test]
+    fn test_discrete_log() {
+        let cyclic = curve(2, 2, 17);
+        let g = cyclic.point(5, 1).unwrap();
+
+        for k in 0..19u32 {
+            let target = &g * BigUint::from(k);
+            assert_eq!(cyclic.discrete_log(&g, &target), Some(u64::from(k)));
+        }
+
+        // (15, 86) generates a subgroup of order 7 that (47, 71) is not in.
+        let book = curve(0, 7, 223);
+        assert_eq!(
+            book.discrete_log(&book.point(15, 86).unwrap(), &book.point(47, 71).unwrap()),
+            None
+        );
+    }
+}
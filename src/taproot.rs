@@ -0,0 +1,521 @@
+//! Taproot script trees (BIP341).
+//!
+//! A Taproot output commits to an internal key and, optionally, to a merkle
+//! tree of leaf scripts. `TapTree` builds that tree, either explicitly or from
+//! leaf weights, and `TaprootSpendInfo` holds everything a wallet needs to
+//! spend it: the tweaked output key and a control block for every leaf.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::elliptic_curve::PointError;
+use crate::script::Script;
+use crate::secp256k1::{Parity, TweakError, XOnlyPublicKey, tagged_hash};
+use crate::varint::encode_varint;
+
+/// The leaf version of BIP342 tapscript.
+pub const TAPSCRIPT_LEAF_VERSION: u8 = 0xc0;
+
+// A control block is the leaf version byte and the internal key, followed by
+// up to 128 merkle branch hashes.
+const CONTROL_BLOCK_BASE_SIZE: usize = 33;
+const CONTROL_BLOCK_NODE_SIZE: usize = 32;
+const TAPROOT_CONTROL_MAX_NODE_COUNT: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TapLeaf {
+    pub leaf_version: u8,
+    pub script: Script,
+}
+
+impl TapLeaf {
+    /// A tapscript leaf (leaf version `0xc0`).
+    pub fn new(script: Script) -> Self {
+        TapLeaf {
+            leaf_version: TAPSCRIPT_LEAF_VERSION,
+            script,
+        }
+    }
+
+    /// `H_TapLeaf(leaf_version || compact_size(script) || script)`.
+    pub fn hash(&self) -> [u8; 32] {
+        let script = self.script.serialize();
+
+        let mut data = vec![self.leaf_version];
+        encode_varint(&mut data, script.len() as u64);
+        data.extend_from_slice(&script);
+
+        tagged_hash("TapLeaf", &data)
+    }
+}
+
+/// `H_TapBranch` of two child hashes.
+///
+/// The children are sorted before hashing, so a branch commits to the set of
+/// its children rather than their order, and control blocks do not need to
+/// record which side each sibling was on.
+pub fn tap_branch_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+
+    let mut data = Vec::with_capacity(64);
+    data.extend_from_slice(left);
+    data.extend_from_slice(right);
+
+    tagged_hash("TapBranch", &data)
+}
+
+/// A binary tree of Taproot leaf scripts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TapTree {
+    Leaf(TapLeaf),
+    Branch(Box<TapTree>, Box<TapTree>),
+}
+
+impl TapTree {
+    pub fn leaf(script: Script) -> Self {
+        TapTree::Leaf(TapLeaf::new(script))
+    }
+
+    pub fn branch(left: TapTree, right: TapTree) -> Self {
+        TapTree::Branch(Box::new(left), Box::new(right))
+    }
+
+    /// Builds a Huffman tree from `(weight, leaf)` pairs, so that the leaves
+    /// expected to be spent most often get the shortest merkle paths.
+    ///
+    /// The two lightest subtrees are merged until one remains; ties are broken
+    /// by input order, which keeps the result deterministic. Returns `None`
+    /// when there are no leaves.
+    pub fn from_weighted(leaves: Vec<(u64, TapLeaf)>) -> Option<TapTree> {
+        let mut nodes: Vec<Option<TapTree>> = Vec::with_capacity(leaves.len() * 2);
+        let mut heap = BinaryHeap::new();
+
+        for (weight, leaf) in leaves {
+            heap.push(Reverse((weight, nodes.len())));
+            nodes.push(Some(TapTree::Leaf(leaf)));
+        }
+
+        while heap.len() > 1 {
+            let Reverse((left_weight, left_index)) = heap.pop().unwrap();
+            let Reverse((right_weight, right_index)) = heap.pop().unwrap();
+
+            let left = nodes[left_index].take().unwrap();
+            let right = nodes[right_index].take().unwrap();
+
+            heap.push(Reverse((
+                left_weight.saturating_add(right_weight),
+                nodes.len(),
+            )));
+            nodes.push(Some(TapTree::branch(left, right)));
+        }
+
+        let Reverse((_, root_index)) = heap.pop()?;
+
+        nodes[root_index].take()
+    }
+
+    pub fn merkle_root(&self) -> [u8; 32] {
+        match self {
+            TapTree::Leaf(leaf) => leaf.hash(),
+            TapTree::Branch(left, right) => {
+                tap_branch_hash(&left.merkle_root(), &right.merkle_root())
+            }
+        }
+    }
+
+    /// Every leaf with its merkle branch: the sibling hashes from the leaf up
+    /// to the root, in the order a control block lists them.
+    pub fn leaves_with_branches(&self) -> Vec<(TapLeaf, Vec<[u8; 32]>)> {
+        match self {
+            TapTree::Leaf(leaf) => vec![(leaf.clone(), Vec::new())],
+            TapTree::Branch(left, right) => {
+                let left_hash = left.merkle_root();
+                let right_hash = right.merkle_root();
+
+                let mut leaves = Vec::new();
+                for (leaf, mut branch) in left.leaves_with_branches() {
+                    branch.push(right_hash);
+                    leaves.push((leaf, branch));
+                }
+                for (leaf, mut branch) in right.leaves_with_branches() {
+                    branch.push(left_hash);
+                    leaves.push((leaf, branch));
+                }
+
+                leaves
+            }
+        }
+    }
+
+    /// Commits this tree to `internal_key`, producing the output key and the
+    /// control blocks for script-path spends.
+    pub fn finalize(&self, internal_key: &XOnlyPublicKey) -> Result<TaprootSpendInfo, TweakError> {
+        let merkle_root = self.merkle_root();
+        let (output_key, output_key_parity) = internal_key.tap_tweak(Some(&merkle_root))?;
+
+        Ok(TaprootSpendInfo {
+            internal_key: internal_key.clone(),
+            merkle_root: Some(merkle_root),
+            output_key,
+            output_key_parity,
+            leaves: self.leaves_with_branches(),
+        })
+    }
+}
+
+/// The data needed to spend a Taproot output by key path or script path.
+#[derive(Debug, Clone)]
+pub struct TaprootSpendInfo {
+    internal_key: XOnlyPublicKey,
+    merkle_root: Option<[u8; 32]>,
+    output_key: XOnlyPublicKey,
+    output_key_parity: Parity,
+    leaves: Vec<(TapLeaf, Vec<[u8; 32]>)>,
+}
+
+impl TaprootSpendInfo {
+    /// An output that can only be spent by key path.
+    pub fn key_path_only(internal_key: &XOnlyPublicKey) -> Result<Self, TweakError> {
+        let (output_key, output_key_parity) = internal_key.tap_tweak(None)?;
+
+        Ok(TaprootSpendInfo {
+            internal_key: internal_key.clone(),
+            merkle_root: None,
+            output_key,
+            output_key_parity,
+            leaves: Vec::new(),
+        })
+    }
+
+    pub fn internal_key(&self) -> &XOnlyPublicKey {
+        &self.internal_key
+    }
+
+    pub fn merkle_root(&self) -> Option<&[u8; 32]> {
+        self.merkle_root.as_ref()
+    }
+
+    pub fn output_key(&self) -> &XOnlyPublicKey {
+        &self.output_key
+    }
+
+    pub fn output_key_parity(&self) -> Parity {
+        self.output_key_parity
+    }
+
+    pub fn script_pub_key(&self) -> Vec<u8> {
+        self.output_key.p2tr_script_pub_key()
+    }
+
+    /// The control block for spending `leaf`, or `None` if the leaf is not in
+    /// the tree. If the same leaf appears more than once, the first
+    /// occurrence is used.
+    pub fn control_block(&self, leaf: &TapLeaf) -> Option<ControlBlock> {
+        let (_, merkle_branch) = self
+            .leaves
+            .iter()
+            .find(|(candidate, _)| candidate == leaf)?;
+
+        Some(ControlBlock {
+            leaf_version: leaf.leaf_version,
+            output_key_parity: self.output_key_parity,
+            internal_key: self.internal_key.clone(),
+            merkle_branch: merkle_branch.clone(),
+        })
+    }
+}
+
+/// The last witness element of a BIP341 script-path spend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlBlock {
+    pub leaf_version: u8,
+    pub output_key_parity: Parity,
+    pub internal_key: XOnlyPublicKey,
+    pub merkle_branch: Vec<[u8; 32]>,
+}
+
+impl ControlBlock {
+    pub fn serialize(&self) -> Vec<u8> {
+        let parity_bit = match self.output_key_parity {
+            Parity::Even => 0,
+            Parity::Odd => 1,
+        };
+
+        let mut serialized = Vec::with_capacity(
+            CONTROL_BLOCK_BASE_SIZE + CONTROL_BLOCK_NODE_SIZE * self.merkle_branch.len(),
+        );
+        serialized.push(self.leaf_version | parity_bit);
+        serialized.extend_from_slice(&self.internal_key.serialize());
+        for node in &self.merkle_branch {
+            serialized.extend_from_slice(node);
+        }
+
+        serialized
+    }
+
+    pub fn parse(serialized: &[u8]) -> Result<Self, ControlBlockError> {
+        let branch_len = serialized
+            .len()
+            .checked_sub(CONTROL_BLOCK_BASE_SIZE)
+            .ok_or(ControlBlockError::InvalidLength)?;
+
+        if branch_len % CONTROL_BLOCK_NODE_SIZE != 0
+            || branch_len / CONTROL_BLOCK_NODE_SIZE > TAPROOT_CONTROL_MAX_NODE_COUNT
+        {
+            return Err(ControlBlockError::InvalidLength);
+        }
+
+        let output_key_parity = if serialized[0] & 1 == 1 {
+            Parity::Odd
+        } else {
+            Parity::Even
+        };
+        let internal_key = XOnlyPublicKey::parse(&serialized[1..CONTROL_BLOCK_BASE_SIZE])
+            .map_err(ControlBlockError::InvalidInternalKey)?;
+        let merkle_branch = serialized[CONTROL_BLOCK_BASE_SIZE..]
+            .chunks(CONTROL_BLOCK_NODE_SIZE)
+            .map(|node| node.try_into().unwrap())
+            .collect();
+
+        Ok(ControlBlock {
+            leaf_version: serialized[0] & 0xfe,
+            output_key_parity,
+            internal_key,
+            merkle_branch,
+        })
+    }
+
+    /// Checks that `script` is committed to by `output_key` through this
+    /// control block, as BIP341 script-path validation does.
+    pub fn verify(&self, output_key: &XOnlyPublicKey, script: &Script) -> bool {
+        let leaf = TapLeaf {
+            leaf_version: self.leaf_version,
+            script: script.clone(),
+        };
+
+        let merkle_root = self
+            .merkle_branch
+            .iter()
+            .fold(leaf.hash(), |node, sibling| tap_branch_hash(&node, sibling));
+
+        match self.internal_key.tap_tweak(Some(&merkle_root)) {
+            Ok((expected_key, parity)) => {
+                &expected_key == output_key && parity == self.output_key_parity
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// The reason a byte string is not a valid control block.
+#[derive(Debug)]
+pub enum ControlBlockError {
+    /// The length is not 33 + 32m bytes with m at most 128.
+    InvalidLength,
+    InvalidInternalKey(PointError),
+}
+
+impl std::fmt::Display for ControlBlockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ControlBlockError::InvalidLength => write!(f, "control block has an invalid length"),
+            ControlBlockError::InvalidInternalKey(err) => {
+                write!(f, "control block has an invalid internal key: {err:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ControlBlockError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script::{Cmd, OpCode};
+    use crate::secp256k1::G;
+    use crate::test_utils::hex_to_bytes;
+    use num_bigint::BigUint;
+
+    // `<x-only k.G> OP_CHECKSIG`
+    fn checksig_leaf(k: u32) -> TapLeaf {
+        let key = XOnlyPublicKey::from_point(&(&*G * BigUint::from(k))).0;
+
+        TapLeaf::new(Script {
+            cmds: vec![
+                Cmd::Push(key.serialize().to_vec()),
+                Cmd::Op(OpCode::OpCheckSig),
+            ],
+        })
+    }
+
+    fn internal_key() -> XOnlyPublicKey {
+        XOnlyPublicKey::from_point(&(&*G * BigUint::from(7u32))).0
+    }
+
+    // From the BIP341 wallet test vectors (`scriptPubKey`, entry 1).
+    #[test]
+    fn test_single_leaf_bip341_vector() {
+        let internal_key = XOnlyPublicKey::parse(&hex_to_bytes(
+            "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+        ))
+        .unwrap();
+        let script = Script::parse(&hex_to_bytes(
+            "20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac",
+        ))
+        .unwrap();
+        let leaf = TapLeaf::new(script);
+
+        let spend_info = TapTree::Leaf(leaf.clone()).finalize(&internal_key).unwrap();
+
+        assert_eq!(
+            spend_info.merkle_root().unwrap().to_vec(),
+            hex_to_bytes("5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21")
+        );
+        assert_eq!(
+            spend_info.script_pub_key(),
+            hex_to_bytes("5120147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3")
+        );
+        assert_eq!(
+            spend_info.control_block(&leaf).unwrap().serialize(),
+            hex_to_bytes("c1187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27")
+        );
+    }
+
+    // Expected values computed with the `taproot_tree_helper` reference code
+    // from BIP341.
+    #[test]
+    fn test_explicit_tree() {
+        let (a, b, c) = (checksig_leaf(1), checksig_leaf(2), checksig_leaf(3));
+        let tree = TapTree::branch(
+            TapTree::branch(TapTree::Leaf(a.clone()), TapTree::Leaf(b.clone())),
+            TapTree::Leaf(c.clone()),
+        );
+
+        let spend_info = tree.finalize(&internal_key()).unwrap();
+
+        assert_eq!(
+            spend_info.merkle_root().unwrap().to_vec(),
+            hex_to_bytes("b2e5059ac5eb030f7ccf94802228d5809b7abe03ab685f1789efd55ed2b47e35")
+        );
+        assert_eq!(
+            spend_info.output_key().serialize().to_vec(),
+            hex_to_bytes("d5ea416561dab05b8e9ab3544e0eeb5a75308c503d9dfc22ceee94d95b16ea68")
+        );
+        assert_eq!(spend_info.output_key_parity(), Parity::Odd);
+
+        let expected = [
+            (
+                &a,
+                "c15cbdf0646e5db4eaa398f365f2ea7a0e3d419b7e0330e39ce92bddedcac4f9bc\
+                 ab11b8ce98a88b0dccf33a8144f90266dd8228b9fec6fa0cc0f7d4c0a28b8977\
+                 a5f4dd9bd9e8664ec5d1b97819e523b297ea9c7f8daa0b2e96e6460060333e5f",
+            ),
+            (
+                &b,
+                "c15cbdf0646e5db4eaa398f365f2ea7a0e3d419b7e0330e39ce92bddedcac4f9bc\
+                 763e9da064b9dc0471fb0f3c8fa2c84b4b84d2ca992497c12d2274386795aa8e\
+                 a5f4dd9bd9e8664ec5d1b97819e523b297ea9c7f8daa0b2e96e6460060333e5f",
+            ),
+            (
+                &c,
+                "c15cbdf0646e5db4eaa398f365f2ea7a0e3d419b7e0330e39ce92bddedcac4f9bc\
+                 4ca63a41f6849e2043baedd8f34ad532e6f4e5f458f26c4ac3e166055bd5ac2f",
+            ),
+        ];
+
+        for (leaf, control_block_hex) in expected {
+            let control_block = spend_info.control_block(leaf).unwrap();
+            assert_eq!(control_block.serialize(), hex_to_bytes(control_block_hex));
+            assert!(control_block.verify(spend_info.output_key(), &leaf.script));
+
+            let parsed = ControlBlock::parse(&control_block.serialize()).unwrap();
+            assert_eq!(parsed, control_block);
+        }
+
+        // A leaf that is not in the tree has no control block, and a real
+        // control block does not vouch for a different script.
+        let outsider = checksig_leaf(4);
+        assert!(spend_info.control_block(&outsider).is_none());
+        assert!(
+            !spend_info
+                .control_block(&a)
+                .unwrap()
+                .verify(spend_info.output_key(), &outsider.script)
+        );
+    }
+
+    #[test]
+    fn test_weighted_tree() {
+        assert!(TapTree::from_weighted(Vec::new()).is_none());
+
+        // The heaviest leaf ends up next to the root; the two light ones are
+        // paired first.
+        let (a, b, c) = (checksig_leaf(1), checksig_leaf(2), checksig_leaf(3));
+        let tree =
+            TapTree::from_weighted(vec![(1, a.clone()), (1, b.clone()), (2, c.clone())]).unwrap();
+
+        let explicit = TapTree::branch(
+            TapTree::branch(TapTree::Leaf(a.clone()), TapTree::Leaf(b.clone())),
+            TapTree::Leaf(c.clone()),
+        );
+        assert_eq!(tree.merkle_root(), explicit.merkle_root());
+
+        let depths: Vec<usize> = tree
+            .leaves_with_branches()
+            .iter()
+            .map(|(_, branch)| branch.len())
+            .collect();
+        assert_eq!(depths.iter().max(), Some(&2));
+        assert_eq!(depths.iter().min(), Some(&1));
+
+        let heavy = TapTree::from_weighted(vec![
+            (1, checksig_leaf(1)),
+            (1, checksig_leaf(2)),
+            (1, checksig_leaf(3)),
+            (100, checksig_leaf(4)),
+        ])
+        .unwrap();
+        let spend_info = heavy.finalize(&internal_key()).unwrap();
+        let control_block = spend_info.control_block(&checksig_leaf(4)).unwrap();
+        assert_eq!(control_block.merkle_branch.len(), 1);
+
+        for (leaf, _) in heavy.leaves_with_branches() {
+            let control_block = spend_info.control_block(&leaf).unwrap();
+            assert!(control_block.verify(spend_info.output_key(), &leaf.script));
+        }
+    }
+
+    #[test]
+    fn test_key_path_only() {
+        let spend_info = TaprootSpendInfo::key_path_only(&internal_key()).unwrap();
+        let (expected, _) = internal_key().tap_tweak(None).unwrap();
+
+        assert!(spend_info.merkle_root().is_none());
+        assert_eq!(spend_info.output_key(), &expected);
+        assert!(spend_info.control_block(&checksig_leaf(1)).is_none());
+    }
+
+    #[test]
+    fn test_control_block_parse_errors() {
+        assert!(matches!(
+            ControlBlock::parse(&[0xc0; 32]),
+            Err(ControlBlockError::InvalidLength)
+        ));
+
+        let mut serialized = vec![0xc0];
+        serialized.extend_from_slice(&internal_key().serialize());
+        serialized.push(0x00);
+        assert!(matches!(
+            ControlBlock::parse(&serialized),
+            Err(ControlBlockError::InvalidLength)
+        ));
+
+        // An x coordinate at or above the field prime is not a valid key.
+        let mut serialized = vec![0xc0];
+        serialized.extend_from_slice(&[0xff; 32]);
+        assert!(matches!(
+            ControlBlock::parse(&serialized),
+            Err(ControlBlockError::InvalidInternalKey(_))
+        ));
+    }
+}
@@ -0,0 +1,18 @@
+//! Helpers shared by the test modules.
+
+/// Decodes a hex string, or returns `None` if it is not valid hex.
+pub(crate) fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Decodes a hex string that is known to be valid, as in a test vector.
+pub(crate) fn hex_to_bytes(hex: &str) -> Vec<u8> {
+    parse_hex(hex).unwrap()
+}
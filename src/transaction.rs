@@ -1,7 +1,11 @@
 use std::collections::HashMap;
 
-use crate::{script::Script, varint::encode_varint};
+use crate::{
+    script::{Cmd, Script, ScriptFlags},
+    varint::encode_varint,
+};
 use num_bigint::BigUint;
+use ripemd::Ripemd160;
 use sha2::{Digest, Sha256};
 
 pub type UtxoSet = HashMap<([u8; 32], u32), TxOut>;
@@ -48,7 +52,7 @@ impl Transaction for Tx {
         serialized.extend_from_slice(&self.locktime.to_le_bytes());
     }
 
-    fn deserialize(serialized: &[u8]) {
+    fn deserialize(_serialized: &[u8]) {
         todo!()
     }
 }
@@ -74,7 +78,51 @@ impl Tx {
         BigUint::from_bytes_be(&hash2)
     }
 
+    /// The BIP143 signature hash for spending a version 0 witness output
+    /// worth `amount` with SIGHASH_ALL.
+    pub fn sig_hash_segwit_v0(
+        &self,
+        input_index: usize,
+        script_code: &[u8],
+        amount: u64,
+    ) -> BigUint {
+        let mut prevouts = Vec::new();
+        let mut sequences = Vec::new();
+        for tx_in in &self.tx_ins {
+            prevouts.extend_from_slice(&tx_in.prev_tx_hash);
+            prevouts.extend_from_slice(&tx_in.prev_index.to_le_bytes());
+            sequences.extend_from_slice(&tx_in.sequence.to_le_bytes());
+        }
+
+        let mut outputs = Vec::new();
+        for tx_out in &self.tx_outs {
+            tx_out.serialize(&mut outputs);
+        }
+
+        let tx_in = &self.tx_ins[input_index];
+
+        let mut serialized = Vec::new();
+        serialized.extend_from_slice(&self.version.to_le_bytes());
+        serialized.extend_from_slice(&hash256(&prevouts));
+        serialized.extend_from_slice(&hash256(&sequences));
+        serialized.extend_from_slice(&tx_in.prev_tx_hash);
+        serialized.extend_from_slice(&tx_in.prev_index.to_le_bytes());
+        encode_varint(&mut serialized, script_code.len() as u64);
+        serialized.extend_from_slice(script_code);
+        serialized.extend_from_slice(&amount.to_le_bytes());
+        serialized.extend_from_slice(&tx_in.sequence.to_le_bytes());
+        serialized.extend_from_slice(&hash256(&outputs));
+        serialized.extend_from_slice(&self.locktime.to_le_bytes());
+        serialized.extend_from_slice(&1_u32.to_le_bytes());
+
+        BigUint::from_bytes_be(&hash256(&serialized))
+    }
+
     pub fn verify(&self, utxos: &UtxoSet) -> bool {
+        self.verify_with_flags(utxos, ScriptFlags::STANDARD)
+    }
+
+    pub fn verify_with_flags(&self, utxos: &UtxoSet, flags: ScriptFlags) -> bool {
         let mut prev_utxos = Vec::new();
 
         for tx_in in self.tx_ins.iter() {
@@ -95,27 +143,100 @@ impl Tx {
         for (index, tx_in) in self.tx_ins.iter().enumerate() {
             let prev_script_pub_key = &prev_utxos[index].script_pub_key;
 
-            let script_concat: Vec<u8> = tx_in
-                .script_sig
-                .iter()
-                .chain(prev_script_pub_key)
-                .cloned()
-                .collect();
+            if let Some(pub_key_hash) = p2wpkh_program(prev_script_pub_key) {
+                if !self.verify_p2wpkh_input(index, pub_key_hash, prev_utxos[index].amount, flags) {
+                    return false;
+                }
+                continue;
+            }
 
-            let parsed_script = match Script::parse(&script_concat) {
-                Ok(script) => script,
-                Err(_) => return false,
+            // Only witness outputs may be spent with a witness.
+            if !tx_in.witness.is_empty() {
+                return false;
+            }
+
+            // The two halves are parsed separately, as Bitcoin Core does, so a
+            // truncated push at the end of the scriptSig cannot swallow bytes
+            // of the scriptPubKey.
+            let (Ok(script_sig), Ok(script_pub_key)) = (
+                Script::parse(&tx_in.script_sig),
+                Script::parse(prev_script_pub_key),
+            ) else {
+                return false;
             };
+            let parsed_script = script_sig.combine(&script_pub_key);
 
             let z = self.sig_hash(index, prev_script_pub_key);
 
-            if !parsed_script.evaluate(&z) {
+            if parsed_script.evaluate_with_flags(&z, flags).is_err() {
                 return false;
             }
         }
 
         true
     }
+
+    /// Checks a native P2WPKH spend: the scriptSig must be empty and the
+    /// witness must be `<sig> <pubkey>`, which is then run against the
+    /// equivalent P2PKH script with the BIP143 signature hash.
+    fn verify_p2wpkh_input(
+        &self,
+        index: usize,
+        pub_key_hash: &[u8],
+        amount: u64,
+        flags: ScriptFlags,
+    ) -> bool {
+        let tx_in = &self.tx_ins[index];
+
+        if !tx_in.script_sig.is_empty() || tx_in.witness.len() != 2 {
+            return false;
+        }
+
+        let script_code = p2pkh_script(pub_key_hash);
+        let witness_script = Script {
+            cmds: tx_in.witness.iter().cloned().map(Cmd::Push).collect(),
+        };
+        let parsed_script = witness_script.combine(&Script::parse(&script_code).unwrap()); // a P2PKH script always parses
+
+        let z = self.sig_hash_segwit_v0(index, &script_code, amount);
+
+        parsed_script.evaluate_with_flags(&z, flags).is_ok()
+    }
+}
+
+/// Returns the 20-byte key hash if `script_pub_key` is a native P2WPKH output
+/// (`OP_0 <20 bytes>`).
+pub(crate) fn p2wpkh_program(script_pub_key: &[u8]) -> Option<&[u8]> {
+    match script_pub_key {
+        [0x00, 0x14, program @ ..] if program.len() == 20 => Some(program),
+        _ => None,
+    }
+}
+
+/// `OP_DUP OP_HASH160 <pub_key_hash> OP_EQUALVERIFY OP_CHECKSIG`
+pub(crate) fn p2pkh_script(pub_key_hash: &[u8]) -> Vec<u8> {
+    let mut script = vec![0x76, 0xa9, pub_key_hash.len() as u8];
+    script.extend_from_slice(pub_key_hash);
+    script.extend_from_slice(&[0x88, 0xac]);
+
+    script
+}
+
+/// `OP_0 <pub_key_hash>`
+pub(crate) fn p2wpkh_script(pub_key_hash: &[u8]) -> Vec<u8> {
+    let mut script = vec![0x00, 0x14];
+    script.extend_from_slice(pub_key_hash);
+
+    script
+}
+
+/// RIPEMD160(SHA256(data)), the hash behind P2PKH and P2WPKH addresses.
+pub(crate) fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
+
+fn hash256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(data)).into()
 }
 
 #[derive(Clone, Debug)]
@@ -124,6 +245,9 @@ pub struct TxIn {
     pub prev_index: u32,
     pub script_sig: Vec<u8>,
     pub sequence: u32,
+    /// The witness stack (BIP141). It is not part of `serialize`, which
+    /// produces the serialization the txid commits to.
+    pub witness: Vec<Vec<u8>>,
 }
 
 impl TxIn {
@@ -166,6 +290,7 @@ mod tests {
                 prev_index: 0,
                 script_sig: vec![0x01, 0x02],
                 sequence: 0xffffffff,
+                witness: vec![],
             }],
             tx_outs: vec![TxOut {
                 amount: 5000,
@@ -243,6 +368,7 @@ mod tests {
                 prev_index: 0,
                 script_sig: vec![],
                 sequence: 0xffffffff,
+                witness: vec![],
             }],
             tx_outs: vec![TxOut {
                 amount: 50,
@@ -300,6 +426,7 @@ mod tests {
                 // Raw script for: Push([1])
                 script_sig: vec![0x01, 0x01],
                 sequence: 0xffffffff,
+                witness: vec![],
             }],
             tx_outs: vec![TxOut {
                 amount: 900,
@@ -322,6 +449,7 @@ mod tests {
                 prev_index: 0,
                 script_sig: vec![0x01, 0x01],
                 sequence: 0xffffffff,
+                witness: vec![],
             }],
             tx_outs: vec![TxOut {
                 amount: 1100, // More than the 1000 available in the input
@@ -345,6 +473,7 @@ mod tests {
                 prev_index: 0,
                 script_sig: vec![0x01, 0x01],
                 sequence: 0xffffffff,
+                witness: vec![],
             }],
             tx_outs: vec![TxOut {
                 amount: 900,
@@ -369,6 +498,7 @@ mod tests {
                 // Raw script for: Push(2), Push(3), OpEqual
                 script_sig: vec![0x01, 0x02, 0x01, 0x03, 0x87],
                 sequence: 0xffffffff,
+                witness: vec![],
             }],
             tx_outs: vec![TxOut {
                 amount: 900,
@@ -379,4 +509,39 @@ mod tests {
 
         assert!(!spending_tx.verify(&utxos));
     }
+
+    #[test]
+    fn test_verify_rejects_witness_on_legacy_input() {
+        let (prev_tx, utxos) = create_test_utxo_set();
+
+        let spending_tx = Tx {
+            version: 1,
+            tx_ins: vec![TxIn {
+                prev_tx_hash: prev_tx.hash(),
+                prev_index: 0,
+                script_sig: vec![0x01, 0x01],
+                sequence: 0xffffffff,
+                witness: vec![vec![0x01]],
+            }],
+            tx_outs: vec![TxOut {
+                amount: 900,
+                script_pub_key: vec![],
+            }],
+            locktime: 0,
+        };
+
+        assert!(!spending_tx.verify(&utxos));
+    }
+
+    #[test]
+    fn test_p2wpkh_program() {
+        let mut script_pub_key = vec![0x00, 0x14];
+        script_pub_key.extend_from_slice(&[7u8; 20]);
+        assert_eq!(p2wpkh_program(&script_pub_key), Some(&[7u8; 20][..]));
+
+        // A 32-byte program is P2WSH, not P2WPKH.
+        let mut p2wsh = vec![0x00, 0x20];
+        p2wsh.extend_from_slice(&[7u8; 32]);
+        assert_eq!(p2wpkh_program(&p2wsh), None);
+    }
 }